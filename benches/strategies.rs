@@ -0,0 +1,83 @@
+//! Compare the cost of the strategies on the consumed and the leaked
+//! path.
+//!
+//! Run with `cargo bench --features log`. The consumed path of every
+//! strategy should be as fast as the baseline, a type without a `Drop`
+//! implementation, because consuming a value never calls `drop`. For
+//! the link strategy the `Drop` implementation is not even generated
+//! into the binary, so `link/consumed` and `baseline/consumed` compile
+//! to the same instructions. `tests/link_strategy.rs` checks that by
+//! comparing the size of both functions in an optimized build.
+
+#[macro_use]
+extern crate criterion;
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+use std::panic;
+
+use criterion::{black_box, Criterion};
+
+macro_rules! resource {
+    ($T:ident) => {
+        struct $T(u64);
+
+        impl $T {
+            #[inline]
+            fn close(self) -> u64 {
+                let this = ManuallyDrop::new(self);
+                this.0
+            }
+        }
+    };
+}
+
+resource!(Baseline);
+resource!(Link);
+resource!(Abort);
+resource!(Panic);
+#[cfg(feature = "log")]
+resource!(Log);
+
+prevent_drop_link!(Link, prevent_drop_bench_Link);
+prevent_drop_abort!(Abort, prevent_drop_bench_Abort);
+prevent_drop_panic!(Panic, prevent_drop_bench_Panic);
+#[cfg(feature = "log")]
+prevent_drop_log!(Log, prevent_drop_bench_Log);
+
+fn consumed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("consumed");
+    group.bench_function("baseline", |b| b.iter(|| Baseline(black_box(1)).close()));
+    group.bench_function("link", |b| b.iter(|| Link(black_box(1)).close()));
+    group.bench_function("abort", |b| b.iter(|| Abort(black_box(1)).close()));
+    group.bench_function("panic", |b| b.iter(|| Panic(black_box(1)).close()));
+    #[cfg(feature = "log")]
+    group.bench_function("log", |b| b.iter(|| Log(black_box(1)).close()));
+    group.finish();
+}
+
+fn leaked(c: &mut Criterion) {
+    let mut group = c.benchmark_group("leaked");
+    group.bench_function("baseline", |b| {
+        b.iter(|| {
+            let _ = Baseline(black_box(1));
+        })
+    });
+
+    // The abort strategy terminates the process and the link strategy
+    // does not compile a leak, so neither is measured here.
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    group.bench_function("panic", |b| {
+        b.iter(|| panic::catch_unwind(|| drop(Panic(black_box(1)))).is_err())
+    });
+    panic::set_hook(hook);
+
+    #[cfg(feature = "log")]
+    group.bench_function("log", |b| b.iter(|| drop(Log(black_box(1)))));
+    group.finish();
+}
+
+criterion_group!(benches, consumed, leaked);
+criterion_main!(benches);
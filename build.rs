@@ -1,17 +1,108 @@
 use std::env;
 
 fn main() {
-    println!("rerun-if-changed=\"Cargo.toml\"");
-    println!("rerun-if-env-changed=\"OPT_LEVEL\"");
+    for directive in directives(|name| env::var(name).ok()) {
+        println!("{}", directive);
+    }
+}
 
-    let opt_level = env::var("OPT_LEVEL").unwrap();
+/// Every directive the build script prints, reading the environment
+/// through `var`.
+///
+/// Cargo runs this script once per profile that `prevent_drop` is built
+/// with and sets `OPT_LEVEL` to the opt-level of that build, not of the
+/// host. When `prevent_drop` is a dependency of a build script or a
+/// proc macro it is built for the host with the `build-override`
+/// profile, and `OPT_LEVEL` is that profile's opt-level, 0 by default.
+/// `PROFILE` only tells `debug` from `release` and ignores overrides, so
+/// it is not consulted.
+///
+/// The opt-level itself is passed on as the `prevent_drop_opt_level`
+/// cfg, next to `opt_level_gt_0` which the strategy selection keys off.
+///
+/// `PREVENT_DROP_FORCE_STRATEGY` overrides both the features and the
+/// opt-level. It is passed on as the `prevent_drop_force` cfg.
+///
+/// Panics if `PREVENT_DROP_FORCE_STRATEGY` is not a known strategy.
+pub(crate) fn directives<F: Fn(&str) -> Option<String>>(var: F) -> Vec<String> {
+    let mut directives = vec![
+        "cargo:rerun-if-changed=build.rs".to_string(),
+        "cargo:rerun-if-env-changed=PREVENT_DROP_FORCE_STRATEGY".to_string(),
+        "cargo:rustc-check-cfg=cfg(opt_level_gt_0)".to_string(),
+        "cargo:rustc-check-cfg=cfg(prevent_drop_opt_level, values(\"0\", \"1\", \"2\", \"3\", \"s\", \"z\"))"
+            .to_string(),
+        "cargo:rustc-check-cfg=cfg(prevent_drop_force, values(none(), \"link\", \"abort\", \"panic\"))"
+            .to_string(),
+    ];
 
-    match opt_level.parse::<u32>() {
-        Ok(opt_level) => {
-            if opt_level > 0 {
-                println!("cargo:rustc-cfg=opt_level_gt_0");
+    let forced = var("PREVENT_DROP_FORCE_STRATEGY").filter(|strategy| !strategy.is_empty());
+    if let Some(strategy) = forced {
+        match force_strategy(&strategy) {
+            Some(strategy) => {
+                directives.push("cargo:rustc-cfg=prevent_drop_force".to_string());
+                directives.push(format!("cargo:rustc-cfg=prevent_drop_force=\"{}\"", strategy));
             }
+            None => panic!(
+                "PREVENT_DROP_FORCE_STRATEGY must be one of `link`, `abort` or `panic`, not `{}`",
+                strategy
+            ),
+        }
+    }
+
+    // Some tools run the build script without setting `OPT_LEVEL`. Treat
+    // that like opt-level 0 instead of failing the build.
+    let opt_level = var("OPT_LEVEL");
+
+    if let Some(cfg) = rustc_cfg(opt_level.as_deref()) {
+        directives.push(cfg.to_string());
+    }
+    if let Some(cfg) = opt_level_cfg(opt_level.as_deref()) {
+        directives.push(cfg);
+    }
+
+    directives
+}
+
+/// The strategy named by `PREVENT_DROP_FORCE_STRATEGY`, if it is one
+/// that can be forced.
+pub(crate) fn force_strategy(strategy: &str) -> Option<&'static str> {
+    match strategy.trim() {
+        "link" => Some("link"),
+        "abort" => Some("abort"),
+        "panic" => Some("panic"),
+        _ => None,
+    }
+}
+
+/// The cfg directive to emit for the given `OPT_LEVEL`, if any.
+pub(crate) fn rustc_cfg(opt_level: Option<&str>) -> Option<&'static str> {
+    if opt_level_gt_0(opt_level) {
+        Some("cargo:rustc-cfg=opt_level_gt_0")
+    } else {
+        None
+    }
+}
+
+/// The `prevent_drop_opt_level` cfg directive for the given
+/// `OPT_LEVEL`, if it is one cargo knows.
+///
+/// It carries the opt-level the strategy was chosen for, e.g.
+/// `prevent_drop_opt_level="2"`.
+pub(crate) fn opt_level_cfg(opt_level: Option<&str>) -> Option<String> {
+    match opt_level {
+        Some(opt_level @ ("0" | "1" | "2" | "3" | "s" | "z")) => {
+            Some(format!("cargo:rustc-cfg=prevent_drop_opt_level=\"{}\"", opt_level))
         }
-        _ => {}
+        _ => None,
+    }
+}
+
+/// Whether the `OPT_LEVEL` cargo passes to build scripts enables
+/// optimizations. The size levels `s` and `z` optimize as well.
+pub(crate) fn opt_level_gt_0(opt_level: Option<&str>) -> bool {
+    match opt_level {
+        Some("s") | Some("z") => true,
+        Some(opt_level) => opt_level.parse::<u32>().is_ok_and(|opt_level| opt_level > 0),
+        None => false,
     }
 }
@@ -0,0 +1,355 @@
+//! Derive macro for the `prevent_drop` crate.
+//!
+//! Use it through `prevent_drop` with the `derive` feature enabled
+//! rather than depending on this crate directly.
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate prevent_drop;
+//!
+//! #[derive(PreventDrop)]
+//! struct Resource;
+//!
+//! #[derive(PreventDrop)]
+//! #[prevent_drop(strategy = "panic", message = "Leaked a Connection.")]
+//! struct Connection;
+//! ```
+//!
+//! The `#[prevent_drop]` attribute does the same and takes its options
+//! directly, as in `#[prevent_drop(strategy = "panic")]`.
+//!
+//! The derive writes a `prevent_drop!` invocation for you. The label is
+//! generated from the type name and a hash of the crate name, the
+//! source location of the derive and the type definition, so two types
+//! with the same name in different modules or functions do not collide.
+//!
+//! Labels start with `PREVENT_DROP_you_forgot_to_consume_` followed by
+//! the type name, so the undefined symbol in the linker error of the
+//! link strategy tells you which type was dropped.
+
+#![doc(html_root_url = "https://docs.rs/prevent_drop_derive")]
+#![deny(missing_docs)]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::hash::{Hash, Hasher};
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use syn::meta::ParseNestedMeta;
+use syn::parse::Parser;
+use syn::{DeriveInput, GenericParam, LitStr};
+
+/// Implement Drop for a type so that instances of it cannot be dropped.
+///
+/// Without attributes this uses the strategy `prevent_drop!` selects
+/// based on the enabled features. Use `#[prevent_drop(strategy =
+/// "...")]` with one of `link`, `abort`, `panic` or `log` to pick a
+/// strategy for this type, and `message = "..."` to customize the
+/// message of strategies that report one.
+#[proc_macro_derive(PreventDrop, attributes(prevent_drop))]
+pub fn derive_prevent_drop(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => compile_error(error).into(),
+    }
+}
+
+/// Attach a drop guard to a struct, enum or union definition.
+///
+/// `#[prevent_drop]` is equivalent to `#[derive(PreventDrop)]` but keeps
+/// the guard next to the type. It accepts the same options directly,
+/// e.g. `#[prevent_drop(strategy = "panic", message = "...")]`.
+///
+/// Unlike the derive, the attribute also marks the type `#[must_use]`
+/// unless it already is, so ignoring a returned value is reported by
+/// the compiler in every build.
+///
+/// It is an ordinary attribute, so `cfg_attr` can install the guard only
+/// under a feature of your own crate: `#[cfg_attr(feature = "strict",
+/// prevent_drop::attr::prevent_drop(strategy = "panic"))]`. Without the
+/// feature the type is left untouched.
+#[proc_macro_attribute]
+pub fn prevent_drop(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut options = Options::default();
+    let parser = syn::meta::parser(|meta| options.parse_meta(meta));
+    if let Err(error) = parser.parse(args) {
+        let error = compile_error(error);
+        let input = TokenStream2::from(input);
+        return quote!(#error #input).into();
+    }
+    let mut input = match syn::parse::<DeriveInput>(input.clone()) {
+        Ok(input) => input,
+        Err(_) => {
+            let item = TokenStream2::from(input);
+            let error = syn::Error::new_spanned(
+                &item,
+                "#[prevent_drop] can only be applied to a struct, enum or union",
+            );
+            let error = compile_error(error);
+            return quote!(#error #item).into();
+        }
+    };
+    let guard = match options.validate().and_then(|()| expand_with(&input, options)) {
+        Ok(tokens) => tokens,
+        Err(error) => compile_error(error),
+    };
+    if !input.attrs.iter().any(|attr| attr.path().is_ident("must_use")) {
+        input.attrs.push(syn::parse_quote!(
+            #[must_use = "values of this type must be consumed explicitly instead of being dropped"]
+        ));
+    }
+    quote!(#input #guard).into()
+}
+
+#[derive(Clone, Copy)]
+enum Strategy {
+    Link,
+    Abort,
+    Panic,
+    Log,
+}
+
+impl Strategy {
+    fn from_name(name: &LitStr) -> syn::Result<Strategy> {
+        match name.value().as_str() {
+            "link" => Ok(Strategy::Link),
+            "abort" => Ok(Strategy::Abort),
+            "panic" => Ok(Strategy::Panic),
+            "log" => Ok(Strategy::Log),
+            other => Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "unknown strategy `{}`, expected one of link/abort/panic/log{}",
+                    other,
+                    suggestion(other, &["link", "abort", "panic", "log"])
+                ),
+            )),
+        }
+    }
+
+    fn supports_message(self) -> bool {
+        match self {
+            Strategy::Link => false,
+            Strategy::Abort | Strategy::Panic | Strategy::Log => true,
+        }
+    }
+
+    fn name(self) -> Ident {
+        let name = match self {
+            Strategy::Link => "link",
+            Strategy::Abort => "abort",
+            Strategy::Panic => "panic",
+            Strategy::Log => "log",
+        };
+        Ident::new(name, Span::call_site())
+    }
+
+    fn macro_name(self) -> Ident {
+        let name = match self {
+            Strategy::Link => "prevent_drop_link",
+            Strategy::Abort => "prevent_drop_abort",
+            Strategy::Panic => "prevent_drop_panic",
+            Strategy::Log => "prevent_drop_log",
+        };
+        Ident::new(name, Span::call_site())
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    strategy: Option<Strategy>,
+    message: Option<LitStr>,
+}
+
+impl Options {
+    fn from_input(input: &DeriveInput) -> syn::Result<Options> {
+        let mut options = Options::default();
+        for attr in &input.attrs {
+            if !attr.path().is_ident("prevent_drop") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| options.parse_meta(meta))?;
+        }
+        options.validate()?;
+        Ok(options)
+    }
+
+    fn parse_meta(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("strategy") {
+            let name = parse_string(&meta, "strategy", "panic")?;
+            self.strategy = Some(Strategy::from_name(&name)?);
+            Ok(())
+        } else if meta.path.is_ident("message") {
+            self.message = Some(parse_string(&meta, "message", "...")?);
+            Ok(())
+        } else {
+            let path = meta.path.clone();
+            let name = quote!(#path).to_string().replace(' ', "");
+            Err(syn::Error::new_spanned(
+                path,
+                format!(
+                    "unknown prevent_drop option `{}`, expected `strategy` or `message`{}",
+                    name,
+                    suggestion(&name, &["strategy", "message"])
+                ),
+            ))
+        }
+    }
+
+    fn validate(&self) -> syn::Result<()> {
+        if let (Some(strategy), Some(message)) = (self.strategy, self.message.as_ref()) {
+            if !strategy.supports_message() {
+                return Err(syn::Error::new(
+                    message.span(),
+                    "the `link` strategy does not support a message",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turn an error into `compile_error!` invocations spanned at the
+/// offending tokens.
+///
+/// `syn::Error::to_compile_error` expands to `::core::compile_error!`,
+/// which does not resolve in edition 2015 crates without `extern crate
+/// core`, so the builtin macro is called by its bare name instead.
+fn compile_error(error: syn::Error) -> TokenStream2 {
+    error
+        .into_iter()
+        .map(|error| {
+            let message = error.to_string();
+            quote_spanned!(error.span()=> compile_error!(#message);)
+        })
+        .collect()
+}
+
+/// Parse the string literal value of an option, pointing at the value
+/// with an example if it is something else.
+fn parse_string(meta: &ParseNestedMeta, option: &str, example: &str) -> syn::Result<LitStr> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        return value.parse();
+    }
+    let expr: syn::Expr = value.parse()?;
+    Err(syn::Error::new_spanned(
+        expr,
+        format!("expected a string literal, as in `{} = \"{}\"`", option, example),
+    ))
+}
+
+/// A `, did you mean ...?` hint if one of the candidates is close to
+/// what was written.
+fn suggestion(written: &str, candidates: &[&str]) -> String {
+    candidates
+        .iter()
+        .map(|candidate| (distance(written, candidate), candidate))
+        .filter(|&(distance, _)| distance <= 2)
+        .min()
+        .map(|(_, candidate)| format!(", did you mean `{}`?", candidate))
+        .unwrap_or_default()
+}
+
+/// The Levenshtein distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitute = previous[j] + if a == b { 0 } else { 1 };
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let options = Options::from_input(input)?;
+    expand_with(input, options)
+}
+
+fn expand_with(input: &DeriveInput, options: Options) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let label = label(input);
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let params = input.generics.params.iter().map(|param| {
+        let mut param = param.clone();
+        match param {
+            GenericParam::Type(ref mut param) => {
+                param.eq_token = None;
+                param.default = None;
+            }
+            GenericParam::Const(ref mut param) => {
+                param.eq_token = None;
+                param.default = None;
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+        param
+    });
+    let message = options.message.map(|message| quote!(, #message));
+
+    // Only `prevent_drop!` itself accepts a where clause.
+    if let Some(where_clause) = input.generics.where_clause.as_ref() {
+        let predicates = &where_clause.predicates;
+        let strategy = options.strategy.map(|strategy| {
+            let name = strategy.name();
+            quote!(, strategy = #name)
+        });
+        return Ok(quote! {
+            ::prevent_drop::prevent_drop!(
+                #name #ty_generics,
+                #label #strategy,
+                generics = [#(#params),*],
+                where = [#predicates]
+                #message
+            );
+        });
+    }
+
+    let mac = match options.strategy {
+        Some(strategy) => strategy.macro_name(),
+        None => Ident::new("prevent_drop", Span::call_site()),
+    };
+
+    Ok(quote! {
+        ::prevent_drop::#mac!(#name #ty_generics, #label, generics = [#(#params),*] #message);
+    })
+}
+
+/// Derive a label that is unique within the final binary.
+///
+/// The label doubles as the name of the missing symbol in the linker
+/// error of the link strategy, so it is phrased to read as the error.
+///
+/// Proc macros cannot observe the module path, so the crate name and the
+/// source location of the derive stand in for it. The type definition is
+/// hashed as well to separate types generated by the same macro.
+fn label(input: &DeriveInput) -> Ident {
+    let call_site = proc_macro::Span::call_site();
+    let mut hasher = DefaultHasher::new();
+    env::var("CARGO_CRATE_NAME").unwrap_or_default().hash(&mut hasher);
+    call_site.file().hash(&mut hasher);
+    call_site.line().hash(&mut hasher);
+    call_site.column().hash(&mut hasher);
+    quote!(#input).to_string().hash(&mut hasher);
+    Ident::new(
+        &format!(
+            "PREVENT_DROP_you_forgot_to_consume_{}_{:016x}",
+            input.ident,
+            hasher.finish()
+        ),
+        Span::call_site(),
+    )
+}
@@ -0,0 +1,119 @@
+//! Scoped suppression of the run-time strategies behind `allow_drop`.
+
+#[cfg(any(feature = "std", test))]
+use std::cell::Cell;
+
+#[cfg(any(feature = "std", test))]
+thread_local! {
+    static ALLOWED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Run `f` with the drop guards of the current thread switched off.
+///
+/// Dropping a guarded value inside `f` does nothing instead of aborting,
+/// panicking, logging or counting. This is meant for test setup and
+/// teardown where restructuring the code around `ManuallyDrop` is not
+/// worth it. Only the run-time strategies can be switched off. The link
+/// and const strategies reject drops at compile time and are unaffected,
+/// and so is the raw abort strategy, which cannot read the switch in a
+/// signal handler.
+/// Other threads keep their guards, and the guards are switched back on
+/// when `f` returns or panics.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     prevent_drop::allow_drop(|| drop(Resource));
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+pub fn allow_drop<F: FnOnce() -> R, R>(f: F) -> R {
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            ALLOWED.with(|allowed| allowed.set(allowed.get() - 1));
+        }
+    }
+
+    ALLOWED.with(|allowed| allowed.set(allowed.get() + 1));
+    let _reset = Reset;
+    f()
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn drop_allowed() -> bool {
+    #[cfg(any(feature = "std", test))]
+    return ALLOWED
+        .try_with(|allowed| allowed.get() > 0)
+        .unwrap_or(false);
+
+    #[cfg(not(any(feature = "std", test)))]
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::panic;
+
+    use super::{allow_drop, drop_allowed};
+
+    struct Resource;
+
+    prevent_drop_panic!(Resource, forget_to_explicitly_drop_an_instance_of_allow_Resource);
+
+    thread_local! {
+        static CALLS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn count_call() {
+        CALLS.with(|calls| calls.set(calls.get() + 1));
+    }
+
+    struct Called;
+
+    prevent_drop_callback!(Called, forget_to_explicitly_drop_an_instance_of_allow_Called, count_call);
+
+    #[test]
+    fn allow_drop_suppresses_the_guard() {
+        allow_drop(|| drop(Resource));
+        allow_drop(|| drop(Called));
+        assert_eq!(CALLS.with(Cell::get), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Resource.")]
+    fn allow_drop_reenables_the_guard_afterwards() {
+        allow_drop(|| {});
+        drop(Resource);
+    }
+
+    #[test]
+    fn allow_drop_nests() {
+        allow_drop(|| {
+            allow_drop(|| {});
+            assert!(drop_allowed());
+        });
+        assert!(!drop_allowed());
+    }
+
+    #[test]
+    fn allow_drop_reenables_the_guard_after_a_panic() {
+        let result = panic::catch_unwind(|| allow_drop(|| panic!("teardown failed")));
+        assert!(result.is_err());
+        assert!(!drop_allowed());
+    }
+
+    #[test]
+    fn allow_drop_returns_the_result() {
+        assert_eq!(allow_drop(|| 3), 3);
+    }
+}
@@ -0,0 +1,46 @@
+//! Backtrace reporting for the run-time strategies.
+//!
+//! Everything in here compiles to nothing unless the `backtrace` feature
+//! is enabled. Even then a backtrace is only captured when
+//! `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` asks for it.
+
+#[cfg(feature = "backtrace")]
+use std::backtrace::{Backtrace, BacktraceStatus};
+
+/// Print a backtrace of the current thread to stderr.
+///
+/// Called from the generated label functions right before they abort or
+/// panic so you can tell which scope dropped the value.
+#[inline]
+pub fn report() {
+    #[cfg(feature = "backtrace")]
+    {
+        if let Some(backtrace) = format(&Backtrace::capture()) {
+            ::rt::report(format_args!("prevent_drop: value dropped at:\n{}", backtrace));
+        }
+    }
+}
+
+#[cfg(feature = "backtrace")]
+fn format(backtrace: &Backtrace) -> Option<String> {
+    match backtrace.status() {
+        BacktraceStatus::Captured => Some(backtrace.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "backtrace"))]
+mod tests {
+    use std::backtrace::Backtrace;
+
+    #[test]
+    fn format_captured_backtrace_is_not_empty() {
+        let backtrace = super::format(&Backtrace::force_capture()).unwrap();
+        assert!(!backtrace.is_empty());
+    }
+
+    #[test]
+    fn format_disabled_backtrace_is_none() {
+        assert!(super::format(&Backtrace::disabled()).is_none());
+    }
+}
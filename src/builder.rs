@@ -0,0 +1,111 @@
+//! The drop guard field behind `prevent_drop_builder!`.
+
+use core::marker::PhantomData;
+use core::mem;
+
+/// Implemented by `prevent_drop_builder!` for the guarded builder.
+///
+/// You should not implement this trait yourself.
+pub trait GuardedBuilder {
+    #[doc(hidden)]
+    fn forgot_to_build();
+}
+
+/// A zero-sized field that fires the drop guard of the builder `B` when
+/// it is dropped.
+///
+/// Put it in a builder guarded with `prevent_drop_builder!` and call
+/// `defuse` on it in the method that finishes the builder. Because the
+/// guard is a field, the builder itself does not implement `Drop` and
+/// the finishing method can move its fields out.
+pub struct BuildGuard<B: GuardedBuilder>(PhantomData<fn() -> B>);
+
+impl<B: GuardedBuilder> BuildGuard<B> {
+    /// Create an armed guard.
+    #[inline]
+    pub fn new() -> Self {
+        BuildGuard(PhantomData)
+    }
+
+    /// Disarm the guard.
+    #[inline]
+    pub fn defuse(self) {
+        mem::forget(self);
+    }
+}
+
+impl<B: GuardedBuilder> Default for BuildGuard<B> {
+    #[inline]
+    fn default() -> Self {
+        BuildGuard::new()
+    }
+}
+
+impl<B: GuardedBuilder> Drop for BuildGuard<B> {
+    #[inline]
+    fn drop(&mut self) {
+        B::forgot_to_build();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BuildGuard;
+
+    struct Request {
+        url: String,
+        retries: u32,
+    }
+
+    #[derive(Default)]
+    struct RequestBuilder {
+        url: String,
+        retries: u32,
+        guard: BuildGuard<RequestBuilder>,
+    }
+
+    impl RequestBuilder {
+        fn url(mut self, url: &str) -> Self {
+            self.url = url.to_string();
+            self
+        }
+
+        fn retries(mut self, retries: u32) -> Self {
+            self.retries = retries;
+            self
+        }
+
+        fn build(self) -> Request {
+            let RequestBuilder { url, retries, guard } = self;
+            guard.defuse();
+            Request { url, retries }
+        }
+    }
+
+    prevent_drop_builder!(
+        RequestBuilder,
+        forget_to_explicitly_drop_an_instance_of_builder_RequestBuilder,
+        strategy = panic
+    );
+
+    assert_prevent_drop!(RequestBuilder);
+
+    #[test]
+    fn build_defuses_the_guard() {
+        let request = RequestBuilder::default().url("https://example.com").retries(3).build();
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(request.retries, 3);
+    }
+
+    #[cfg(not(feature = "dry_run"))]
+    #[test]
+    #[should_panic(expected = "Forgot to build an instance of RequestBuilder.")]
+    fn forgetting_to_build_trips_the_guard() {
+        let _ = RequestBuilder::default().url("https://example.com");
+    }
+
+    #[test]
+    fn guard_is_zero_sized() {
+        assert_eq!(::std::mem::size_of::<BuildGuard<RequestBuilder>>(), 0);
+    }
+}
@@ -0,0 +1,148 @@
+//! A scoped guard that cleans a value up when the scope is left early.
+
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+
+/// Own a guarded value for the rest of a scope and clean it up if the
+/// scope is left without calling `defuse`.
+///
+/// `DropGuard` reports an early exit as a leak. In functions where an
+/// early `return` or `?` is expected, such as the error path of a
+/// constructor, that is not what you want. A `CleanupGuard` runs the
+/// cleanup function on the value instead, so the value is consumed and
+/// its own guard never fires. On the happy path `defuse` gives the value
+/// back without running the cleanup. Create one with
+/// `guard_or_cleanup!`.
+#[must_use = "the cleanup runs as soon as the guard is dropped"]
+pub struct CleanupGuard<T, F: FnOnce(T)> {
+    value: ManuallyDrop<T>,
+    cleanup: ManuallyDrop<F>,
+}
+
+impl<T, F: FnOnce(T)> CleanupGuard<T, F> {
+    /// Guard `value` until `defuse` is called and pass it to `cleanup`
+    /// otherwise.
+    #[inline]
+    pub fn new(value: T, cleanup: F) -> Self {
+        CleanupGuard {
+            value: ManuallyDrop::new(value),
+            cleanup: ManuallyDrop::new(cleanup),
+        }
+    }
+
+    /// Consume the guard without running the cleanup and give the value
+    /// back.
+    #[inline]
+    pub fn defuse(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // Safe because the guard is never dropped, so both fields are
+        // taken exactly once.
+        unsafe {
+            ManuallyDrop::drop(&mut this.cleanup);
+            ManuallyDrop::take(&mut this.value)
+        }
+    }
+}
+
+impl<T, F: FnOnce(T)> Deref for CleanupGuard<T, F> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, F: FnOnce(T)> DerefMut for CleanupGuard<T, F> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T, F: FnOnce(T)> Drop for CleanupGuard<T, F> {
+    #[inline]
+    fn drop(&mut self) {
+        // Safe because `drop` runs at most once and `defuse` forgets the
+        // guard, so both fields are taken exactly once.
+        let (value, cleanup) = unsafe {
+            (
+                ManuallyDrop::take(&mut self.value),
+                ManuallyDrop::take(&mut self.cleanup),
+            )
+        };
+        cleanup(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::mem::ManuallyDrop;
+
+    thread_local! {
+        static CLOSED: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn closed() -> Vec<u32> {
+        CLOSED.with(|closed| closed.borrow().clone())
+    }
+
+    #[derive(Debug)]
+    struct Connection {
+        id: u32,
+        ready: bool,
+    }
+
+    impl Connection {
+        fn close(self) {
+            let this = ManuallyDrop::new(self);
+            CLOSED.with(|closed| closed.borrow_mut().push(this.id));
+        }
+    }
+
+    prevent_drop_panic!(Connection, forget_to_explicitly_drop_an_instance_of_cleanup_Connection);
+
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Handshake,
+        Parse(::std::num::ParseIntError),
+        NotReady,
+    }
+
+    fn connect(id: u32, handshake: bool, port: &str) -> Result<Connection, Error> {
+        let mut connection = guard_or_cleanup!(Connection { id, ready: false }, |c| c.close());
+        if !handshake {
+            return Err(Error::Handshake);
+        }
+        let port: u32 = port.parse().map_err(Error::Parse)?;
+        connection.ready = port != 0;
+        if !connection.ready {
+            return Err(Error::NotReady);
+        }
+        Ok(connection.defuse())
+    }
+
+    #[test]
+    fn ok_path_defuses_without_cleanup() {
+        let connection = connect(1, true, "80").unwrap();
+        assert!(connection.ready);
+        assert!(closed().is_empty());
+        connection.close();
+        assert_eq!(closed(), [1]);
+    }
+
+    #[test]
+    fn err_paths_run_the_cleanup() {
+        assert_eq!(connect(1, false, "80").unwrap_err(), Error::Handshake);
+        assert!(matches!(connect(2, true, "eighty").unwrap_err(), Error::Parse(_)));
+        assert_eq!(connect(3, true, "0").unwrap_err(), Error::NotReady);
+        assert_eq!(closed(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn cleanup_accepts_functions() {
+        drop(guard_or_cleanup!(Connection { id: 4, ready: true }, Connection::close));
+        assert_eq!(closed(), [4]);
+    }
+}
@@ -0,0 +1,28 @@
+//! Compile-time support for the code generated by `prevent_drop_const!`.
+//!
+//! The error is a constant that fails to evaluate. Constants that depend
+//! on a generic parameter are only evaluated when the function using
+//! them is instantiated, which happens exactly when the drop glue of the
+//! guarded type is needed. This works without optimizations because the
+//! compiler decides which drops are needed before optimizing.
+
+use core::marker::PhantomData;
+
+/// The message to report when a value of the implementing type would be
+/// dropped.
+pub trait ConstDropMessage {
+    const MESSAGE: &'static str;
+}
+
+struct ConstDropError<T: ?Sized>(PhantomData<T>);
+
+impl<T: ?Sized + ConstDropMessage> ConstDropError<T> {
+    const ERROR: () = panic!("{}", T::MESSAGE);
+}
+
+/// Fail to compile if this function is instantiated.
+#[inline]
+pub fn drop_error<T: ?Sized + ConstDropMessage>() {
+    #[allow(clippy::let_unit_value)]
+    let () = ConstDropError::<T>::ERROR;
+}
@@ -0,0 +1,131 @@
+//! The `Consume` trait for explicit drop functions that need a context.
+
+use core::mem::ManuallyDrop;
+
+/// An explicit drop function that takes a context and can fail.
+///
+/// This is the `drop(self, context) -> Result<(), Error>` pattern from
+/// the crate documentation as a trait. Implement `cleanup` and call
+/// `consume`. Like the functions given to `prevent_drop_consume!`,
+/// `cleanup` receives the value wrapped in `ManuallyDrop` so it cannot
+/// trigger the guard. Move fields out of it with `ptr::read`, anything
+/// left behind is leaked.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use prevent_drop::Consume;
+/// use std::mem::ManuallyDrop;
+///
+/// struct Context;
+/// struct Error;
+/// struct Resource;
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// impl Consume for Resource {
+///     type Ctx = Context;
+///     type Err = Error;
+///
+///     fn cleanup(_this: ManuallyDrop<Self>, _ctx: &Context) -> Result<(), Error> {
+///         // Perform cleanup.
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+///     let r = Resource;
+///     assert!(r.consume(&Context).is_ok());
+/// }
+/// ```
+pub trait Consume: Sized {
+    /// The context needed to clean up.
+    type Ctx: ?Sized;
+
+    /// The error returned when cleaning up fails.
+    type Err;
+
+    /// Clean up a value whose drop guard has been defused.
+    fn cleanup(this: ManuallyDrop<Self>, ctx: &Self::Ctx) -> Result<(), Self::Err>;
+
+    /// Defuse the drop guard and clean up.
+    #[inline]
+    fn consume(self, ctx: &Self::Ctx) -> Result<(), Self::Err> {
+        Self::cleanup(ManuallyDrop::new(self), ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::mem::ManuallyDrop;
+    use std::ptr;
+
+    use super::Consume;
+
+    struct Pool {
+        healthy: bool,
+        returned: RefCell<Vec<u32>>,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Error(u32);
+
+    struct Connection {
+        id: u32,
+    }
+
+    prevent_drop_panic!(Connection, forget_to_explicitly_drop_an_instance_of_consume_trait_Connection);
+
+    impl Consume for Connection {
+        type Ctx = Pool;
+        type Err = Error;
+
+        fn cleanup(this: ManuallyDrop<Self>, pool: &Pool) -> Result<(), Error> {
+            let id = unsafe { ptr::read(&this.id) };
+            if pool.healthy {
+                pool.returned.borrow_mut().push(id);
+                Ok(())
+            } else {
+                Err(Error(id))
+            }
+        }
+    }
+
+    fn pool(healthy: bool) -> Pool {
+        Pool {
+            healthy,
+            returned: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn consume_ok() {
+        let pool = pool(true);
+        assert_eq!(Connection { id: 1 }.consume(&pool), Ok(()));
+        assert_eq!(*pool.returned.borrow(), [1]);
+    }
+
+    #[test]
+    fn consume_err() {
+        let pool = pool(false);
+        assert_eq!(Connection { id: 2 }.consume(&pool), Err(Error(2)));
+        assert!(pool.returned.borrow().is_empty());
+    }
+
+    #[test]
+    fn consume_composes_with_question_mark() {
+        fn close_all(connections: Vec<Connection>, pool: &Pool) -> Result<(), Error> {
+            for connection in ::defuse_all(connections) {
+                ManuallyDrop::into_inner(connection).consume(pool)?;
+            }
+            Ok(())
+        }
+
+        let pool = pool(true);
+        let connections = vec![Connection { id: 1 }, Connection { id: 2 }];
+        assert_eq!(close_all(connections, &pool), Ok(()));
+        assert_eq!(*pool.returned.borrow(), [1, 2]);
+    }
+}
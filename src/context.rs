@@ -0,0 +1,64 @@
+//! Leak errors for applications that report errors through `anyhow`.
+
+use anyhow::Error;
+
+/// An error saying that a value of `type_name` was about to be leaked.
+///
+/// Its message is `default_leak_message(type_name)`, which is also the
+/// default message of the panic strategy. When an explicit drop function
+/// fails, attach it as context so the report shows which value the
+/// failure leaked.
+///
+/// ```
+/// extern crate anyhow;
+/// extern crate prevent_drop;
+///
+/// use prevent_drop::leak_context;
+///
+/// fn close() -> anyhow::Result<()> {
+///     anyhow::bail!("connection reset")
+/// }
+///
+/// fn main() {
+///     let error = close().map_err(|error| error.context(leak_context("Connection"))).unwrap_err();
+///     assert_eq!(
+///         format!("{:#}", error),
+///         "Forgot to explicitly drop an instance of Connection.: connection reset"
+///     );
+/// }
+/// ```
+#[inline]
+pub fn leak_context(type_name: &str) -> Error {
+    Error::msg(::formatter::default_leak_message(type_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::leak_context;
+    use anyhow::anyhow;
+
+    #[test]
+    fn context_chain_includes_the_type_name() {
+        let error = anyhow!("connection reset").context(leak_context("Connection"));
+        let chain: Vec<String> = error.chain().map(|cause| cause.to_string()).collect();
+        assert_eq!(
+            chain,
+            [
+                "Forgot to explicitly drop an instance of Connection.",
+                "connection reset",
+            ]
+        );
+        assert!(leak_context("Buffer<T>").chain().any(|cause| cause.to_string().contains("Buffer<T>")));
+    }
+
+    struct Resource;
+
+    prevent_drop_panic!(Resource, prevent_drop_context_Resource);
+
+    #[test]
+    fn panic_strategy_reports_the_context_message() {
+        let payload = ::std::panic::catch_unwind(|| ::std::mem::drop(Resource)).unwrap_err();
+        let message = payload.downcast::<String>().unwrap();
+        assert!(message.starts_with(&leak_context("Resource").to_string()), "unexpected message: {}", message);
+    }
+}
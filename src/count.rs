@@ -0,0 +1,150 @@
+//! The process-global counters behind `prevent_drop_count!`.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(any(feature = "std", test))]
+use std::collections::BTreeMap;
+#[cfg(all(feature = "libc", any(feature = "std", test)))]
+use std::sync::OnceLock;
+#[cfg(any(feature = "std", test))]
+use std::sync::{Mutex, PoisonError};
+
+static LEAKS: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(all(feature = "libc", any(feature = "std", test)))]
+static ON_LEAK: OnceLock<fn(u64)> = OnceLock::new();
+
+#[cfg(any(feature = "std", test))]
+static LEAKS_PER_TYPE: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+
+/// The number of times a `prevent_drop_count!` guard fired since the
+/// start of the process or the last call to `reset_leak_count`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_count!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     prevent_drop::reset_leak_count();
+///     drop(Resource);
+///     assert_eq!(prevent_drop::leak_count(), 1);
+/// }
+/// ```
+#[inline]
+pub fn leak_count() -> u64 {
+    LEAKS.load(Ordering::SeqCst)
+}
+
+/// The number of times the `prevent_drop_count!` guard of the type named
+/// `type_name` fired since the start of the process or the last call to
+/// `reset_leak_count`.
+///
+/// The name is the type as written in the macro invocation, like in
+/// `registered_leak_types`. Requires `std`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+/// struct Socket;
+///
+/// prevent_drop_count!(Resource, prevent_drop_Resource);
+/// prevent_drop_count!(Socket, prevent_drop_Socket);
+///
+/// fn main() {
+///     prevent_drop::reset_leak_count();
+///     drop(Resource);
+///     drop(Resource);
+///     drop(Socket);
+///     assert_eq!(prevent_drop::leak_count_for("Resource"), 2);
+///     assert_eq!(prevent_drop::leak_count_for("Socket"), 1);
+///     assert_eq!(prevent_drop::leak_counts(), [("Resource", 2), ("Socket", 1)]);
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn leak_count_for(type_name: &str) -> u64 {
+    let counts = LEAKS_PER_TYPE.lock().unwrap_or_else(PoisonError::into_inner);
+    counts.get(type_name).cloned().unwrap_or(0)
+}
+
+/// A snapshot of the leak count of every type whose `prevent_drop_count!`
+/// guard fired, sorted by type name. Requires `std`.
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn leak_counts() -> Vec<(&'static str, u64)> {
+    let counts = LEAKS_PER_TYPE.lock().unwrap_or_else(PoisonError::into_inner);
+    counts.iter().map(|(&type_name, &count)| (type_name, count)).collect()
+}
+
+/// Set the leak counter and the counts per type back to zero.
+#[inline]
+pub fn reset_leak_count() {
+    LEAKS.store(0, Ordering::SeqCst);
+    #[cfg(any(feature = "std", test))]
+    LEAKS_PER_TYPE.lock().unwrap_or_else(PoisonError::into_inner).clear();
+}
+
+/// Call `on_leak` with `leak_count()` when the process exits normally
+/// if any `prevent_drop_count!` guard fired.
+///
+/// This catches leaks that happened anywhere during the run of a
+/// long-running program. The check is registered with `atexit(3)`, so
+/// it runs when `main` returns or `process::exit` is called, but not
+/// when the process aborts or is killed. `on_leak` decides what to do:
+/// log the count, or abort to make the exit fail. It must not panic,
+/// because a panic cannot unwind out of the exit handler and aborts.
+///
+/// The check can be installed once. Later calls return `on_leak` back
+/// as the error and leave the first one in place. Requires the `libc`
+/// feature and `std`.
+///
+/// ```
+/// fn report(leaks: u64) {
+///     eprintln!("{} values leaked", leaks);
+///     std::process::abort();
+/// }
+///
+/// fn main() {
+///     prevent_drop::install_exit_leak_check(report).unwrap();
+/// }
+/// ```
+#[cfg(all(feature = "libc", any(feature = "std", test)))]
+pub fn install_exit_leak_check(on_leak: fn(u64)) -> Result<(), fn(u64)> {
+    extern "C" fn check() {
+        let leaks = leak_count();
+        if leaks > 0 {
+            if let Some(on_leak) = ON_LEAK.get() {
+                on_leak(leaks);
+            }
+        }
+    }
+
+    ON_LEAK.set(on_leak)?;
+    // Safe because `check` is an `extern "C" fn()` that lives for the
+    // whole process. If registering fails there is nothing to fall back
+    // to, so the check is silently skipped.
+    unsafe { ::libc::atexit(check) };
+    Ok(())
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn record_leak(type_name: &'static str) {
+    LEAKS.fetch_add(1, Ordering::SeqCst);
+    #[cfg(any(feature = "std", test))]
+    record_leak_of(type_name);
+
+    #[cfg(not(any(feature = "std", test)))]
+    let _ = type_name;
+}
+
+#[cfg(any(feature = "std", test))]
+fn record_leak_of(type_name: &'static str) {
+    let mut counts = LEAKS_PER_TYPE.lock().unwrap_or_else(PoisonError::into_inner);
+    *counts.entry(type_name).or_insert(0) += 1;
+}
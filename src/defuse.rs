@@ -0,0 +1,396 @@
+//! Defuse the drop guards of a whole collection at once.
+
+use core::mem::ManuallyDrop;
+#[cfg(any(feature = "std", test))]
+use core::pin::Pin;
+use core::ptr;
+#[cfg(any(feature = "std", test))]
+use std::rc::Rc;
+#[cfg(any(feature = "std", test))]
+use std::sync::Arc;
+
+/// Collections whose elements can be wrapped in `ManuallyDrop` in place.
+///
+/// Implemented for `Vec<T>` and `[T; N]`. Use it through `defuse_all`.
+pub trait DefuseAll {
+    /// The collection with every element wrapped in `ManuallyDrop`.
+    type Output;
+
+    /// Wrap every element in `ManuallyDrop`.
+    fn defuse_all(self) -> Self::Output;
+}
+
+#[cfg(any(feature = "std", test))]
+impl<T> DefuseAll for Vec<T> {
+    type Output = Vec<ManuallyDrop<T>>;
+
+    #[inline]
+    fn defuse_all(self) -> Vec<ManuallyDrop<T>> {
+        let mut this = ManuallyDrop::new(self);
+        // Safe because `ManuallyDrop<T>` has the same layout as `T` and
+        // the original vector is never dropped.
+        unsafe {
+            Vec::from_raw_parts(
+                this.as_mut_ptr() as *mut ManuallyDrop<T>,
+                this.len(),
+                this.capacity(),
+            )
+        }
+    }
+}
+
+impl<T, const N: usize> DefuseAll for [T; N] {
+    type Output = [ManuallyDrop<T>; N];
+
+    #[inline]
+    fn defuse_all(self) -> [ManuallyDrop<T>; N] {
+        let this = ManuallyDrop::new(self);
+        // Safe because `ManuallyDrop<T>` has the same layout as `T` and
+        // the original array is never dropped.
+        unsafe { ptr::read(&*this as *const [T; N] as *const [ManuallyDrop<T>; N]) }
+    }
+}
+
+/// Wrap every element of a collection of guarded values in
+/// `ManuallyDrop`.
+///
+/// Dropping a `Vec` of guarded values triggers the guard of every
+/// element. Defuse them first and then consume each element explicitly
+/// with `ManuallyDrop::into_inner`. Elements you skip are leaked rather
+/// than reported. This does not allocate or move the elements.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::mem::ManuallyDrop;
+///
+/// struct Resource;
+///
+/// impl Resource {
+///     fn close(self) {
+///         let _zelf = ManuallyDrop::new(self);
+///     }
+/// }
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     let resources = vec![Resource, Resource];
+///     for resource in prevent_drop::defuse_all(resources) {
+///         ManuallyDrop::into_inner(resource).close();
+///     }
+/// }
+/// ```
+#[inline]
+pub fn defuse_all<C: DefuseAll>(collection: C) -> C::Output {
+    collection.defuse_all()
+}
+
+/// Take the guarded value out of an `Arc` and wrap it in `ManuallyDrop`.
+///
+/// Dropping the last `Arc` drops the value inside, which triggers its
+/// guard like any other drop. Call this instead of dropping what you
+/// expect to be the last `Arc`. If other clones are still alive the
+/// `Arc` is handed back unchanged.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::mem::ManuallyDrop;
+/// use std::sync::Arc;
+///
+/// struct Resource;
+///
+/// impl Resource {
+///     fn close(self) {
+///         let _zelf = ManuallyDrop::new(self);
+///     }
+/// }
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     let shared = Arc::new(Resource);
+///     let clone = Arc::clone(&shared);
+///     let shared = prevent_drop::defuse_arc(shared).err().unwrap();
+///     drop(clone);
+///     let resource = prevent_drop::defuse_arc(shared).ok().unwrap();
+///     ManuallyDrop::into_inner(resource).close();
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn defuse_arc<T>(arc: Arc<T>) -> Result<ManuallyDrop<T>, Arc<T>> {
+    Arc::try_unwrap(arc).map(ManuallyDrop::new)
+}
+
+/// Take the guarded value out of an `Rc` and wrap it in `ManuallyDrop`.
+///
+/// See `defuse_arc`.
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn defuse_rc<T>(rc: Rc<T>) -> Result<ManuallyDrop<T>, Rc<T>> {
+    Rc::try_unwrap(rc).map(ManuallyDrop::new)
+}
+
+/// Move the guarded value out of a `Box` and wrap it in `ManuallyDrop`.
+///
+/// The allocation is freed without dropping the value. This is what
+/// `prevent_drop_try_drop!` does with the `self: Box<Self>` receiver of
+/// `TryDrop::try_drop`.
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn defuse_box<T>(boxed: Box<T>) -> ManuallyDrop<T> {
+    // Safe because `ManuallyDrop<T>` has the same layout as `T`.
+    let boxed = unsafe { Box::from_raw(Box::into_raw(boxed) as *mut ManuallyDrop<T>) };
+    *boxed
+}
+
+/// Leak a pinned guarded value and return a pinned reference to it.
+///
+/// Dropping a `Pin<Box<T>>` drops the value, which triggers its guard.
+/// A pinned value must stay at its address until it is dropped, so it
+/// cannot be moved out like with `defuse_box`. This never frees the
+/// allocation instead, which upholds pinning without `unsafe`. Tear the
+/// value down through the returned reference. Use `defuse_pin_box` to
+/// free the allocation as well.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::pin::Pin;
+///
+/// struct Resource {
+///     open: bool,
+/// }
+///
+/// impl Resource {
+///     fn close(self: Pin<&mut Self>) {
+///         self.get_mut().open = false;
+///     }
+/// }
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     let resource = Box::pin(Resource { open: true });
+///     prevent_drop::leak_pin_box(resource).close();
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub fn leak_pin_box<'a, T: 'a>(pinned: Pin<Box<T>>) -> Pin<&'a mut T> {
+    // Safe because the allocation is leaked, so the value is never moved
+    // or freed.
+    unsafe { Pin::new_unchecked(Box::leak(Pin::into_inner_unchecked(pinned))) }
+}
+
+/// Tear a pinned guarded value down in place and free its allocation
+/// without dropping it.
+///
+/// `teardown` receives the value pinned at its address. Whatever it
+/// returns is returned from this function. The allocation is freed
+/// afterwards, and also if `teardown` panics, but the value is never
+/// dropped, so its guard does not fire.
+///
+/// # Safety
+///
+/// Pinning promises that the memory of a value that is not `Unpin` is
+/// not reused before the value is dropped. Once `teardown` returns or
+/// panics, nothing may rely on the address of the value or on its
+/// destructor running any more, e.g. pointers to it registered
+/// elsewhere must have been removed. For `Unpin` values this always
+/// holds, so prefer `defuse_box(Pin::into_inner(pinned))`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::pin::Pin;
+///
+/// struct Resource {
+///     id: u32,
+/// }
+///
+/// impl Resource {
+///     fn close(self: Pin<&mut Self>) -> u32 {
+///         self.id
+///     }
+/// }
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     let resource = Box::pin(Resource { id: 3 });
+///     // Safe because nothing refers to the address of the resource.
+///     let id = unsafe { prevent_drop::defuse_pin_box(resource, Resource::close) };
+///     assert_eq!(id, 3);
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+#[inline]
+pub unsafe fn defuse_pin_box<T, R, F: FnOnce(Pin<&mut T>) -> R>(pinned: Pin<Box<T>>, teardown: F) -> R {
+    // Sound because `ManuallyDrop<T>` has the same layout as `T` and the
+    // value stays in the same allocation.
+    let mut boxed = Box::from_raw(Box::into_raw(Pin::into_inner_unchecked(pinned)) as *mut ManuallyDrop<T>);
+    teardown(Pin::new_unchecked(&mut **boxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomPinned;
+    use std::mem::ManuallyDrop;
+    use std::pin::Pin;
+    use std::ptr;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use super::{defuse_all, defuse_arc, defuse_box, defuse_pin_box, defuse_rc, leak_pin_box};
+
+    struct Resource(u32);
+
+    impl Resource {
+        fn close(self) -> u32 {
+            let zelf = ManuallyDrop::new(self);
+            zelf.0
+        }
+    }
+
+    prevent_drop_panic!(Resource, forget_to_explicitly_drop_an_instance_of_defuse_Resource);
+
+    #[test]
+    fn defuse_all_vec() {
+        let resources = vec![Resource(1), Resource(2), Resource(3)];
+        let capacity = resources.capacity();
+        let defused = defuse_all(resources);
+        assert_eq!(defused.capacity(), capacity);
+        let ids: Vec<u32> = defused
+            .into_iter()
+            .map(|resource| ManuallyDrop::into_inner(resource).close())
+            .collect();
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[test]
+    fn defuse_all_array() {
+        let defused = defuse_all([Resource(1), Resource(2)]);
+        let mut sum = 0;
+        for resource in defused {
+            sum += ManuallyDrop::into_inner(resource).close();
+        }
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn defuse_all_empty() {
+        assert!(defuse_all(Vec::<Resource>::new()).is_empty());
+        assert!(defuse_all([] as [Resource; 0]).is_empty());
+    }
+
+    #[test]
+    fn defuse_all_leaks_skipped_elements() {
+        let defused = defuse_all(vec![Resource(1), Resource(2)]);
+        assert_eq!(defused[1].0, 2);
+    }
+
+    #[test]
+    fn defuse_box_moves_the_value_out() {
+        let resource = defuse_box(Box::new(Resource(4)));
+        assert_eq!(ManuallyDrop::into_inner(resource).close(), 4);
+    }
+
+    // A self-referential value that must not move once pinned.
+    struct Node {
+        id: u32,
+        this: *const Node,
+        _pinned: PhantomPinned,
+    }
+
+    impl Node {
+        fn pinned(id: u32) -> Pin<Box<Node>> {
+            let mut node = Box::pin(Node {
+                id,
+                this: ptr::null(),
+                _pinned: PhantomPinned,
+            });
+            // Safe because the node is not moved out of the box.
+            let this = unsafe { node.as_mut().get_unchecked_mut() };
+            this.this = this;
+            node
+        }
+
+        fn close(self: Pin<&mut Self>) -> u32 {
+            assert_eq!(self.this, &*self as *const Node, "the node moved");
+            // Safe because the node is not moved.
+            let this = unsafe { self.get_unchecked_mut() };
+            this.this = ptr::null();
+            this.id
+        }
+    }
+
+    prevent_drop_panic!(Node, forget_to_explicitly_drop_an_instance_of_defuse_Node);
+
+    #[test]
+    fn leak_pin_box_keeps_the_value_in_place() {
+        let node = Node::pinned(5);
+        assert_eq!(leak_pin_box(node).close(), 5);
+    }
+
+    #[test]
+    fn defuse_pin_box_tears_down_in_place() {
+        let node = Node::pinned(6);
+        // Safe because `close` clears the only pointer to the node.
+        assert_eq!(unsafe { defuse_pin_box(node, Node::close) }, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Node.")]
+    fn dropping_a_pinned_value_triggers_the_guard() {
+        drop(Node::pinned(7));
+    }
+
+    #[test]
+    fn defuse_arc_unique_owner() {
+        let resource = defuse_arc(Arc::new(Resource(1))).ok().unwrap();
+        assert_eq!(ManuallyDrop::into_inner(resource).close(), 1);
+    }
+
+    #[test]
+    fn defuse_arc_shared() {
+        let shared = Arc::new(Resource(1));
+        let clone = Arc::clone(&shared);
+        let shared = defuse_arc(shared).err().unwrap();
+        assert_eq!(Arc::strong_count(&shared), 2);
+        drop(clone);
+        let resource = defuse_arc(shared).ok().unwrap();
+        assert_eq!(ManuallyDrop::into_inner(resource).close(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Resource.")]
+    fn dropping_the_last_arc_triggers_the_guard() {
+        let shared = Arc::new(Resource(1));
+        let clone = Arc::clone(&shared);
+        drop(shared);
+        drop(clone);
+    }
+
+    #[test]
+    fn defuse_rc_unique_owner() {
+        let resource = defuse_rc(Rc::new(Resource(1))).ok().unwrap();
+        assert_eq!(ManuallyDrop::into_inner(resource).close(), 1);
+    }
+
+    #[test]
+    fn defuse_rc_shared() {
+        let shared = Rc::new(Resource(2));
+        let clone = Rc::clone(&shared);
+        let shared = defuse_rc(shared).err().unwrap();
+        drop(clone);
+        let resource = defuse_rc(shared).ok().unwrap();
+        assert_eq!(ManuallyDrop::into_inner(resource).close(), 2);
+    }
+}
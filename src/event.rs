@@ -0,0 +1,234 @@
+//! Structured leak reports for the log, record and channel strategies.
+
+use std::cell::Cell;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+static HANDLER: OnceLock<fn(LeakEvent)> = OnceLock::new();
+
+static CHANNEL: Mutex<Option<Sender<LeakEvent>>> = Mutex::new(None);
+
+static THREADS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A leak reported by a log, record or channel strategy guard.
+///
+/// Register a handler with `set_leak_event_handler` to receive these,
+/// e.g. to emit them as JSON to a telemetry pipeline, or a channel with
+/// `set_leak_channel`. Requires the `serde` feature.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LeakEvent {
+    /// The type as written in the macro invocation, e.g. `Buffer<T>`.
+    pub type_name: &'static str,
+    /// The message the guard reports.
+    pub message: String,
+    /// When the value was dropped, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The thread that dropped the value. Threads are numbered from 1 in
+    /// the order they first report a leak.
+    pub thread_id: u64,
+}
+
+/// Pass a `LeakEvent` to `handler` for every leak reported by a log or
+/// record strategy guard in the process.
+///
+/// The handler can be set once. Later calls return it back as the error
+/// and leave the first one in place. It runs on the thread that dropped
+/// the value, inside the `Drop` implementation, so it should not panic.
+/// No event is built while no handler is set.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use prevent_drop::LeakEvent;
+///
+/// struct Resource;
+///
+/// prevent_drop_record!(Resource, prevent_drop_Resource);
+///
+/// fn report(event: LeakEvent) {
+///     assert_eq!(event.type_name, "Resource");
+/// }
+///
+/// fn main() {
+///     prevent_drop::set_leak_event_handler(report).unwrap();
+///     drop(Resource);
+/// }
+/// ```
+#[inline]
+pub fn set_leak_event_handler(handler: fn(LeakEvent)) -> Result<(), fn(LeakEvent)> {
+    HANDLER.set(handler)
+}
+
+/// Send a `LeakEvent` over `sender` for every leak reported by a
+/// `prevent_drop_channel!` guard in the process.
+///
+/// This hands leaks to a monitoring thread, away from the drop site.
+/// Setting a channel again replaces the previous one. Sending never
+/// blocks, and the guard carries on if the receiver is gone. No event is
+/// built while no channel is set.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::sync::mpsc;
+///
+/// struct Resource;
+///
+/// prevent_drop_channel!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     let (sender, receiver) = mpsc::channel();
+///     prevent_drop::set_leak_channel(sender);
+///     drop(Resource);
+///     assert_eq!(receiver.recv().unwrap().type_name, "Resource");
+/// }
+/// ```
+#[inline]
+pub fn set_leak_channel(sender: Sender<LeakEvent>) {
+    *CHANNEL.lock().unwrap_or_else(PoisonError::into_inner) = Some(sender);
+}
+
+/// Build a `LeakEvent` and pass it to the handler, if one is set.
+#[doc(hidden)]
+pub fn emit<M: Display>(type_name: &'static str, message: M) {
+    if let Some(handler) = HANDLER.get() {
+        handler(event(type_name, message));
+    }
+}
+
+/// Build a `LeakEvent` and send it over the channel, if one is set.
+#[doc(hidden)]
+pub fn send<M: Display>(type_name: &'static str, message: M) {
+    let channel = CHANNEL.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(ref sender) = *channel {
+        // The receiver may be gone, the leak is dropped with the event.
+        let _ = sender.send(event(type_name, message));
+    }
+}
+
+fn event<M: Display>(type_name: &'static str, message: M) -> LeakEvent {
+    LeakEvent {
+        type_name,
+        message: message.to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64),
+        thread_id: thread_id(),
+    }
+}
+
+fn thread_id() -> u64 {
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(THREADS.fetch_add(1, Ordering::Relaxed) + 1);
+        }
+        id.get()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+
+    use super::{set_leak_channel, set_leak_event_handler, thread_id, LeakEvent};
+
+    static EVENTS: Mutex<Vec<LeakEvent>> = Mutex::new(Vec::new());
+
+    fn collect(event: LeakEvent) {
+        EVENTS.lock().unwrap().push(event);
+    }
+
+    struct Recorded;
+
+    prevent_drop_record!(Recorded, forget_to_explicitly_drop_an_instance_of_event_Recorded);
+
+    #[test]
+    fn leak_event_serializes_to_json() {
+        let event = LeakEvent {
+            type_name: "Buffer<T>",
+            message: String::from("Leaked a Buffer."),
+            timestamp: 1_700_000_000_000,
+            thread_id: 2,
+        };
+        assert_eq!(
+            ::serde_json::to_string(&event).unwrap(),
+            r#"{"type_name":"Buffer<T>","message":"Leaked a Buffer.","timestamp":1700000000000,"thread_id":2}"#
+        );
+    }
+
+    #[test]
+    fn record_strategy_emits_events() {
+        set_leak_event_handler(collect).unwrap();
+        drop(Recorded);
+        ::record::take_recorded_leaks();
+
+        let events = EVENTS.lock().unwrap();
+        let event = events
+            .iter()
+            .find(|event| event.type_name == "Recorded")
+            .unwrap();
+        assert_eq!(event.message, "Forgot to explicitly drop an instance of Recorded.");
+        assert_eq!(event.thread_id, thread_id());
+        assert!(event.timestamp > 0);
+    }
+
+    struct Sent;
+    struct SentBuffer<T>(T);
+
+    prevent_drop_channel!(Sent, forget_to_explicitly_drop_an_instance_of_event_Sent);
+    prevent_drop_channel!(
+        SentBuffer<T>,
+        forget_to_explicitly_drop_an_instance_of_event_SentBuffer,
+        generics = [T],
+        "Leaked a SentBuffer."
+    );
+
+    // `set_leak_channel` replaces the sender, so a second test installing
+    // its own would take the events this one waits for.
+    #[test]
+    fn channel_strategy_sends_events() {
+        // Without a channel the leak is ignored.
+        drop(Sent);
+
+        let (sender, receiver) = mpsc::channel();
+        set_leak_channel(sender);
+        drop(Sent);
+        drop(SentBuffer(1u8));
+        let _ = ::std::mem::ManuallyDrop::new(Sent);
+        let events: Vec<LeakEvent> = receiver.try_iter().collect();
+        let summary: Vec<(&str, &str)> = events
+            .iter()
+            .map(|event| (event.type_name, event.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Sent", "Forgot to explicitly drop an instance of Sent."),
+                ("SentBuffer<T>", "Leaked a SentBuffer."),
+            ]
+        );
+        assert!(events.iter().all(|event| event.thread_id == thread_id()));
+
+        // A closed channel does not stop the guard from returning.
+        drop(receiver);
+        drop(Sent);
+    }
+
+    #[test]
+    fn threads_get_distinct_ids() {
+        let other = ::std::thread::spawn(thread_id).join().unwrap();
+        assert_ne!(other, thread_id());
+    }
+}
@@ -0,0 +1,65 @@
+//! The process-wide formatter for the messages of the panic strategy.
+
+use std::sync::OnceLock;
+
+static FORMATTER: OnceLock<fn(&'static str) -> String> = OnceLock::new();
+
+/// Format the default message of every guard in the process with
+/// `formatter`.
+///
+/// The formatter receives the type name as written in the macro
+/// invocation, e.g. `Buffer<T>`. It only replaces the default message:
+/// guards that were given a message or an error keep it. The abort
+/// strategy, which reports where the guard was defined, and the const
+/// strategy, whose message is fixed at compile time, keep their own.
+///
+/// The formatter can be set once. Later calls return it back as the
+/// error and leave the first one in place, which makes it safe to call
+/// from several threads. Set it at the start of `main` so that no guard
+/// fires before it is in place. It runs on the thread that dropped the
+/// value, inside the `Drop` implementation, so it should not panic.
+///
+/// ```should_panic
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     prevent_drop::set_leak_message_formatter(|name| format!("leaked a {}", name)).unwrap();
+///     drop(Resource); // Panics with "leaked a Resource incident=000001".
+/// }
+/// ```
+#[inline]
+pub fn set_leak_message_formatter(
+    formatter: fn(&'static str) -> String,
+) -> Result<(), fn(&'static str) -> String> {
+    FORMATTER.set(formatter)
+}
+
+/// The message a guard reports for a leak of `type_name` when it
+/// was not given a message and no formatter is set.
+///
+/// ```
+/// assert_eq!(
+///     prevent_drop::default_leak_message("Resource"),
+///     "Forgot to explicitly drop an instance of Resource."
+/// );
+/// ```
+#[inline]
+pub fn default_leak_message(type_name: &str) -> String {
+    ::rt::DefaultLeakMessage(type_name).to_string()
+}
+
+/// The message for a leak of `type_name`, from the formatter if one is
+/// set.
+#[doc(hidden)]
+#[inline]
+pub fn leak_message(type_name: &'static str) -> String {
+    match FORMATTER.get() {
+        Some(formatter) => formatter(type_name),
+        None => default_leak_message(type_name),
+    }
+}
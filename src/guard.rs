@@ -0,0 +1,79 @@
+//! A scoped guard that catches early exits from a function.
+
+use core::ops::{Deref, DerefMut};
+use rt;
+
+/// Borrow a slot for the rest of a scope and trigger the drop guard if
+/// the scope is left without calling `disarm`.
+///
+/// Use it in functions with a lot of control flow. Create the guard at
+/// the start and disarm it on the happy path. An early `return` or an
+/// error propagated with `?` drops the guard and is reported like any
+/// other accidental drop. It uses the strategy selected through the
+/// features, just like `prevent_drop!`.
+///
+/// ```
+/// use prevent_drop::DropGuard;
+///
+/// let mut slot = Some(String::from("value"));
+/// let guard = DropGuard::new(&mut slot);
+/// // Nothing here may return early.
+/// let slot = guard.disarm();
+/// assert_eq!(slot.take().as_deref(), Some("value"));
+/// ```
+pub struct DropGuard<'a, T: 'a> {
+    slot: &'a mut Option<T>,
+}
+
+impl<'a, T> DropGuard<'a, T> {
+    /// Guard `slot` until `disarm` is called.
+    #[inline]
+    pub fn new(slot: &'a mut Option<T>) -> Self {
+        DropGuard { slot }
+    }
+
+    /// Consume the guard without triggering it and give the slot back.
+    #[inline]
+    pub fn disarm(self) -> &'a mut Option<T> {
+        rt::take_field(self, |this| &this.slot)
+    }
+}
+
+impl<'a, T> Deref for DropGuard<'a, T> {
+    type Target = Option<T>;
+
+    #[inline]
+    fn deref(&self) -> &Option<T> {
+        self.slot
+    }
+}
+
+impl<'a, T> DerefMut for DropGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Option<T> {
+        self.slot
+    }
+}
+
+prevent_drop_wrapper!(
+    DropGuard<'a, T>,
+    prevent_drop_DropGuard,
+    generics = ['a, T],
+    "The `DropGuard` requires you to enable optimizations or to enable one of the `abort`, `panic` or `auto_fallback` features."
+);
+
+#[cfg(test)]
+mod tests {
+    use super::DropGuard;
+
+    // The control flow tests live in `tests/wrappers.rs`, where the guard
+    // panics in every build.
+    #[test]
+    fn disarm_returns_slot() {
+        let mut slot = Some(1u32);
+        let mut guard = DropGuard::new(&mut slot);
+        *guard = Some(2);
+        let slot = guard.disarm();
+        assert_eq!(slot.take(), Some(2));
+    }
+}
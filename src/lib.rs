@@ -77,6 +77,12 @@
 //! }
 //! ```
 //!
+//! On Rust 2018 and later you can also import the macros by path, e.g.
+//! `use prevent_drop::prevent_drop;`, instead of using `#[macro_use]`.
+//! The macros refer to each other through `$crate`, so importing only
+//! the one you call is enough, and they expand the same way in every
+//! edition.
+//!
 //! ## Configuration
 //!
 //! By default, `prevent_drop` only works when optimizations are
@@ -99,6 +105,29 @@
 //! incremental = false
 //! ```
 //!
+//! Optimizing for size with `opt-level = "s"` or `opt-level = "z"`
+//! elides the drop calls as well and counts as having optimizations
+//! enabled.
+//!
+//! The strategy is chosen from the opt-level `prevent_drop` itself is
+//! built with, while the drops are elided in your crate, so keep the two
+//! the same. A `[profile.dev.package.prevent_drop]` override that only
+//! changes `prevent_drop` can select the link strategy for a crate that
+//! is not optimized. When a build script or proc macro uses
+//! `prevent_drop`, both are built with the `build-override` profile,
+//! which does not optimize by default. Raise its opt-level or enable one
+//! of the features below to use `prevent_drop!` there.
+//!
+//! ```ignore
+//! [profile.dev.build-override]
+//! opt-level = 1
+//! ```
+//!
+//! The build script passes the opt-level on as a cfg such as
+//! `prevent_drop_opt_level="2"`, and `prevent_drop::OPT_LEVEL` exposes it
+//! to other crates. Together with `prevent_drop::STRATEGY` you can
+//! mirror the choice `prevent_drop!` made in your own code.
+//!
 //! Alternatively, you can enable the either the `abort` or the `panic`
 //! feature. Like the names suggest this will make `prevent_drop!` use
 //! `prevent_drop_abort!` or `prevent_drop_panic!` respectively. To set
@@ -110,13 +139,278 @@
 //! features = ["panic"] # or "abort"
 //! ```
 //!
+//! If you want `prevent_drop!` to work without touching your profiles,
+//! enable the `auto_fallback` feature instead. With optimizations it
+//! uses the link strategy as usual. Without them it falls back to the
+//! panic strategy rather than failing to compile. Keep in mind that this
+//! changes a compile-time check into a run-time check for unoptimized
+//! builds: a drop that would not link in release only panics in debug
+//! when your tests actually reach it. The `abort` and `panic` features
+//! take precedence over `auto_fallback`.
+//!
+//! ```ignore
+//! [dependencies.prevent_drop]
+//! version = "..."
+//! features = ["auto_fallback"]
+//! ```
+//!
+//! To try another strategy without editing `Cargo.toml`, for example
+//! in a CI matrix, set the `PREVENT_DROP_FORCE_STRATEGY` environment
+//! variable to `link`, `abort` or `panic` while building. The variable
+//! takes precedence over the features, which take precedence over the
+//! opt-level. A forced link strategy is used even without optimizations.
+//!
+//! ```ignore
+//! PREVENT_DROP_FORCE_STRATEGY=panic cargo test
+//! ```
+//!
+//! If you want a compile-time check without optimizations, use
+//! `prevent_drop_const!`. It catches the same drops in debug builds, but
+//! reports values that are alive while calling something that might
+//! panic, because the drop on the unwinding path is never optimized
+//! away.
+//!
+//! Enable the `never_panic` feature to make sure `prevent_drop!` never
+//! picks the panic strategy. Wherever the `panic` feature, the
+//! environment variable or `auto_fallback` would select it, the abort
+//! strategy is used instead. This keeps guarded types usable from code
+//! that must not panic, such as functions checked with `#[no_panic]`.
+//! Neither the link strategy nor the abort strategy introduce a
+//! potential panic: the abort strategy reports and aborts from a
+//! function that cannot unwind, so even a message or hook that panics
+//! ends in an abort. A type that asks for `strategy = panic` explicitly,
+//! or uses `prevent_drop_panic!`, still panics. Without `std` the only
+//! way to abort is to panic, so `never_panic` enables the `std` feature.
+//!
+//! ```ignore
+//! [dependencies.prevent_drop]
+//! version = "..."
+//! features = ["panic", "never_panic"]
+//! ```
+//!
+//! To choose between aborting and panicking without rebuilding, enable
+//! the `runtime_strategy` feature instead of `abort` or `panic`.
+//! `prevent_drop!` then uses the panic strategy, and its guards abort
+//! when the `PREVENT_DROP_STRATEGY` environment variable is `abort` at
+//! run time. Any other value, or none, keeps them panicking. The
+//! variable is read when the first guard fires and cached in an atomic,
+//! so each leak afterwards costs one extra relaxed load and branch. The
+//! feature also makes `abort` and `panic` compatible and takes precedence
+//! over both.
+//!
+//! ```ignore
+//! PREVENT_DROP_STRATEGY=abort ./server
+//! ```
+//!
+//! ### Gradual rollout
+//!
+//! The `dry_run` feature makes every `prevent_drop!` invocation expand
+//! to an empty `Drop` impl, whatever the opt-level, the other features
+//! or the `strategy = ...` argument say. Use it to introduce
+//! `prevent_drop` to a large codebase in steps:
+//!
+//! 1. Enable `dry_run` and add `prevent_drop!` to the types you want to
+//!    guard. This changes no behavior, so it can land right away.
+//! 2. Write the explicit cleanup functions and call them where the
+//!    values go out of scope.
+//! 3. Disable `dry_run`, optionally together with enabling `panic` or
+//!    `auto_fallback`, and fix the drops your tests or the linker
+//!    report.
+//!
+//! The labels that the selected strategy would generate as functions,
+//! and the `message_fn`, keep their signatures but do nothing, so code
+//! that calls them compiles either way. The other macros, such as
+//! `prevent_drop_panic!`, are not affected, and `prevent_drop::STRATEGY`
+//! is `Strategy::DryRun`.
+//!
+//! ```ignore
+//! [dependencies.prevent_drop]
+//! version = "..."
+//! features = ["dry_run"]
+//! ```
+//!
+//! ### Miri
+//!
+//! Miri reports leaked memory by itself, which makes the guards
+//! redundant there, and the link strategy's undefined label or an abort
+//! gets in the way of a run that checks for leaks. The `miri_inert`
+//! feature makes `prevent_drop!` behave as with `dry_run` when the code
+//! runs under Miri (`cfg(miri)`) and changes nothing otherwise.
+//!
+//! ```ignore
+//! [dependencies.prevent_drop]
+//! version = "..."
+//! features = ["miri_inert"]
+//! ```
+//!
+//! The panic strategy stays silent when a value is dropped while the
+//! thread is already panicking. Enable the `note_skipped_leaks` feature
+//! to print a note to stderr for each of those drops instead.
+//!
 //! Review the documentation for the different prevent_drop strategies
 //! for advice on when to use which one.
+//!
+//! ## no_std
+//!
+//! `std` support is the default `std` feature, so disable default
+//! features to use `prevent_drop` in a `#![no_std]` crate. There is no
+//! `no_std` feature: Cargo unites the features that every dependent
+//! enables, so one crate turning on `no_std` would take `std` away from
+//! all the others. The default feature set used to be empty, so a build
+//! that already sets `default-features = false` now goes without `std`;
+//! add `features = ["std"]` to keep it.
+//!
+//! The link, abort, callback and log strategies are all available.
+//! Because `core` offers no stable way to abort, the abort strategy
+//! panics from a function that cannot unwind, which aborts after your
+//! panic handler has run. The panic strategy also works but cannot tell
+//! whether the thread is already panicking, so it always panics. The
+//! `never_panic`, `runtime_strategy`, `backtrace`, `metrics`, `serde`
+//! and `anyhow` features enable `std`.
+//!
+//! ```ignore
+//! [dependencies.prevent_drop]
+//! version = "..."
+//! default-features = false
+//! ```
+//!
+//! ## Minimum supported Rust version
+//!
+//! `prevent_drop` and `prevent_drop_derive` need Rust 1.88, the release
+//! that stabilized `proc_macro::Span::file`, which the derive uses to
+//! tell apart types defined at different places.
+//!
+//! ## Derive
+//!
+//! With the `derive` feature enabled, `#[derive(PreventDrop)]` writes
+//! the `prevent_drop!` invocation for you and generates a unique label.
+//! The strategy and message can be chosen per type.
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate prevent_drop;
+//!
+//! #[derive(PreventDrop)]
+//! #[prevent_drop(strategy = "panic", message = "Leaked a Resource.")]
+//! struct Resource;
+//! ```
+//!
+//! If you prefer to keep the guard on the type itself, import the
+//! `#[prevent_drop]` attribute from the `attr` module. It takes the same
+//! options. Because it shares its name with the `prevent_drop!` macro,
+//! don't import both into the same module.
+//!
+//! ```ignore
+//! extern crate prevent_drop;
+//!
+//! use prevent_drop::attr::prevent_drop;
+//!
+//! #[prevent_drop(strategy = "panic")]
+//! struct Resource;
+//! ```
 
 #![doc(html_root_url = "https://docs.rs/prevent_drop")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(missing_docs)]
 #![cfg_attr(test, deny(warnings))]
 
+#[cfg(any(feature = "std", test))]
+extern crate core;
+
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub extern crate log;
+
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub extern crate tracing;
+
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+pub extern crate metrics;
+
+#[cfg(feature = "libc")]
+extern crate libc;
+
+#[cfg(feature = "serde")]
+extern crate serde;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+#[cfg(feature = "anyhow")]
+extern crate anyhow;
+
+#[cfg(feature = "scopeguard")]
+extern crate scopeguard;
+
+#[cfg(feature = "derive")]
+extern crate prevent_drop_derive;
+
+#[cfg(feature = "derive")]
+pub use prevent_drop_derive::PreventDrop;
+
+/// The `#[prevent_drop]` attribute.
+///
+/// It lives in its own module because the attribute and the
+/// `prevent_drop!` macro share a name.
+#[cfg(feature = "derive")]
+pub mod attr {
+    pub use prevent_drop_derive::prevent_drop;
+}
+
+#[doc(hidden)]
+pub mod backtrace;
+
+#[doc(hidden)]
+pub mod rt;
+
+#[doc(hidden)]
+pub mod constant;
+
+/// Marker trait implemented by every strategy macro for the guarded type.
+///
+/// You should not implement this trait yourself. It exists so that
+/// `assert_prevent_drop!` can check that a guard is installed.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not guarded by prevent_drop",
+    label = "missing a prevent_drop guard",
+    note = "install a guard with one of the prevent_drop macros or #[derive(PreventDrop)]"
+)]
+pub trait PreventDropped {}
+
+/// Assert at compile time that each of the listed types is guarded by
+/// one of the strategy macros.
+///
+/// Use this to keep a list of types that must never lose their guard
+/// during a refactor. It expands to nothing at run time.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// assert_prevent_drop!(Resource);
+///
+/// fn main() {}
+/// ```
+#[macro_export]
+macro_rules! assert_prevent_drop {
+    ($($T:ty),+ $(,)*) => {
+        const _: () = {
+            fn assert_prevent_drop<T: ?Sized + $crate::PreventDropped>() {}
+
+            #[allow(dead_code)]
+            fn assert_prevent_drop_all() {
+                $(assert_prevent_drop::<$T>();)+
+            }
+        };
+    };
+}
+
 /// Implement Drop for a type that will not compile if it
 /// gets called.
 ///
@@ -128,20 +422,143 @@
 ///
 /// Since this is a compile-time check you cannot and need not test your
 /// code for potential drops as it will not compile.
-
+///
+/// The linker only reports the name of the missing symbol, which is the
+/// label. Prefer a descriptive label such as
+/// `PREVENT_DROP_you_forgot_to_consume_Resource` so the error explains
+/// itself. `#[derive(PreventDrop)]` generates labels like that for you.
+///
+/// The generated `Drop` implementation is the same for unit structs,
+/// tuple structs, structs with named fields and enums. No shape causes
+/// false positives by itself; they come from control flow, such as a call
+/// that may unwind while the value is alive. Because the type implements
+/// `Drop` you cannot move fields out by destructuring it, so read them
+/// out of a `ManuallyDrop` with `ptr::read` in your consuming function.
+///
+/// Generic types need their parameters repeated on the `Drop` impl.
+/// Pass them with `generics = [...]`, e.g.
+/// `prevent_drop_link!(Buffer<'a, T>, label, generics = ['a, T])`. The
+/// `extern` function is declared once and shared by all instantiations.
+///
+/// The generated `drop` is `#[inline]` by default. Put `inline = always`
+/// or `inline = never` right after the label, before `generics`, to
+/// choose the attribute yourself. `always` takes inlining out of the
+/// optimizer's hands, but elision still needs optimization to remove the
+/// dead call afterwards, so unoptimized builds fail to link either way.
+/// `never` keeps `drop` a separate function, so a drop is only elided
+/// where the optimizer can prove the call itself unreachable.
 #[macro_export]
 macro_rules! prevent_drop_link {
-    ($T:ty, $label:ident) => {
+    (@impl [$($inline:ident)?] $T:ty, $label:ident, [$($generics:tt)*]) => {
         extern "C" {
             fn $label();
         }
 
-        impl Drop for $T {
-            #[inline]
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline $(($inline))?]
             fn drop(&mut self) {
                 unsafe { $label() };
             }
-        }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_link!(@impl [] $T, $label, []);
+    };
+    ($T:ty, $label:ident, inline = $inline:ident $(, generics = [$($generics:tt)*])?) => {
+        $crate::prevent_drop_link!(@impl [$inline] $T, $label, [$($($generics)*)?]);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_link!(@impl [] $T, $label, [$($generics)*]);
+    };
+}
+
+/// Implement Drop for a type that will not compile if it gets called,
+/// even without optimizations.
+///
+/// The const strategy makes the `Drop` implementation evaluate a
+/// constant that fails to compile. The compiler only evaluates it when
+/// it generates code that drops the value, which it decides before
+/// optimizing. As a result this strategy works in debug builds without
+/// changing your profiles. The error names the type and is reported
+/// where the value is dropped, as ``the above error was encountered while
+/// instantiating `fn core::ptr::drop_in_place::<Resource>` ``.
+///
+/// The compiler keeps a drop of a value on the unwinding path of every
+/// call that might panic while the value is alive. Unlike the link
+/// strategy, optimizations cannot remove those drops, so consume values
+/// before calling anything that might panic, or build with `panic =
+/// "abort"`. The error is reported by `cargo build`, not by `cargo
+/// check`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// impl Resource {
+///     fn close(self) {
+///         let _zelf = std::mem::ManuallyDrop::new(self);
+///     }
+/// }
+///
+/// prevent_drop_const!(Resource);
+///
+/// fn main() {
+///     let r = Resource;
+///     r.close();
+/// }
+/// ```
+///
+/// Pass `generics = [...]` to guard a generic type and a message after
+/// the type, or after the generics, to replace the default message. The
+/// message has to be a string literal or a `concat!`. Unlike the other
+/// strategies this one does not take a label, because it declares no
+/// symbol.
+#[macro_export]
+macro_rules! prevent_drop_const {
+    ($T:ty) => {
+        $crate::prevent_drop_const!($T, generics = []);
+    };
+    ($T:ty, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_const!(
+            $T,
+            generics = [$($generics)*],
+            $crate::prevent_drop_default_message!($T)
+        );
+    };
+    ($T:ty, generics = [$($generics:tt)*], $msg:expr) => {
+        $crate::prevent_drop_impl!([$($generics)*] $crate::constant::ConstDropMessage, $T {
+            const MESSAGE: &'static str = $msg;
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $crate::constant::drop_error::<Self>();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $msg:expr) => {
+        $crate::prevent_drop_const!($T, generics = [], $msg);
+    };
+}
+
+// The default message of `rt::DefaultLeakMessage`, spelled out at compile
+// time for `prevent_drop_const!`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default_message {
+    ($T:ty) => {
+        $crate::rt::concat!(
+            "Forgot to explicitly drop an instance of ",
+            $crate::rt::stringify!($T),
+            "."
+        )
     };
 }
 
@@ -153,171 +570,5764 @@ macro_rules! prevent_drop_link {
 /// code or the binary. You can use it on a type if you guarantee that
 /// it will never be dropped but the compiler is unable to deduct this.
 ///
+/// Before aborting it prints the type and the location of the
+/// `prevent_drop_abort!` invocation to stderr, e.g. `prevent_drop: an
+/// instance of Resource (defined at src/resource.rs:12) was dropped`.
+/// Keep the invocation next to the type definition so the location
+/// points at it. With `#[derive(PreventDrop)]` it is the location of the
+/// type. Pass a message after the label, or after `generics = [...]`,
+/// to print that instead. Without `std` nothing is printed.
+///
 /// Since this is a run-time check you need to have proper tests to
-/// discover all potential drops.
-
+/// discover all potential drops. Enable the `backtrace` feature and set
+/// `RUST_BACKTRACE=1` to print where the value was dropped before
+/// aborting.
+///
+/// Pass a path to a `fn()` or `fn() -> !` last to run it before
+/// aborting, e.g. to flush logs or write a crash file. Because a hook is
+/// a path, a message has to be an expression that is not a plain path,
+/// such as a string literal. Mark the hook `#[inline(never)]` if you want it
+/// to show up in stack traces. If the hook returns, the process aborts.
+///
+/// The label is an ordinary Rust function, so the same label can be used
+/// in different modules and crates. To make it easy to spot in the
+/// binary, put `no_mangle` right after the label to export it as an
+/// unmangled symbol, which then has to be unique across every crate in
+/// the binary. Put `link_name = "..."` instead to export it under that
+/// name and keep the label as its Rust name, e.g. `link_name =
+/// "mycrate_prevent_drop_Resource"`. The same works for
+/// `prevent_drop_panic!` and every other strategy that takes a label,
+/// except the link strategy, whose label is always a symbol.
+///
+/// The label is `#[inline(never)]` so it shows up in stack traces, and
+/// `drop` is `#[inline]`. Put `inline = always` or `inline = never` right
+/// after the label to give both that attribute instead. Neither changes
+/// behavior; `always` trades the stack frame for less call overhead in
+/// optimized builds, while unoptimized builds only inline
+/// `#[inline(always)]` functions. A label exported with `no_mangle` or
+/// `link_name` is never inlined, whatever you pass. The same works for
+/// `prevent_drop_panic!`.
+///
+/// The label is a `pub fn $label()` that does exactly what a drop of the
+/// type does. You can call it from your own error paths to report the
+/// same message the same way. Put `message_fn = name` right after the
+/// label to also generate `pub fn name(out: &mut dyn fmt::Write) ->
+/// fmt::Result`, which writes the message without aborting. Both work
+/// the same way for `prevent_drop_panic!`.
+///
+/// With `std` the label function never unwinds: failing to write to
+/// stderr is ignored, and the report and hook run in an `extern "C"`
+/// function, so a panic in either aborts the process. That makes the
+/// abort strategy safe to use from `#[no_panic]` code. Without `std` it
+/// aborts by panicking from a function that cannot unwind.
 #[macro_export]
 macro_rules! prevent_drop_abort {
-    ($T:ty, $label:ident) => {
-        #[inline(never)]
-        #[no_mangle]
-        #[allow(non_snake_case, private_no_mangle_fns)]
+    (@parse $previous_label:tt [$($previous_drop:ident)? $(=> $($when:tt)*)?] [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, inline = $inline:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_abort!(@parse [$inline] [$inline $(=> $($when)*)?] [$($export)*] [$($message_fn)?] $T, $label $(, $($rest)*)?);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_abort!(@parse $label_inline $drop_inline [#[export_name = $name]] [$($message_fn)?] $T, $label $(, $($rest)*)?);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, no_mangle $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_abort!(@parse $label_inline $drop_inline [#[no_mangle]] [$($message_fn)?] $T, $label $(, $($rest)*)?);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($previous:ident)?] $T:ty, $label:ident, message_fn = $message_fn:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_abort!(@parse $label_inline $drop_inline [$($export)*] [$message_fn] $T, $label $(, $($rest)*)?);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident) => {
+        $crate::prevent_drop_abort!(@parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, generics = []);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_abort!(
+            @parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::concat!(
+                "prevent_drop: an instance of ",
+                $crate::rt::stringify!($T),
+                " (defined at ",
+                $crate::rt::file!(),
+                ":",
+                $crate::rt::line!(),
+                ") was dropped"
+            )
+        );
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*], $hook:path) => {
+        $crate::prevent_drop_abort!(
+            @parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::concat!(
+                "prevent_drop: an instance of ",
+                $crate::rt::stringify!($T),
+                " (defined at ",
+                $crate::rt::file!(),
+                ":",
+                $crate::rt::line!(),
+                ") was dropped"
+            ),
+            $hook
+        );
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr, $hook:path) => {
+        $crate::prevent_drop_abort!(@impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, [$($generics)*], ($msg), ($hook();));
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        $crate::prevent_drop_abort!(@impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, [$($generics)*], ($msg), ());
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, $hook:path) => {
+        $crate::prevent_drop_abort!(@parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, generics = [], $hook);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, $msg:expr, $hook:path) => {
+        $crate::prevent_drop_abort!(@impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, [], ($msg), ($hook();));
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_abort!(@impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, [], ($msg), ());
+    };
+    (@impl [$($label_inline:ident)?] [$($drop_inline:ident)? $(=> |$when:ident| $pred:expr)?] [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, [$($generics:tt)*], ($msg:expr), ($($hook:tt)*)) => {
+        #[inline $(($label_inline))?]
+        $($export)*
+        #[allow(non_snake_case, unreachable_code, unused_attributes)]
         pub fn $label() {
-            ::std::process::abort();
+            if $crate::allow::drop_allowed() {
+                return;
+            }
+
+            // A panic cannot unwind out of an `extern "C"` function, so the
+            // compiler knows that neither the label nor `drop` unwinds.
+            extern "C" fn leak() -> ! {
+                $crate::rt::register_leak_type($crate::rt::stringify!($T));
+                $crate::rt::report($msg);
+                $crate::backtrace::report();
+                $($hook)*
+                $crate::rt::abort();
+            }
+
+            leak()
         }
 
-        impl Drop for $T {
-            #[inline]
+        $crate::prevent_drop_abort!(@message_fn [$($message_fn)?] ("{}", $msg));
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline $(($drop_inline))?]
             fn drop(&mut self) {
+                $(
+                    let $when: &Self = &*self;
+                    if !$pred {
+                        return;
+                    }
+                )?
                 $label();
             }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    (@message_fn [] ($($message:tt)*)) => {};
+    (@message_fn [$message_fn:ident] ($($message:tt)*)) => {
+        /// Write the message the drop guard reports.
+        #[allow(non_snake_case)]
+        pub fn $message_fn(out: &mut dyn $crate::rt::Write) -> $crate::rt::fmt::Result {
+            $crate::rt::Write::write_fmt(out, $crate::rt::format_args!($($message)*))
         }
     };
+    ($T:ty, $label:ident $($rest:tt)*) => {
+        $crate::prevent_drop_abort!(@parse [never] [] [] [] $T, $label $($rest)*);
+    };
 }
 
-/// Implement Drop for a type that will panic if it gets called.
+/// Implement Drop for a type that writes a fixed message to stderr with
+/// a raw `write(2)` and then aborts.
 ///
-/// The panic strategy panics with a customizable error message only if
-/// the thread is not already panicking. The reason for this is that
-/// usually the original panic is more informative. If we are already
-/// panicking, leaking some resources is not as important.
+/// This is the async-signal-safe counterpart of `prevent_drop_abort!`.
+/// Use it for types that can be dropped in a signal handler or another
+/// context where formatting, allocating or taking the stderr lock is not
+/// allowed. The label function does nothing but a `write(2)` to file
+/// descriptor 2 followed by `abort(3)`, both of which are
+/// async-signal-safe.
 ///
-/// Since this is a run-time check you need to have proper tests to
-/// discover all potential drops.
-
+/// The price is that the message has to be a string known at compile
+/// time, so it cannot include a backtrace, an incident ID or details of
+/// the value. A newline is appended to it. A hook is not supported
+/// because it could not be checked for signal safety, and neither is
+/// `allow_drop`, because reading its thread-local state is not
+/// async-signal-safe. The label is not exported unless you put
+/// `no_mangle` or `link_name = "..."` right after it. Requires the
+/// `libc` feature, which is only available on Unix.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_raw_abort!(Resource, prevent_drop_Resource, "Leaked a Resource.");
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Resource);
+/// }
+/// ```
+#[cfg(feature = "libc")]
 #[macro_export]
-macro_rules! prevent_drop_panic {
+macro_rules! prevent_drop_raw_abort {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_raw_abort!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_raw_abort!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
     ($T:ty, $label:ident) => {
-        prevent_drop_panic!(
+        $crate::prevent_drop_raw_abort!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_raw_abort!(
             $T,
             $label,
-            concat!(
-                "Forgot to explicitly drop an instance of ",
-                stringify!($T),
-                "."
+            generics = [$($generics)*],
+            $crate::rt::concat!(
+                "prevent_drop: an instance of ",
+                $crate::rt::stringify!($T),
+                " (defined at ",
+                $crate::rt::file!(),
+                ":",
+                $crate::rt::line!(),
+                ") was dropped"
             )
         );
     };
-    ($T:ty, $label:ident, $msg:expr) => {
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
         #[inline(never)]
-        #[no_mangle]
-        #[allow(non_snake_case, private_no_mangle_fns)]
+        #[allow(non_snake_case)]
         pub fn $label() {
-            if ::std::thread::panicking() == false {
-                panic!($msg);
-            }
+            $crate::rt::write_stderr($crate::rt::concat!($msg, "\n").as_bytes());
+            $crate::rt::raw_abort();
         }
 
-        impl Drop for $T {
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
             #[inline]
             fn drop(&mut self) {
                 $label();
             }
-        }
-    };
-}
+        });
 
-#[cfg(all(not(feature = "abort"), not(feature = "panic"), opt_level_gt_0))]
-#[macro_export]
-macro_rules! prevent_drop {
-    ($T:ty, $label:ident) => {
-        prevent_drop_link!($T, $label);
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
     };
     ($T:ty, $label:ident, $msg:expr) => {
-        prevent_drop!($T, $label);
+        $crate::prevent_drop_raw_abort!($T, $label, generics = [], $msg);
     };
 }
 
-#[cfg(all(not(feature = "abort"), not(feature = "panic"), not(opt_level_gt_0)))]
-#[macro_export]
-
-/// Implement Drop for a type so that instances of it cannot
-/// be dropped.
+/// Implement Drop for a type that will panic if it gets called.
 ///
-/// By default, this macro redirects to `prevent_drop_link`. If the
-/// `abort` feature is enabled it will redirect to `prevent_drop_abort.
-/// If the `panic` feature is enabled it will redirect to
-/// `prevent_drop_panic`.
-
-macro_rules! prevent_drop {
-    ($T:ty, $label:ident) => {
-        compile_error!("The `prevent_drop!` macro requires you to enable optimizations or to enable either the `abort` or the `panic` feature.");
-    };
-    ($T:ty, $label:ident, $msg:expr) => {
-        prevent_drop!($T, $label);
-    };
-}
-
-#[cfg(all(feature = "abort", not(feature = "panic")))]
+/// The panic strategy panics with a customizable error message only if
+/// the thread is not already panicking. The reason for this is that
+/// usually the original panic is more informative. If we are already
+/// panicking, leaking some resources is not as important.
+/// Enable the `note_skipped_leaks` feature to print a line to stderr
+/// for every drop that was ignored this way.
+///
+/// Every panic message ends with an incident ID, e.g. `Forgot to
+/// explicitly drop an instance of Resource. incident=000042`. The IDs
+/// count up from 1 and are unique within the process, so you can find
+/// the panic that belongs to an alert in your logs.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+///
+/// The panic location reported is the `prevent_drop_panic!` invocation,
+/// not the scope that dropped the value. Implicit drops go through the
+/// compiler generated `drop_in_place`, which does not forward caller
+/// locations, so `#[track_caller]` on `Drop::drop` would only point into
+/// `core::ptr`. With the `backtrace` feature enabled and `RUST_BACKTRACE`
+/// set, a backtrace of the drop is printed to stderr before panicking.
+///
+/// To change the default message of every guard at once, call
+/// `set_leak_message_formatter` at the start of your program.
+///
+/// With the `runtime_strategy` feature the guard aborts instead, like
+/// `prevent_drop_abort!`, when the `PREVENT_DROP_STRATEGY` environment
+/// variable is `abort`.
+///
+/// Instead of a message you can pass `error = make_error`, where
+/// `make_error` is a `fn() -> E` and `E: Debug`. The error is constructed
+/// when the guard fires and its debug output is appended to the message
+/// of `default_leak_message`, which a formatter set with
+/// `set_leak_message_formatter` does not replace. This keeps the
+/// diagnostic in line with the error your explicit drop would have
+/// returned.
+///
+/// Like the other strategies, generic types can pass their parameters
+/// with `generics = [...]`, optionally followed by a message or error.
+///
+/// To put details of the leaked value in the message, pass `|this|`
+/// followed by an expression instead of a message. `this` is bound to
+/// a reference to the value and the expression has to implement
+/// `Display`. It is only evaluated when the guard panics.
+///
+/// ```should_panic
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Fd(i32);
+///
+/// prevent_drop_panic!(Fd, prevent_drop_Fd, |this| format!("leaked Fd fd={}", this.0));
+///
+/// fn main() {
+///     drop(Fd(3)); // Panics with "leaked Fd fd=3 incident=000001".
+/// }
+/// ```
+///
+/// The label is a `pub fn $label()` that panics like a drop of the type
+/// would, or `pub fn $label(message: &dyn Display)` with `|this|`. Put
+/// `message_fn = name` right after the label to also generate `pub fn
+/// name(out: &mut dyn fmt::Write) -> fmt::Result`, which writes the
+/// message, without the incident ID, instead of panicking. It cannot be
+/// combined with `|this|`.
+///
+/// The label is not exported by default. Put `no_mangle` right after the
+/// label to export it as an unmangled symbol, or `link_name = "..."` to
+/// export it under a namespaced symbol instead of the label itself.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_panic!(
+///     Resource,
+///     prevent_drop_Resource,
+///     link_name = "mycrate_prevent_drop_Resource"
+/// );
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Resource);
+/// }
+/// ```
 #[macro_export]
-macro_rules! prevent_drop {
-    ($T:ty, $label:ident) => {
-        prevent_drop_abort!($T, $label);
+macro_rules! prevent_drop_panic {
+    (@parse $previous_label:tt [$($previous_drop:ident)? $(=> $($when:tt)*)?] [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, inline = $inline:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_panic!(@parse [$inline] [$inline $(=> $($when)*)?] [$($export)*] [$($message_fn)?] $T, $label $(, $($rest)*)?);
     };
-    ($T:ty, $label:ident, $msg:expr) => {
-        prevent_drop!($T, $label);
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [#[export_name = $name]] [$($message_fn)?] $T, $label $(, $($rest)*)?);
     };
-}
-
-#[cfg(all(not(feature = "abort"), feature = "panic"))]
-#[macro_export]
-macro_rules! prevent_drop {
-    ($T:ty, $label:ident) => {
-        prevent_drop_panic!($T, $label);
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, no_mangle $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [#[no_mangle]] [$($message_fn)?] $T, $label $(, $($rest)*)?);
     };
-    ($T:ty, $label:ident, $msg:expr) => {
-        prevent_drop_panic!($T, $label, $msg);
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($previous:ident)?] $T:ty, $label:ident, message_fn = $message_fn:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [$($export)*] [$message_fn] $T, $label $(, $($rest)*)?);
     };
-}
-
-#[cfg(all(feature = "abort", feature = "panic"))]
-compile_error!("You cannot use both the abort and the panic strategies at the same time. Choose one or the other.");
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, generics = []);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_panic!(
+            @impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T,
+            $label,
+            [$($generics)*],
+            (
+                "{}",
+                $crate::rt::leak_message($crate::rt::stringify!($T))
+            )
+        );
+    };
+    (@parse [$($label_inline:ident)?] [$($drop_inline:ident)? $(=> |$when:ident| $pred:expr)?] [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*], |$this:ident| $describe:expr) => {
+        $crate::prevent_drop_panic!(@message_fn [$($message_fn)?] describe);
+
+        #[inline $(($label_inline))?]
+        $($export)*
+        #[allow(non_snake_case, unused_attributes)]
+        pub fn $label(message: &dyn $crate::rt::Display) {
+            $crate::backtrace::report();
+            if $crate::rt::runtime_aborts() {
+                $crate::rt::report(message);
+                $crate::rt::abort();
+            }
+            $crate::rt::panic!("{} incident={:06}", message, $crate::rt::next_incident());
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline $(($drop_inline))?]
+            fn drop(&mut self) {
+                $(
+                    let $when: &Self = &*self;
+                    if !$pred {
+                        return;
+                    }
+                )?
+                if $crate::allow::drop_allowed() {
+                    return;
+                }
+                $crate::rt::register_leak_type($crate::rt::stringify!($T));
+                if $crate::rt::runtime_aborts() || !$crate::rt::panicking() {
+                    let $this: &Self = &*self;
+                    $label(&$describe);
+                } else {
+                    $crate::rt::note_skipped_leak($crate::rt::stringify!($T));
+                }
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*], error = $error:expr) => {
+        $crate::prevent_drop_panic!(
+            @impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T,
+            $label,
+            [$($generics)*],
+            (
+                "{} {:?}",
+                $crate::rt::DefaultLeakMessage($crate::rt::stringify!($T)),
+                ($error)()
+            )
+        );
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        $crate::prevent_drop_panic!(@impl $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, [$($generics)*], ("{}", $msg));
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, error = $error:expr) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, generics = [], error = $error);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, |$this:ident| $describe:expr) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, generics = [], |$this| $describe);
+    };
+    (@parse $label_inline:tt $drop_inline:tt [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_panic!(@parse $label_inline $drop_inline [$($export)*] [$($message_fn)?] $T, $label, generics = [], $msg);
+    };
+    (@impl [$($label_inline:ident)?] [$($drop_inline:ident)? $(=> |$when:ident| $pred:expr)?] [$($export:tt)*] [$($message_fn:ident)?] $T:ty, $label:ident, [$($generics:tt)*], ($($panic:tt)*)) => {
+        #[inline $(($label_inline))?]
+        $($export)*
+        #[allow(non_snake_case, unused_attributes)]
+        pub fn $label() {
+            if $crate::allow::drop_allowed() {
+                return;
+            }
+            $crate::rt::register_leak_type($crate::rt::stringify!($T));
+            if $crate::rt::runtime_aborts() {
+                $crate::rt::report($crate::rt::format_args!($($panic)*));
+                $crate::backtrace::report();
+                $crate::rt::abort();
+            }
+            if !$crate::rt::panicking() {
+                $crate::backtrace::report();
+                $crate::rt::panic!(
+                    "{} incident={:06}",
+                    $crate::rt::format_args!($($panic)*),
+                    $crate::rt::next_incident()
+                );
+            } else {
+                $crate::rt::note_skipped_leak($crate::rt::stringify!($T));
+            }
+        }
+
+        $crate::prevent_drop_panic!(@message_fn [$($message_fn)?] ($($panic)*));
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline $(($drop_inline))?]
+            fn drop(&mut self) {
+                $(
+                    let $when: &Self = &*self;
+                    if !$pred {
+                        return;
+                    }
+                )?
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    (@message_fn [] describe) => {};
+    (@message_fn [$message_fn:ident] describe) => {
+        $crate::rt::compile_error!("message_fn cannot be combined with a `|this|` message");
+    };
+    (@message_fn [] ($($message:tt)*)) => {};
+    (@message_fn [$message_fn:ident] ($($message:tt)*)) => {
+        /// Write the message the drop guard reports.
+        #[allow(non_snake_case)]
+        pub fn $message_fn(out: &mut dyn $crate::rt::Write) -> $crate::rt::fmt::Result {
+            $crate::rt::Write::write_fmt(out, $crate::rt::format_args!($($message)*))
+        }
+    };
+    ($T:ty, $label:ident $($rest:tt)*) => {
+        $crate::prevent_drop_panic!(@parse [never] [] [] [] $T, $label $($rest)*);
+    };
+}
+
+/// Implement Drop for a type that will send a `LeakEvent` over a channel
+/// if it gets called.
+///
+/// The channel strategy sends the event over the channel set with
+/// `set_leak_channel` and then returns normally, leaking whatever the
+/// value was supposed to clean up. Sending never blocks, and a leak is
+/// ignored while no channel is set or after the receiver is gone. This
+/// leaves handling the leak to a monitoring thread. The label is not
+/// exported unless you put `no_mangle` or `link_name = "..."` right after
+/// it. Requires the `serde` feature.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! prevent_drop_channel {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_channel!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_channel!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_channel!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_channel!(
+            $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::leak_message($crate::rt::stringify!($T))
+        );
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::event::send($crate::rt::stringify!($T), $msg);
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_channel!($T, $label, generics = [], $msg);
+    };
+}
+
+/// Implement Drop for a type that will log an error if it gets called.
+///
+/// The log strategy emits an error through the `log` crate and then
+/// returns normally, leaking whatever the value was supposed to clean
+/// up. This suits long-running programs where leaking a resource is bad
+/// but crashing is worse. Requires the `log` feature.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+#[cfg(feature = "log")]
+#[macro_export]
+macro_rules! prevent_drop_log {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_log!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_log!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_log!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_log!(
+            $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::leak_message($crate::rt::stringify!($T))
+        );
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                let message = $msg;
+                $crate::rt::register_leak_type($crate::rt::stringify!($T));
+                $crate::rt::leak_event($crate::rt::stringify!($T), &message);
+                $crate::log::error!("{}", message);
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_log!($T, $label, generics = [], $msg);
+    };
+}
+
+/// Implement Drop for a type that will print an error to stderr if it
+/// gets called.
+///
+/// The eprintln strategy writes a message to stderr and then returns
+/// normally, leaking whatever the value was supposed to clean up. It sits
+/// between the log strategy and the panic strategy: the drop is visible
+/// without crashing and without a logging framework, which suits command
+/// line tools. The label function is never inlined, so you can set a
+/// breakpoint on it. Without `std` nothing is printed.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_eprintln!(Resource, prevent_drop_Resource, "Resource was not closed.");
+///
+/// fn main() {
+///     drop(Resource);
+///     println!("still running");
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_eprintln {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_eprintln!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_eprintln!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_eprintln!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_eprintln!(
+            $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::leak_message($crate::rt::stringify!($T))
+        );
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::rt::report($msg);
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_eprintln!($T, $label, generics = [], $msg);
+    };
+}
+
+/// Implement Drop for a type that prints a warning to stderr the first
+/// time an instance of it is dropped.
+///
+/// The deprecated strategy is meant for migrating a type from "may be
+/// dropped" to "must be consumed". Guard the type with it first, so
+/// existing code keeps working while every place that still drops the
+/// type shows up in the output, then switch to a stricter strategy once
+/// the warnings are gone. To keep the output readable the warning is
+/// printed only once per type, no matter how often the type is dropped.
+/// Generic types share a single warning for all instantiations. Pass a
+/// message after the label, or after `generics = [...]`, to print that
+/// instead of the default. Without `std` nothing is printed.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_deprecated!(Resource, prevent_drop_Resource, "Close a Resource instead of dropping it.");
+///
+/// fn main() {
+///     drop(Resource); // Prints the warning.
+///     drop(Resource); // Prints nothing.
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_deprecated {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_deprecated!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_deprecated!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_deprecated!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_deprecated!(
+            $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::concat!(
+                "warning: dropping an instance of ",
+                $crate::rt::stringify!($T),
+                " is deprecated, consume it explicitly instead"
+            )
+        );
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            static WARNED: $crate::rt::AtomicBool = $crate::rt::AtomicBool::new(false);
+
+            if !$crate::allow::drop_allowed() {
+                $crate::rt::warn_once(&WARNED, $msg);
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_deprecated!($T, $label, generics = [], $msg);
+    };
+}
+
+/// Implement Drop for a type that will emit a `tracing` error event if
+/// it gets called.
+///
+/// The trace strategy emits a `tracing::error!` event and then returns
+/// normally, like the log strategy. The event is recorded inside the
+/// current span, so your subscriber can tell which request leaked the
+/// value. It carries the type name in the `type_name` field and the
+/// message, which you can customize, as the event message. The event is
+/// emitted even if the thread is already panicking. Requires the
+/// `tracing` feature.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! prevent_drop_trace {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_trace!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_trace!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_trace!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_trace!(
+            $T,
+            $label,
+            generics = [$($generics)*],
+            $crate::rt::leak_message($crate::rt::stringify!($T))
+        );
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $msg:expr) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::tracing::error!(type_name = $crate::rt::stringify!($T), "{}", $msg);
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_trace!($T, $label, generics = [], $msg);
+    };
+}
+
+/// Implement Drop for a type that will increment a `metrics` counter if
+/// it gets called.
+///
+/// The metric strategy increments the `prevent_drop.leaks` counter with
+/// a `type` label set to the type name and then returns normally, like
+/// the log strategy. Export the counter with your usual recorder to
+/// watch leaks on a dashboard instead of crashing. Requires the
+/// `metrics` feature.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+#[cfg(feature = "metrics")]
+#[macro_export]
+macro_rules! prevent_drop_metric {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_metric!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_metric!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_metric!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::metrics::counter!("prevent_drop.leaks", "type" => $crate::rt::stringify!($T))
+                    .increment(1);
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+}
+
+/// Implement Drop for a type that will call a user function if it gets
+/// called.
+///
+/// The callback strategy calls `$callback`, a path to a `fn()`, and then
+/// returns normally. Use it to run your own telemetry, such as
+/// incrementing a metric or sending a message to a channel, when a value
+/// is dropped by accident.
+///
+/// The callback does not get access to the value being dropped. This
+/// keeps a single callback usable for many types and the label function
+/// non-generic. A value that needs cleanup should be consumed
+/// explicitly instead of having the guard patch things up.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+#[macro_export]
+macro_rules! prevent_drop_callback {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_callback!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_callback!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $callback:path) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $callback();
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, $callback:path) => {
+        $crate::prevent_drop_callback!($T, $label, generics = [], $callback);
+    };
+}
+
+/// Implement Drop for a type that will count the drop if it gets
+/// called.
+///
+/// The count strategy increments a process-global counter and then
+/// returns normally, leaking whatever the value was supposed to clean
+/// up. Read the counter with `leak_count` and clear it with
+/// `reset_leak_count`. This suits fuzzing and soak tests that run a
+/// workload and then assert that nothing leaked. The counter is shared
+/// by all types guarded with this strategy. To attribute the leaks, read
+/// the count of a single type with `leak_count_for` or of all types with
+/// `leak_counts`.
+///
+/// Since this is a run-time check you need to have proper tests to
+/// discover all potential drops.
+#[macro_export]
+macro_rules! prevent_drop_count {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_count!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_count!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_count!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::count::record_leak($crate::rt::stringify!($T));
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+}
+
+/// Implement Drop for a type that will record the drop in a
+/// thread-local log if it gets called.
+///
+/// The record strategy pushes the type name onto a log of the current
+/// thread and then returns normally, leaking whatever the value was
+/// supposed to clean up. Take the log with `take_recorded_leaks` to
+/// assert that the code under test leaked nothing, without catching
+/// panics or spawning a subprocess. Requires `std`.
+#[cfg(any(feature = "std", test))]
+#[macro_export]
+macro_rules! prevent_drop_record {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_record!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_record!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_record!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::record::record_leak($crate::rt::stringify!($T));
+                $crate::rt::leak_event(
+                    $crate::rt::stringify!($T),
+                    $crate::rt::leak_message($crate::rt::stringify!($T)),
+                );
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+}
+
+/// Implement Drop for a type that has to be consumed before the
+/// shutdown deadline.
+///
+/// During shutdown some values are legitimately dropped late, but a
+/// value that outlives the shutdown is a teardown ordering bug. The
+/// guard fires only if the deadline set with `set_shutdown_deadline`
+/// has passed, and does nothing while no deadline is set. It is built on
+/// `prevent_drop_if!` and takes the same arguments after the label,
+/// such as `strategy = panic` or `strategy = abort`, `generics = [...]`
+/// and a message. Requires `std`.
+///
+/// ```should_panic
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::time::Instant;
+///
+/// struct Connection;
+///
+/// prevent_drop_timeout!(Connection, prevent_drop_Connection);
+///
+/// fn main() {
+///     drop(Connection); // Fine, shutdown has not started.
+///     prevent_drop::set_shutdown_deadline(Instant::now());
+///     drop(Connection); // Panics.
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+#[macro_export]
+macro_rules! prevent_drop_timeout {
+    ($T:ty, $label:ident $($rest:tt)*) => {
+        $crate::prevent_drop_if!($T, $label, |_this| $crate::shutdown::deadline_passed() $($rest)*);
+    };
+}
+
+/// Implement Drop for a type that will remember the leak in a global
+/// ring buffer if it gets called.
+///
+/// The recent strategy stores the type name and a source location in a
+/// fixed-size buffer and then returns normally, leaking whatever the
+/// value was supposed to clean up. Read the last leaks at any time with
+/// `recent_leaks`, e.g. from a status page or a post-mortem dump of a
+/// server, without paying for logging. Recording takes a lock but never
+/// allocates.
+///
+/// The location does not tell you where the value leaked. Implicit drops
+/// go through the compiler generated `drop_in_place`, which does not
+/// forward caller locations, so they all record the
+/// `prevent_drop_recent!` invocation. Only a direct call of the
+/// `#[track_caller]` label, e.g. from your own error path, records the
+/// location of that call. The label is not exported unless you put
+/// `no_mangle` or `link_name = "..."` right after it. Requires `std`.
+#[cfg(any(feature = "std", test))]
+#[macro_export]
+macro_rules! prevent_drop_recent {
+    ($T:ty, $label:ident, no_mangle $($rest:tt)*) => {
+        $crate::prevent_drop_recent!($T, $label $($rest)*);
+        $crate::prevent_drop_export!($label, $crate::rt::stringify!($label));
+    };
+    ($T:ty, $label:ident, link_name = $name:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_recent!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop_export!($label, $name);
+    };
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_recent!($T, $label, generics = []);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        #[inline(never)]
+        #[allow(non_snake_case)]
+        #[track_caller]
+        pub fn $label() {
+            if !$crate::allow::drop_allowed() {
+                $crate::recent::record_leak($crate::rt::stringify!($T), $crate::rt::Location::caller());
+            }
+        }
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                $label();
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+}
+
+/// Implement Drop for a type that may be dropped a limited number of
+/// times before it panics.
+///
+/// The once strategy lets the first drop of any instance of the type
+/// pass silently and panics on every drop after that, like
+/// `prevent_drop_panic!`. Use it to model a controlled teardown where a
+/// value is normally consumed explicitly but one instance may be dropped
+/// during shutdown. Pass `allow = n` after the label to allow `n` drops
+/// instead.
+///
+/// The label names the counter of the type. It is shared by all
+/// instances and, for generic types, by all instantiations. Restore the
+/// allowance with `reset_drop_allowance::<T>()`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Runtime;
+///
+/// prevent_drop_once!(Runtime, prevent_drop_Runtime);
+///
+/// fn main() {
+///     // Shutdown drops the runtime once.
+///     drop(Runtime);
+///     prevent_drop::reset_drop_allowance::<Runtime>();
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_once {
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_once!($T, $label, allow = 1, generics = []);
+    };
+    ($T:ty, $label:ident, allow = $allow:expr) => {
+        $crate::prevent_drop_once!($T, $label, allow = $allow, generics = []);
+    };
+    ($T:ty, $label:ident, allow = $allow:expr, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_once!(
+            $T,
+            $label,
+            allow = $allow,
+            generics = [$($generics)*],
+            $crate::rt::leak_message($crate::rt::stringify!($T))
+        );
+    };
+    ($T:ty, $label:ident, allow = $allow:expr, generics = [$($generics:tt)*], $msg:expr) => {
+        #[allow(non_upper_case_globals)]
+        static $label: $crate::rt::AtomicUsize = $crate::rt::AtomicUsize::new(0);
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T {
+            #[inline]
+            fn drop(&mut self) {
+                if !$crate::allow::drop_allowed()
+                    && $crate::once::exceeds_allowance(&$label, $allow)
+                    && !$crate::rt::panicking()
+                {
+                    $crate::backtrace::report();
+                    $crate::rt::panic!("{}", $msg);
+                }
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::DropAllowance, $T {
+            fn drop_counter() -> &'static $crate::rt::AtomicUsize {
+                &$label
+            }
+        });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    ($T:ty, $label:ident, allow = $allow:expr, $msg:expr) => {
+        $crate::prevent_drop_once!($T, $label, allow = $allow, generics = [], $msg);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*] $($rest:tt)*) => {
+        $crate::prevent_drop_once!($T, $label, allow = 1, generics = [$($generics)*] $($rest)*);
+    };
+    ($T:ty, $label:ident, $msg:expr) => {
+        $crate::prevent_drop_once!($T, $label, allow = 1, generics = [], $msg);
+    };
+}
+
+/// Implement Drop for a type that only has to be consumed while a
+/// predicate holds.
+///
+/// Some types only own something some of the time, like a handle that
+/// can be empty. Pass `|this|` followed by a `bool` expression, where
+/// `this` is bound to a reference to the value being dropped. The guard
+/// fires only if the expression is true, so dropping an empty value is
+/// fine.
+///
+/// This is a run-time strategy: the predicate is evaluated on every
+/// drop and the link strategy cannot be used. The guard panics by
+/// default, or aborts with the `never_panic` feature. Put `strategy =
+/// panic` or `strategy = abort` after the predicate to pick one. The
+/// guard is generated by `prevent_drop_panic!` or `prevent_drop_abort!`,
+/// so everything those accept after the label can follow, such as
+/// `generics = [...]`, `no_mangle` or a message.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Handle(Option<u32>);
+///
+/// prevent_drop_if!(Handle, prevent_drop_Handle, |this| this.0.is_some());
+///
+/// fn main() {
+///     drop(Handle(None));
+///     let _ = std::mem::ManuallyDrop::new(Handle(Some(3)));
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_if {
+    ($T:ty, $label:ident, |$this:ident| $pred:expr, strategy = panic $($rest:tt)*) => {
+        $crate::prevent_drop_panic!(@parse [never] [=> |$this| $pred] [] [] $T, $label $($rest)*);
+    };
+    ($T:ty, $label:ident, |$this:ident| $pred:expr, strategy = abort $($rest:tt)*) => {
+        $crate::prevent_drop_abort!(@parse [never] [=> |$this| $pred] [] [] $T, $label $($rest)*);
+    };
+    ($T:ty, $label:ident, |$this:ident| $pred:expr, strategy = $strategy:ident $($rest:tt)*) => {
+        $crate::rt::compile_error!(
+            "prevent_drop_if! supports `strategy = panic` and `strategy = abort`"
+        );
+    };
+    ($T:ty, $label:ident, |$this:ident| $pred:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_if_default!(@parse [never] [=> |$this| $pred] [] [] $T, $label $(, $($rest)*)?);
+    };
+}
+
+#[cfg(not(feature = "never_panic"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_if_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_panic!($($args)*);
+    };
+}
+
+#[cfg(feature = "never_panic")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_if_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_abort!($($args)*);
+    };
+}
+
+/// Implement Drop for a type that has drop logic of its own.
+///
+/// A type can only have one `Drop` implementation, so invoking
+/// `prevent_drop!` for a type that already implements `Drop` fails with
+/// ``conflicting implementations of trait `Drop` ``. That error comes
+/// from the compiler and cannot be replaced by this crate; its note
+/// pointing into `prevent_drop!` is the hint. Move the body of your
+/// `drop` into this macro instead. It runs first, with `this` bound
+/// to `&mut Self`, and then the guard fires through the strategy
+/// `prevent_drop!` selects.
+///
+/// The body only runs on implicit drops. Explicitly dropping the value
+/// by wrapping it in `ManuallyDrop` skips both, so call the same logic
+/// from your explicit drop function if it should always run.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource {
+///     open: bool,
+/// }
+///
+/// prevent_drop_wrap!(Resource, prevent_drop_Resource, |this| {
+///     this.open = false;
+/// });
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Resource { open: true });
+/// }
+/// ```
+///
+/// Any arguments after the body, such as `strategy = panic`,
+/// `generics = [...]` or a message, are passed on to `prevent_drop!`.
+#[macro_export]
+macro_rules! prevent_drop_wrap {
+    ($T:ty, $label:ident, |$this:ident| $body:block $(, strategy = $strategy:ident)?) => {
+        $crate::prevent_drop_wrap!($T, $label, |$this| $body $(, strategy = $strategy)?, generics = []);
+    };
+    ($T:ty, $label:ident, |$this:ident| $body:block $(, strategy = $strategy:ident)?, generics = [$($generics:tt)*] $(, $($msg:tt)+)?) => {
+        $crate::prevent_drop!(
+            $T,
+            $label $(, strategy = $strategy)?,
+            generics = [$($generics)* => |$this| $body]
+            $(, $($msg)+)?
+        );
+    };
+    ($T:ty, $label:ident, |$this:ident| $body:block $(, strategy = $strategy:ident)?, $($msg:tt)+) => {
+        $crate::prevent_drop_wrap!($T, $label, |$this| $body $(, strategy = $strategy)?, generics = [], $($msg)+);
+    };
+}
+
+/// Implement Drop for a type by telling the optimizer that it is never
+/// called.
+///
+/// In release builds the `Drop` implementation calls
+/// `core::hint::unreachable_unchecked`. The optimizer may then assume
+/// that no value of the type is ever dropped and remove the drop glue,
+/// and any code that can only lead to a drop, entirely. This is the
+/// link strategy without the link error: it costs nothing on hot paths,
+/// but nothing checks your claim either. Requires the
+/// `unsafe_unreachable` feature.
+///
+/// # Safety
+///
+/// Dropping a value of the type in a build without `debug_assertions` is
+/// undefined behavior. That includes drops on the unwinding path of a
+/// panic while the value is alive, so this is only sound if every value
+/// is consumed on every path, including panics, or if the program is
+/// built with `panic = "abort"`. Undefined behavior is not guaranteed to
+/// crash. The program may continue with corrupted state or skip
+/// unrelated code.
+///
+/// To catch mistakes before they reach release, builds with
+/// `debug_assertions`, which include tests, expand to
+/// `prevent_drop_panic!` instead, and the remaining arguments are passed
+/// on to it. Run your tests in debug mode, and prefer the link strategy,
+/// which proves the same property at link time, wherever it works. In
+/// release builds the label and the `message_fn`, if any, do nothing.
+///
+/// The invocation has to start with `unsafe` to acknowledge the above.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// impl Resource {
+///     fn close(self) {
+///         let _zelf = std::mem::ManuallyDrop::new(self);
+///     }
+/// }
+///
+/// prevent_drop_assert_unreachable!(unsafe Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     Resource.close();
+/// }
+/// ```
+#[cfg(feature = "unsafe_unreachable")]
+#[macro_export]
+macro_rules! prevent_drop_assert_unreachable {
+    (unsafe $T:ty, $label:ident $($rest:tt)*) => {
+        #[cfg(debug_assertions)]
+        $crate::prevent_drop_panic!($T, $label $($rest)*);
+
+        #[cfg(not(debug_assertions))]
+        $crate::prevent_drop_assert_unreachable!(@release [$T] [$label] $($rest)*);
+    };
+    (@release [$T:ty] [$label:ident] $($rest:tt)*) => {
+        $crate::prevent_drop_inert!([$T] [$label] {
+            #[inline(always)]
+            fn drop(&mut self) {
+                // Safe because the invocation promised that values of this
+                // type are never dropped.
+                unsafe { $crate::rt::unreachable_unchecked() }
+            }
+        } $($rest)*);
+    };
+}
+
+/// Implement Drop for a type that is only checked when
+/// `debug_assertions` are enabled.
+///
+/// In debug builds and tests this expands to `prevent_drop_panic!`, or
+/// to `prevent_drop_abort!` if you put `strategy = abort` after the
+/// label. The remaining arguments are passed on to that macro. Without
+/// `debug_assertions` the `Drop` implementation is empty, so release
+/// builds pay nothing for the guard. The label and the `message_fn`, if
+/// any, are still generated, but do nothing either.
+///
+/// The trade-off is that a drop your tests did not exercise goes
+/// unnoticed in release. Whatever the explicit cleanup function should
+/// have done silently does not happen, so only use this for types where
+/// leaking is acceptable in production.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_debug_only!(Resource, prevent_drop_Resource, "Leaked a Resource.");
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Resource);
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_debug_only {
+    ($T:ty, $label:ident, strategy = abort $($rest:tt)*) => {
+        #[cfg(debug_assertions)]
+        $crate::prevent_drop_abort!($T, $label $($rest)*);
+
+        #[cfg(not(debug_assertions))]
+        $crate::prevent_drop_debug_only!(@release [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = panic $($rest:tt)*) => {
+        #[cfg(debug_assertions)]
+        $crate::prevent_drop_panic!($T, $label $($rest)*);
+
+        #[cfg(not(debug_assertions))]
+        $crate::prevent_drop_debug_only!(@release [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident $($rest:tt)*) => {
+        $crate::prevent_drop_debug_only!($T, $label, strategy = panic $($rest)*);
+    };
+    (@release [$T:ty] [$label:ident] $($rest:tt)*) => {
+        $crate::prevent_drop_inert!([$T] [$label] {
+            #[inline]
+            fn drop(&mut self) {}
+        } $($rest)*);
+    };
+}
+
+/// Implement Drop for a type so that instances of it cannot
+/// be dropped.
+///
+/// By default, this macro redirects to `prevent_drop_link`. If the
+/// `abort` feature is enabled it will redirect to `prevent_drop_abort`.
+/// If the `panic` feature is enabled it will redirect to
+/// `prevent_drop_panic`.
+///
+/// To pick a strategy for a single type regardless of the features, put
+/// `strategy = link`, `strategy = const`, `strategy = abort`, `strategy =
+/// raw_abort`, `strategy = panic`, `strategy = eprintln`, `strategy =
+/// deprecated`, `strategy = log`, `strategy = trace` or `strategy =
+/// metric` right after the label. The remaining arguments are
+/// passed on to the corresponding macro. Like the default, `strategy =
+/// link` requires optimizations.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop!(Resource, prevent_drop_Resource, strategy = panic, "Leaked a Resource.");
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Resource);
+/// }
+/// ```
+///
+/// To pick the strategy per platform or build, write `strategy = cfg {
+/// ... }` with a list of `predicate => strategy` branches. The first
+/// branch whose `cfg` predicate holds is used, so exactly one `Drop`
+/// implementation is generated. An optional final `_ => strategy`
+/// branch applies when none of the predicates hold. Without it the type
+/// is left unguarded in that case.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop!(
+///     Resource,
+///     prevent_drop_Resource,
+///     strategy = cfg { target_os = "linux" => abort, _ => panic }
+/// );
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Resource);
+/// }
+/// ```
+///
+/// All strategies accept `generics = [...]` after the label to guard a
+/// generic type, e.g. `prevent_drop!(Buffer<T>, label, generics = [T])`.
+/// Bounds go inside the brackets, as in `generics = [T: Clone]`, and so
+/// do const parameters, as in `prevent_drop!(Ring<N>, label, generics =
+/// [const N: usize])`.
+///
+/// Rust requires a `Drop` implementation to repeat the bounds of the
+/// type, including its where clause. Pass the where clause as `where =
+/// [...]` right after the generics, optionally followed by a message.
+/// `prevent_drop!` supports it for every strategy.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::collections::HashMap;
+/// use std::hash::Hash;
+///
+/// struct Cache<K, V>
+/// where
+///     K: Hash + Eq,
+/// {
+///     map: HashMap<K, V>,
+/// }
+///
+/// prevent_drop!(
+///     Cache<K, V>,
+///     prevent_drop_Cache,
+///     strategy = panic,
+///     generics = [K, V],
+///     where = [K: Hash + Eq]
+/// );
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(Cache::<u8, u8> { map: HashMap::new() });
+/// }
+/// ```
+///
+/// The run-time strategies define the label as an ordinary Rust
+/// function, so it only has to be unique within its module, unless it is
+/// exported with `no_mangle`. The link strategy uses the label as an
+/// unmangled symbol, so it has to be unique within the final binary.
+/// Reusing a label in the same module is reported as ``the name `label`
+/// is defined multiple times``. Reusing an unmangled label in another
+/// module of the same crate fails the build with ``symbol `label` is
+/// already defined``. Reusing it in another crate is only caught by the
+/// linker as a duplicate symbol. To rule collisions out, let
+/// `#[derive(PreventDrop)]` or the `#[prevent_drop]` attribute generate
+/// the label from the type name and a hash of its location.
+///
+/// The macro implements `Drop`, so it conflicts with a `Drop`
+/// implementation of your own. Use `prevent_drop_wrap!` to keep that
+/// logic and add the guard.
+///
+/// The type can be given by any path or type alias that names a local
+/// struct, enum or union, e.g. `prevent_drop!(crate::io::Handle, label)`.
+/// Rust does not allow implementing `Drop` through an associated type
+/// such as `<Foo as Bar>::Output`, so name the type it resolves to
+/// instead.
+///
+/// Put `defuse = name` right after the label to also generate an
+/// inherent method `name(self) -> ManuallyDrop<Self>`. Call it at the
+/// start of your explicit drop function instead of wrapping `self` in
+/// `ManuallyDrop` by hand.
+///
+/// Follow it with `defuse_style = forget` to generate `name(self)`, which
+/// forgets the value instead. That is simpler when the cleanup does not
+/// need the fields of `self`. The default, `defuse_style =
+/// manually_drop`, keeps them accessible through the returned
+/// `ManuallyDrop`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// impl Resource {
+///     fn close(self) {
+///         let zelf = self.disarm();
+///         // Perform cleanup.
+///     }
+/// }
+///
+/// prevent_drop!(Resource, prevent_drop_Resource, defuse = disarm, strategy = panic);
+///
+/// fn main() {
+///     Resource.close();
+/// }
+/// ```
+///
+/// To guard many types in one place, list them as `Type => label`
+/// pairs. Arguments for a single type, such as a strategy, go in
+/// parentheses after its label.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Socket;
+/// struct File;
+/// struct Buffer<T>(T);
+///
+/// prevent_drop! {
+///     Socket => prevent_drop_Socket,
+///     File => prevent_drop_File(strategy = panic, "Leaked a File."),
+///     Buffer<T> => prevent_drop_Buffer(strategy = panic, generics = [T]),
+/// }
+///
+/// fn main() {
+///     let _ = std::mem::ManuallyDrop::new(File);
+/// }
+/// ```
+///
+/// Put `leak_intentionally` right after the label to generate an
+/// inherent method `leak_intentionally(self)` that forgets the value.
+/// Neither the guard nor the destructors of its fields run. Use it when
+/// the cleanup happens elsewhere, so reviewers can tell the leak apart
+/// from an accidental one.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Handle(u32);
+///
+/// prevent_drop!(Handle, prevent_drop_Handle, leak_intentionally, strategy = panic);
+///
+/// fn main() {
+///     // The handle is owned by the process from here on.
+///     Handle(3).leak_intentionally();
+/// }
+/// ```
+///
+/// Put `with_cleanup` right after the label to generate an inherent
+/// method `with_cleanup(self, f) -> R`. It defuses the guard first and
+/// then calls `f` with the value, so the cleanup fits in one call. As
+/// with `prevent_drop_consume!`, `f` takes ownership of a
+/// `ManuallyDrop<Self>` so that it cannot trigger the guard again.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Handle(u32);
+///
+/// prevent_drop!(Handle, prevent_drop_Handle, with_cleanup, strategy = panic);
+///
+/// fn main() {
+///     let id = Handle(3).with_cleanup(|handle| {
+///         // Perform cleanup.
+///         handle.0
+///     });
+///     assert_eq!(id, 3);
+/// }
+/// ```
+///
+/// Guards with many arguments read better with named fields, which can
+/// come in any order. Only `ty` and `label` are required; every other
+/// field defaults to leaving the argument out. The fields are `ty`,
+/// `label`, `strategy`, `message`, `defuse`, `defuse_style`, `generics`,
+/// `where`, `inline`, which requires `strategy: link`, `abort` or
+/// `panic`, and `methods`, which lists `leak_intentionally` and
+/// `with_cleanup`. An unknown or repeated field is a compile error.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource<T>(T);
+///
+/// prevent_drop! {
+///     ty: Resource<T>,
+///     label: prevent_drop_Resource,
+///     strategy: panic,
+///     message: "Leaked a Resource.",
+///     generics: [T],
+///     defuse: disarm,
+/// }
+///
+/// fn main() {
+///     let _zelf = Resource(3).disarm();
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop {
+    (@config [] $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt) => {
+        $crate::rt::compile_error!("Missing the `ty` field.");
+    };
+    (@config $ty:tt [] $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt) => {
+        $crate::rt::compile_error!("Missing the `label` field.");
+    };
+    (@config $ty:tt $label:tt [] [$($style:tt)+] $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt) => {
+        $crate::rt::compile_error!("The `defuse_style` field requires a `defuse` field.");
+    };
+    (@config [$T:ty] [$label:ident] [$($defuse:tt)*] [$($style:tt)*] [$($methods:tt)*] [$($strategy:tt)*] [$($inline:tt)*] [$($generics:tt)*] [$($where:tt)*] [$($message:tt)*]) => {
+        $crate::prevent_drop!(@config_inline [$($strategy)*] [$($inline)*]);
+        $crate::prevent_drop!(
+            $T,
+            $label $($defuse)* $($style)* $($methods)* $($strategy)* $($inline)* $($generics)* $($where)* $($message)*
+        );
+    };
+    (@config_inline [$($strategy:tt)*] []) => {};
+    (@config_inline [, strategy = link] [$($inline:tt)*]) => {};
+    (@config_inline [, strategy = abort] [$($inline:tt)*]) => {};
+    (@config_inline [, strategy = panic] [$($inline:tt)*]) => {};
+    (@config_inline [$($strategy:tt)*] [$($inline:tt)*]) => {
+        $crate::rt::compile_error!("The `inline` field requires `strategy: link`, `abort` or `panic`.");
+    };
+    (@config [] $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt ty: $T:ty $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config [$T] $label $defuse $style $methods $strategy $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt [] $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt label: $l:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty [$l] $defuse $style $methods $strategy $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt [] $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt defuse: $d:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label [, defuse = $d] $style $methods $strategy $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt [] $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt defuse_style: $s:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse [, defuse_style = $s] $methods $strategy $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt [] $strategy:tt $inline:tt $generics:tt $where:tt $message:tt methods: [$($method:ident),* $(,)*] $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style [$(, $method)*] $strategy $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt [] $inline:tt $generics:tt $where:tt $message:tt strategy: cfg { $($branches:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style $methods [, strategy = cfg { $($branches)* }] $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt [] $inline:tt $generics:tt $where:tt $message:tt strategy: $s:tt $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style $methods [, strategy = $s] $inline $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt [] $generics:tt $where:tt $message:tt inline: $i:ident $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style $methods $strategy [, inline = $i] $generics $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt [] $where:tt $message:tt generics: [$($g:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style $methods $strategy $inline [, generics = [$($g)*]] $where $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt [] $message:tt where: [$($bound:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style $methods $strategy $inline $generics [, where = [$($bound)*]] $message $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt [] message: $m:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!(@config $ty $label $defuse $style $methods $strategy $inline $generics $where [, $m] $($($rest)*)?);
+    };
+    (@config $ty:tt $label:tt $defuse:tt $style:tt $methods:tt $strategy:tt $inline:tt $generics:tt $where:tt $message:tt $field:ident : $($rest:tt)*) => {
+        $crate::rt::compile_error!($crate::rt::concat!(
+            "Unknown or repeated field `",
+            $crate::rt::stringify!($field),
+            "`."
+        ));
+    };
+    ($field:ident : $($rest:tt)*) => {
+        $crate::prevent_drop!(@config [] [] [] [] [] [] [] [] [] [] $field : $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = cfg { $($branches:tt)* } $($rest:tt)*) => {
+        $crate::prevent_drop!(@cfg [$T] $label [$($rest)*] [] $($branches)*);
+    };
+    (@cfg [$T:ty] $label:ident [$($rest:tt)*] [$($seen:tt)*] _ => $strategy:ident $(,)*) => {
+        #[cfg(not(any($($seen)*)))]
+        $crate::prevent_drop!($T, $label, strategy = $strategy $($rest)*);
+    };
+    (@cfg [$T:ty] $label:ident [$($rest:tt)*] [$($seen:tt)*] $predicate:meta => $strategy:ident $(, $($branches:tt)*)?) => {
+        #[cfg(all($predicate, not(any($($seen)*))))]
+        $crate::prevent_drop!($T, $label, strategy = $strategy $($rest)*);
+
+        $crate::prevent_drop!(@cfg [$T] $label [$($rest)*] [$($seen)* $predicate,] $($($branches)*)?);
+    };
+    (@cfg [$T:ty] $label:ident [$($rest:tt)*] [$($seen:tt)*]) => {};
+    ($T:ty, $label:ident $(, strategy = $strategy:ident)? $(, inline = $inline:ident)?, generics = [$($generics:tt)*], where = [$($bounds:tt)*] $($rest:tt)*) => {
+        $crate::prevent_drop!(
+            $T,
+            $label $(, strategy = $strategy)? $(, inline = $inline)?,
+            generics = [$($generics)* where $($bounds)*]
+            $($rest)*
+        );
+    };
+    ($T:ty, $label:ident, defuse = $defuse:ident, defuse_style = $style:ident $($rest:tt)*) => {
+        $crate::prevent_drop!($T, $label $($rest)*);
+        $crate::prevent_drop!(@method [$T] [defuse $defuse $style] $($rest)*);
+    };
+    ($T:ty, $label:ident, defuse = $defuse:ident $($rest:tt)*) => {
+        $crate::prevent_drop!($T, $label $($rest)*);
+        $crate::prevent_drop!(@method [$T] [defuse $defuse manually_drop] $($rest)*);
+    };
+    ($T:ty, $label:ident, leak_intentionally $($rest:tt)*) => {
+        $crate::prevent_drop!($T, $label $($rest)*);
+        $crate::prevent_drop!(@method [$T] [leak_intentionally] $($rest)*);
+    };
+    ($T:ty, $label:ident, with_cleanup $($rest:tt)*) => {
+        $crate::prevent_drop!($T, $label $($rest)*);
+        $crate::prevent_drop!(@method [$T] [with_cleanup] $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, strategy = $strategy:ident $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, defuse = $defuse:ident $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, defuse_style = $style:ident $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, leak_intentionally $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, with_cleanup $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, inline = $inline:ident $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method $($rest)*);
+    };
+    (@method [$T:ty] $method:tt, generics = [$($generics:tt)*], where = [$($bounds:tt)*] $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method [$($generics)*] [$($bounds)*]);
+    };
+    (@method [$T:ty] $method:tt, generics = [$($generics:tt)*] $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method [$($generics)*] []);
+    };
+    (@method [$T:ty] [defuse $defuse:ident manually_drop] [$($generics:tt)*] [$($bounds:tt)*]) => {
+        impl<$($generics)*> $T where $($bounds)* {
+            /// Take ownership of `self` without triggering the drop guard.
+            #[inline]
+            pub fn $defuse(self) -> $crate::rt::ManuallyDrop<Self> {
+                $crate::rt::ManuallyDrop::new(self)
+            }
+        }
+    };
+    (@method [$T:ty] [defuse $defuse:ident forget] [$($generics:tt)*] [$($bounds:tt)*]) => {
+        impl<$($generics)*> $T where $($bounds)* {
+            /// Forget `self` without triggering the drop guard.
+            #[inline]
+            pub fn $defuse(self) {
+                $crate::rt::forget(self);
+            }
+        }
+    };
+    (@method [$T:ty] [defuse $defuse:ident $style:ident] [$($generics:tt)*] [$($bounds:tt)*]) => {
+        $crate::rt::compile_error!($crate::rt::concat!(
+            "Unknown defuse_style `",
+            $crate::rt::stringify!($style),
+            "`, expected `manually_drop` or `forget`."
+        ));
+    };
+    (@method [$T:ty] [async $name:ident [$($arg:ident: $arg_ty:ty),*] [$ret:ty] $cleanup:path] [$($generics:tt)*] [$($bounds:tt)*]) => {
+        impl<$($generics)*> $T where $($bounds)* {
+            /// Consume `self` without triggering the drop guard and
+            /// return the future of its cleanup.
+            #[inline]
+            pub fn $name(self, $($arg: $arg_ty),*) -> impl $crate::rt::Future<Output = $ret> {
+                $cleanup($crate::rt::ManuallyDrop::new(self), $($arg),*)
+            }
+        }
+    };
+    (@method [$T:ty] [leak_intentionally] [$($generics:tt)*] [$($bounds:tt)*]) => {
+        impl<$($generics)*> $T where $($bounds)* {
+            /// Leak `self` on purpose, without triggering the drop guard
+            /// and without running any destructor.
+            #[inline]
+            pub fn leak_intentionally(self) {
+                $crate::rt::forget(self);
+            }
+        }
+    };
+    (@method [$T:ty] [with_cleanup] [$($generics:tt)*] [$($bounds:tt)*]) => {
+        impl<$($generics)*> $T where $($bounds)* {
+            /// Defuse the drop guard of `self` and hand it to the cleanup
+            /// `f`, returning whatever `f` returns.
+            #[inline]
+            pub fn with_cleanup<R, F: FnOnce($crate::rt::ManuallyDrop<Self>) -> R>(self, f: F) -> R {
+                f($crate::rt::ManuallyDrop::new(self))
+            }
+        }
+    };
+    (@method [$T:ty] $method:tt $($rest:tt)*) => {
+        $crate::prevent_drop!(@method [$T] $method [] []);
+    };
+    ($T:ty, $label:ident, strategy = const $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_const [$T] [] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = link $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_link_optimized [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = abort $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_abort [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = panic $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_panic [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = raw_abort $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_raw_abort [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = eprintln $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_eprintln [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = deprecated $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_deprecated [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = log $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_log [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = metric $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_metric [$T] [$label] $($rest)*);
+    };
+    ($T:ty, $label:ident, strategy = trace $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_trace [$T] [$label] $($rest)*);
+    };
+    ($($T:ty => $label:ident $(($($args:tt)*))*),+ $(,)*) => {
+        $(
+            $crate::prevent_drop!($T, $label $(, $($args)*)*);
+        )+
+    };
+    ($T:ty, $label:ident $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(prevent_drop_default [$T] [$label] $($rest)*);
+    };
+}
+
+/// Generate a consuming method that defuses the drop guard and hands
+/// the value to a cleanup function.
+///
+/// This standardizes the explicit drop pattern from the crate
+/// documentation. The generated method wraps `self` in `ManuallyDrop`
+/// and calls the cleanup function with it followed by the method
+/// arguments. Whatever the cleanup function returns is returned from the
+/// method, so a `Result` composes with `?` at the call site.
+///
+/// The cleanup function receives a `ManuallyDrop<T>` rather than `T` so
+/// that it cannot trigger the guard. Move fields out of it with
+/// `ptr::read`, anything left behind is leaked.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::mem::ManuallyDrop;
+///
+/// struct Resource;
+/// struct Context;
+/// struct Error;
+///
+/// fn cleanup(_resource: ManuallyDrop<Resource>, _context: &Context) -> Result<(), Error> {
+///     // Perform cleanup.
+///     Ok(())
+/// }
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+/// prevent_drop_consume!(Resource, fn try_consume(context: &Context) -> Result<(), Error> = cleanup);
+///
+/// fn main() {
+///     let c = Context;
+///     let r = Resource;
+///     assert!(r.try_consume(&c).is_ok());
+/// }
+/// ```
+///
+/// Generic types pass their parameters with `generics = [...]` before
+/// the method, e.g. `prevent_drop_consume!(Buffer<T>, generics = [T], fn
+/// ...)`.
+#[macro_export]
+macro_rules! prevent_drop_consume {
+    ($T:ty, fn $name:ident($($arg:ident: $arg_ty:ty),* $(,)*) -> $ret:ty = $cleanup:path) => {
+        $crate::prevent_drop_consume!($T, generics = [], fn $name($($arg: $arg_ty),*) -> $ret = $cleanup);
+    };
+    ($T:ty, generics = [$($generics:tt)*], fn $name:ident($($arg:ident: $arg_ty:ty),* $(,)*) -> $ret:ty = $cleanup:path) => {
+        impl<$($generics)*> $T {
+            /// Consume `self` without triggering the drop guard and run
+            /// its cleanup.
+            #[inline]
+            pub fn $name(self, $($arg: $arg_ty),*) -> $ret {
+                $cleanup($crate::rt::ManuallyDrop::new(self), $($arg),*)
+            }
+        }
+    };
+}
+
+/// Guard a type whose cleanup is asynchronous and generate the method
+/// that defuses the guard and starts the cleanup.
+///
+/// `Drop` cannot `.await`, so a value that needs asynchronous cleanup has
+/// to be closed explicitly. This macro installs the guard through
+/// `prevent_drop!` and generates `fn $name(self, ...) -> impl
+/// Future<Output = $ret>`. The method wraps `self` in `ManuallyDrop` and
+/// returns the future of the cleanup function, which is usually an
+/// `async fn`. Dropping the value without calling the method fires the
+/// guard. Any arguments after the cleanup function, such as `strategy =
+/// panic`, are passed on to `prevent_drop!`.
+///
+/// The guard is defused when the method is called, not when the future
+/// completes, so a future that is dropped before it finishes leaks the
+/// value silently. The future must not borrow from the method
+/// arguments, so pass owned values.
+///
+/// ```edition2021
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::future::Future;
+/// use std::mem::ManuallyDrop;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// struct Socket {
+///     buffered: Vec<u8>,
+/// }
+///
+/// async fn flush(socket: ManuallyDrop<Socket>) -> Result<usize, ()> {
+///     // Write the buffered bytes.
+///     Ok(socket.buffered.len())
+/// }
+///
+/// prevent_drop_async!(
+///     Socket,
+///     prevent_drop_Socket,
+///     fn close() -> Result<usize, ()> = flush,
+///     strategy = panic
+/// );
+///
+/// fn main() {
+///     let socket = Socket { buffered: vec![1, 2, 3] };
+///     let mut close = pin!(socket.close());
+///     let mut cx = Context::from_waker(Waker::noop());
+///     assert_eq!(close.as_mut().poll(&mut cx), Poll::Ready(Ok(3)));
+/// }
+/// ```
+///
+/// Generic types pass `generics = [...]` after the cleanup function,
+/// like any other argument of `prevent_drop!`.
+#[macro_export]
+macro_rules! prevent_drop_async {
+    ($T:ty, $label:ident, fn $name:ident($($arg:ident: $arg_ty:ty),* $(,)*) -> $ret:ty = $cleanup:path $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!($T, $label $(, $($rest)*)?);
+        $crate::prevent_drop!(
+            @method [$T] [async $name [$($arg: $arg_ty),*] [$ret] $cleanup]
+            $(, $($rest)*)?
+        );
+    };
+}
+
+/// Implement `TryDrop` for a guarded type with a cleanup function.
+///
+/// The generated `try_drop` moves the value out of its `Box`, wrapped in
+/// `ManuallyDrop` so the guard cannot fire, and passes it to the cleanup
+/// function. The cleanup function returns a `Result<(), E>` where `E`
+/// converts into `Box<dyn Error>`. Like with `prevent_drop_consume!`,
+/// move fields out with `ptr::read`, anything left behind is leaked.
+///
+/// Generic types pass their parameters with `generics = [...]` before
+/// the cleanup function. Requires `std`.
+#[macro_export]
+macro_rules! prevent_drop_try_drop {
+    ($T:ty, $cleanup:path) => {
+        $crate::prevent_drop_try_drop!($T, generics = [], $cleanup);
+    };
+    ($T:ty, generics = [$($generics:tt)*], $cleanup:path) => {
+        impl<$($generics)*> $crate::TryDrop for $T {
+            #[inline]
+            fn try_drop(
+                self: $crate::rt::Box<Self>,
+            ) -> $crate::rt::Result<(), $crate::rt::Box<dyn $crate::rt::Error>> {
+                $crate::rt::Result::map_err($cleanup($crate::defuse_box(self)), $crate::rt::Into::into)
+            }
+        }
+    };
+}
+
+/// Guard an FFI handle and generate a consuming `close` method for it.
+///
+/// This is the `FileDesc` example from the crate documentation done
+/// right. The generated `close(self) -> c_int` defuses the guard, binds
+/// the handle to the name between the bars and evaluates the expression
+/// after them. Its result is returned instead of being ignored, so the
+/// caller decides what to do when closing fails. The handle is bound as
+/// a `ManuallyDrop<Self>`, so fields are accessed as usual and whatever
+/// is not moved out is leaked.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::os::raw::c_int;
+///
+/// # unsafe fn close(_fd: c_int) -> c_int { 0 }
+/// struct Fd(c_int);
+///
+/// prevent_drop_ffi!(Fd, prevent_drop_Fd, |fd| unsafe { close(fd.0) });
+///
+/// fn main() {
+///     let fd = Fd(3);
+///     assert_eq!(fd.close(), 0);
+/// }
+/// ```
+///
+/// To choose the method name and return type, write `fn name() -> Ret =`
+/// before the bars. Any arguments after the expression are passed on to
+/// `prevent_drop!`, e.g. `strategy = panic`. Generic types are not
+/// supported.
+#[macro_export]
+macro_rules! prevent_drop_ffi {
+    ($T:ty, $label:ident, |$handle:ident| $close:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_ffi!(
+            $T,
+            $label,
+            fn close() -> $crate::rt::c_int = |$handle| $close
+            $(, $($rest)*)?
+        );
+    };
+    ($T:ty, $label:ident, fn $name:ident() -> $ret:ty = |$handle:ident| $close:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!($T, $label $(, $($rest)*)?);
+
+        impl $T {
+            /// Defuse the drop guard and close the handle.
+            #[inline]
+            pub fn $name(self) -> $ret {
+                let $handle = $crate::rt::ManuallyDrop::new(self);
+                $close
+            }
+        }
+    };
+}
+
+/// Guard a newtype over a raw pointer and generate a consuming method
+/// that releases it.
+///
+/// This is the common case of an FFI handle like `struct
+/// Handle(*mut c_void)`. `prevent_drop_ptr!(Handle, label, close, |ptr:
+/// *mut c_void| unsafe { free(ptr) })` installs the guard and generates
+/// `close(self)`, which defuses the guard and calls the closure body with
+/// the pointer in the first field. Because `close` consumes the handle
+/// and the guard never releases anything, the pointer is released
+/// exactly once. Write a return type after the bars, like a closure, to
+/// return the result of releasing, e.g. `|ptr: *mut c_void| -> c_int {
+/// unsafe { destroy(ptr) } }`. Any arguments after the closure are passed
+/// on to `prevent_drop!`, e.g. `strategy = panic`. Generic types are not
+/// supported.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::os::raw::c_void;
+///
+/// # unsafe fn free(_ptr: *mut c_void) {}
+/// struct Handle(*mut c_void);
+///
+/// prevent_drop_ptr!(Handle, prevent_drop_Handle, close, |ptr: *mut c_void| unsafe { free(ptr) });
+///
+/// fn main() {
+///     let handle = Handle(std::ptr::null_mut());
+///     handle.close();
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_ptr {
+    ($T:ty, $label:ident, $name:ident, |$ptr:ident: $P:ty| -> $ret:ty $release:block $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop!($T, $label $(, $($rest)*)?);
+
+        impl $T {
+            /// Defuse the drop guard and release the pointer.
+            #[inline]
+            pub fn $name(self) -> $ret {
+                let this = $crate::rt::ManuallyDrop::new(self);
+                let $ptr: $P = this.0;
+                $release
+            }
+        }
+    };
+    ($T:ty, $label:ident, $name:ident, |$ptr:ident: $P:ty| $release:expr $(, $($rest:tt)*)?) => {
+        $crate::prevent_drop_ptr!($T, $label, $name, |$ptr: $P| -> () { $release } $(, $($rest)*)?);
+    };
+}
+
+/// Guard a value for the rest of a scope and clean it up on early exits.
+///
+/// Expands to `CleanupGuard::new(value, cleanup)`. If the scope is left
+/// before `defuse` is called, for example by `?` on the error path,
+/// `cleanup` is called with the value and its drop guard stays quiet.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::mem::ManuallyDrop;
+///
+/// struct Connection;
+///
+/// impl Connection {
+///     fn close(self) {
+///         let _this = ManuallyDrop::new(self);
+///     }
+/// }
+///
+/// prevent_drop_panic!(Connection, prevent_drop_Connection);
+///
+/// fn connect(port: &str) -> Result<Connection, std::num::ParseIntError> {
+///     let connection = guard_or_cleanup!(Connection, |c| c.close());
+///     let _port: u16 = port.parse()?;
+///     Ok(connection.defuse())
+/// }
+///
+/// fn main() {
+///     assert!(connect("eighty").is_err());
+///     connect("80").unwrap().close();
+/// }
+/// ```
+#[macro_export]
+macro_rules! guard_or_cleanup {
+    ($value:expr, $cleanup:expr $(,)*) => {
+        $crate::CleanupGuard::new($value, $cleanup)
+    };
+}
+
+/// Guard a builder that must be finished through a `BuildGuard` field.
+///
+/// Builders are usually destructured when they are finished, which is
+/// not possible for a type that implements `Drop`. Instead of guarding
+/// the builder itself, give it a `BuildGuard<Self>` field, which is zero
+/// sized, and let this macro connect the guard to the builder. Dropping
+/// the builder drops the field, which triggers the guard through the
+/// strategy `prevent_drop!` selects. Defuse the field in the finishing
+/// method before you construct the output.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use prevent_drop::BuildGuard;
+///
+/// struct Request {
+///     url: String,
+/// }
+///
+/// #[derive(Default)]
+/// struct RequestBuilder {
+///     url: String,
+///     guard: BuildGuard<RequestBuilder>,
+/// }
+///
+/// impl RequestBuilder {
+///     fn build(self) -> Request {
+///         let RequestBuilder { url, guard } = self;
+///         guard.defuse();
+///         Request { url }
+///     }
+/// }
+///
+/// prevent_drop_builder!(RequestBuilder, prevent_drop_RequestBuilder);
+///
+/// fn main() {
+///     let request = RequestBuilder::default().build();
+///     assert!(request.url.is_empty());
+/// }
+/// ```
+///
+/// Any arguments after the label, such as `strategy = panic`, are passed
+/// on to `prevent_drop!`. Generic builders are not supported.
+#[macro_export]
+macro_rules! prevent_drop_builder {
+    ($B:ty, $label:ident $($rest:tt)*) => {
+        const _: () = {
+            struct Unbuilt;
+
+            $crate::prevent_drop!(
+                Unbuilt,
+                $label $($rest)*,
+                $crate::rt::concat!(
+                    "Forgot to build an instance of ",
+                    $crate::rt::stringify!($B),
+                    "."
+                )
+            );
+
+            impl $crate::GuardedBuilder for $B {
+                #[inline]
+                fn forgot_to_build() {
+                    let _ = Unbuilt;
+                }
+            }
+        };
+
+        impl $crate::PreventDropped for $B {}
+    };
+}
+
+/// Guard a type from another crate by wrapping it in a newtype.
+///
+/// `Drop` can only be implemented for local types, so
+/// `prevent_drop_newtype!(Wrapper, foreign::Type, label)` declares `struct
+/// Wrapper(foreign::Type)` and guards the wrapper with `prevent_drop!`. The
+/// wrapper gets `new` to wrap a value, `into_inner` to take it back out
+/// without triggering the guard, and `Deref` and `DerefMut` to the inner
+/// type. Attributes and a visibility in front of the wrapper name are put
+/// on the struct, and all arguments after the label are passed on to
+/// `prevent_drop!`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// mod foreign {
+///     pub struct Connection {
+///         pub open: bool,
+///     }
+/// }
+///
+/// prevent_drop_newtype!(pub Connection, foreign::Connection, prevent_drop_Connection, strategy = panic);
+///
+/// fn main() {
+///     let connection = Connection::new(foreign::Connection { open: true });
+///     assert!(connection.open);
+///     let mut inner = connection.into_inner();
+///     inner.open = false;
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_newtype {
+    ($(#[$attr:meta])* $vis:vis $W:ident, $Inner:ty, $label:ident $($rest:tt)*) => {
+        $(#[$attr])*
+        $vis struct $W($Inner);
+
+        $crate::prevent_drop!($W, $label $($rest)*);
+
+        impl $W {
+            /// Wrap `inner` and guard it against being dropped.
+            #[inline]
+            pub fn new(inner: $Inner) -> Self {
+                $W(inner)
+            }
+
+            /// Take the wrapped value back out without triggering the
+            /// drop guard.
+            #[inline]
+            pub fn into_inner(self) -> $Inner {
+                $crate::rt::take_field(self, |this| &this.0)
+            }
+        }
+
+        impl $crate::rt::Deref for $W {
+            type Target = $Inner;
+
+            #[inline]
+            fn deref(&self) -> &$Inner {
+                &self.0
+            }
+        }
+
+        impl $crate::rt::DerefMut for $W {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut $Inner {
+                &mut self.0
+            }
+        }
+    };
+}
+
+/// Guard the implementors of a trait behind a trait object and make
+/// its finalizers the only way to get rid of them.
+///
+/// A guard cannot be put on a trait, but it can be put on the trait
+/// object. `prevent_drop_trait!(Pending, dyn Transaction, label, { fn
+/// commit(); fn rollback(); })` declares `struct Pending(Box<dyn
+/// Transaction>)` with `prevent_drop_newtype!` and generates a consuming
+/// method on it for every finalizer. Each method takes the box out
+/// without triggering the guard and calls the trait method of the same
+/// name with the remaining arguments, so a transaction that is neither
+/// committed nor rolled back trips the guard. The finalizers must take
+/// `self: Box<Self>` to keep the trait object safe. All arguments after
+/// the finalizers are passed on to `prevent_drop!`. Requires `std`.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// trait Transaction {
+///     fn insert(&mut self, key: &str);
+///     fn commit(self: Box<Self>) -> Result<usize, String>;
+///     fn rollback(self: Box<Self>);
+/// }
+///
+/// struct InMemory(Vec<String>);
+///
+/// impl Transaction for InMemory {
+///     fn insert(&mut self, key: &str) {
+///         self.0.push(key.to_string());
+///     }
+///
+///     fn commit(self: Box<Self>) -> Result<usize, String> {
+///         Ok(self.0.len())
+///     }
+///
+///     fn rollback(self: Box<Self>) {}
+/// }
+///
+/// prevent_drop_trait!(Pending, dyn Transaction, prevent_drop_Pending, {
+///     fn commit() -> Result<usize, String>;
+///     fn rollback();
+/// }, strategy = panic);
+///
+/// fn main() {
+///     let mut transaction = Pending::new(Box::new(InMemory(Vec::new())));
+///     transaction.insert("key");
+///     assert_eq!(transaction.commit(), Ok(1));
+/// }
+/// ```
+#[cfg(any(feature = "std", test))]
+#[macro_export]
+macro_rules! prevent_drop_trait {
+    (
+        $(#[$attr:meta])* $vis:vis $W:ident, dyn $Trait:path, $label:ident,
+        { $(fn $name:ident($($arg:ident: $arg_ty:ty),* $(,)*) $(-> $ret:ty)?;)* }
+        $($rest:tt)*
+    ) => {
+        $crate::prevent_drop_newtype!(
+            $(#[$attr])* $vis $W,
+            $crate::rt::Box<dyn $Trait>,
+            $label $($rest)*
+        );
+
+        impl $W {
+            $(
+                /// Take the trait object out without triggering the drop
+                /// guard and finalize it.
+                #[inline]
+                pub fn $name(self, $($arg: $arg_ty),*) $(-> $ret)? {
+                    self.into_inner().$name($($arg),*)
+                }
+            )*
+        }
+    };
+}
+
+/// Guard a zero-sized type with `prevent_drop!` and assert at compile
+/// time that it stays zero-sized.
+///
+/// Marker types that only represent a resource owned elsewhere cost
+/// nothing to guard, and the link strategy can nearly always prove their
+/// drops away. The assertion breaks the build when a field is added
+/// later, so you get to reconsider the guard. All arguments after the
+/// label are passed on to `prevent_drop!`. Generic types are not
+/// supported, because the assertion needs a concrete type.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Token;
+///
+/// impl Token {
+///     fn release(self) {
+///         let _zelf = std::mem::ManuallyDrop::new(self);
+///     }
+/// }
+///
+/// prevent_drop_static_assert!(Token, prevent_drop_Token, strategy = panic);
+///
+/// fn main() {
+///     Token.release();
+/// }
+/// ```
+#[macro_export]
+macro_rules! prevent_drop_static_assert {
+    ($T:ty, $label:ident $($rest:tt)*) => {
+        $crate::prevent_drop!($T, $label $($rest)*);
+
+        const _: () = if $crate::rt::size_of::<$T>() != 0 {
+            $crate::rt::panic!($crate::rt::concat!(
+                $crate::rt::stringify!($T),
+                " is guarded by prevent_drop_static_assert! but is not zero-sized."
+            ));
+        };
+    };
+}
+
+// Exports the label of a strategy under `$name`, for the strategies that
+// take `no_mangle` or `link_name = "..."` but do not put the attribute on
+// the label themselves.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_export {
+    ($label:ident, $name:expr) => {
+        const _: () = {
+            #[export_name = $name]
+            extern "Rust" fn exported() {
+                $label()
+            }
+        };
+    };
+}
+
+// Every strategy implements its traits through here. The generics may
+// end in the `where` clause of `prevent_drop!`, which is split off and
+// placed after the type, followed by `=> |this| { ... }` from
+// `prevent_drop_wrap!`, which runs at the start of `drop`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_impl {
+    ([$($generics:tt)*] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@params [] [$($generics)*] $($item)*);
+    };
+    (@params [$($params:tt)*] [where $($rest:tt)*] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@bounds [$($params)*] [] [$($rest)*] $($item)*);
+    };
+    (@params [$($params:tt)*] [=> $($prelude:tt)*] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@emit [$($params)*] [] [$($prelude)*] $($item)*);
+    };
+    (@params [$($params:tt)*] [] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@emit [$($params)*] [] [] $($item)*);
+    };
+    (@params [$($params:tt)*] [$next:tt $($rest:tt)*] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@params [$($params)* $next] [$($rest)*] $($item)*);
+    };
+    (@bounds [$($params:tt)*] [$($bounds:tt)*] [=> $($prelude:tt)*] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@emit [$($params)*] [where $($bounds)*] [$($prelude)*] $($item)*);
+    };
+    (@bounds [$($params:tt)*] [$($bounds:tt)*] [] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@emit [$($params)*] [where $($bounds)*] [] $($item)*);
+    };
+    (@bounds [$($params:tt)*] [$($bounds:tt)*] [$next:tt $($rest:tt)*] $($item:tt)*) => {
+        $crate::prevent_drop_impl!(@bounds [$($params)*] [$($bounds)* $next] [$($rest)*] $($item)*);
+    };
+    (@emit [$($params:tt)*] [$($where:tt)*] [|$this:ident| $prelude:block] $Trait:path, $T:ty {
+        $(#[$attr:meta])*
+        fn drop(&mut $self_:tt) { $($body:tt)* }
+    }) => {
+        impl<$($params)*> $Trait for $T $($where)* {
+            $(#[$attr])*
+            fn drop(&mut $self_) {
+                {
+                    let $this: &mut Self = $self_;
+                    $prelude
+                }
+                $($body)*
+            }
+        }
+    };
+    (@emit [$($params:tt)*] [$($where:tt)*] [$($prelude:tt)*] $Trait:path, $T:ty { $($body:tt)* }) => {
+        impl<$($params)*> $Trait for $T $($where)* { $($body)* }
+    };
+}
+
+// Implements the traits of a guard whose strategy is turned off, with
+// `$drop` as the `drop` method. The arguments meant for the strategy are
+// scanned for `generics = [...]` wherever it appears, for `message_fn`,
+// and for a `|this|` message, which the label of the panic strategy
+// takes as an argument. The label and the message function, if any, do
+// nothing, so calls to them keep compiling.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_inert {
+    ([$T:ty] [$($label:ident)?] { $($drop:tt)* } $($rest:tt)*) => {
+        $crate::prevent_drop_inert!(@find [$T] [$($label)?] { $($drop)* } [] [] [] $($rest)*);
+    };
+    (@find [$T:ty] $label:tt $drop:tt $generics:tt $message:tt $message_fn:tt , generics = [$($found:tt)*] $($rest:tt)*) => {
+        $crate::prevent_drop_inert!(@find [$T] $label $drop [$($found)*] $message $message_fn $($rest)*);
+    };
+    (@find [$T:ty] $label:tt $drop:tt $generics:tt $message:tt $message_fn:tt , |$this:ident| $($rest:tt)*) => {
+        $crate::prevent_drop_inert!(@find [$T] $label $drop $generics [describe] $message_fn $($rest)*);
+    };
+    (@find [$T:ty] $label:tt $drop:tt $generics:tt $message:tt $message_fn:tt , message_fn = $found:ident $($rest:tt)*) => {
+        $crate::prevent_drop_inert!(@find [$T] $label $drop $generics $message [$found] $($rest)*);
+    };
+    (@find [$T:ty] $label:tt $drop:tt $generics:tt $message:tt $message_fn:tt $skip:tt $($rest:tt)*) => {
+        $crate::prevent_drop_inert!(@find [$T] $label $drop $generics $message $message_fn $($rest)*);
+    };
+    (@find [$T:ty] [$($label:ident)?] { $($drop:tt)* } [$($generics:tt)*] $message:tt [$($message_fn:ident)?]) => {
+        $(
+            $crate::prevent_drop_inert!(@label $label $message);
+        )?
+
+        $(
+            /// Write the message the drop guard reports.
+            #[inline]
+            #[allow(non_snake_case, dead_code)]
+            pub fn $message_fn(_out: &mut dyn $crate::rt::Write) -> $crate::rt::fmt::Result {
+                $crate::rt::Result::Ok(())
+            }
+        )?
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::rt::Drop, $T { $($drop)* });
+
+        $crate::prevent_drop_impl!([$($generics)*] $crate::PreventDropped, $T {});
+    };
+    (@label $label:ident []) => {
+        #[inline]
+        #[allow(non_snake_case, dead_code)]
+        pub fn $label() {}
+    };
+    (@label $label:ident [describe]) => {
+        #[inline]
+        #[allow(non_snake_case, dead_code)]
+        pub fn $label(_message: &dyn $crate::rt::Display) {}
+    };
+}
+
+// Every strategy `prevent_drop!` selects goes through here, so the
+// `dry_run` feature, and the `miri_inert` feature under Miri, can swap
+// all of them for an empty `Drop` impl.
+#[cfg(not(any(feature = "dry_run", all(feature = "miri_inert", miri))))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_dispatch {
+    ($strategy:ident [$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::$strategy!($T $(, $label)? $($rest)*);
+    };
+}
+
+// The label of the link strategy is an `extern` declaration, not a
+// function that can be called, so it is left out.
+#[cfg(any(feature = "dry_run", all(feature = "miri_inert", miri)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_dispatch {
+    (prevent_drop_link_optimized [$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(@inert [$T] [] $($rest)*);
+    };
+    (prevent_drop_default [$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::prevent_drop_default_inert!([$T] [$($label)?] $($rest)*);
+    };
+    ($strategy:ident [$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(@inert [$T] [$($label)?] $($rest)*);
+    };
+    (@inert [$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::prevent_drop_inert!([$T] [$($label)?] {
+            #[inline]
+            fn drop(&mut self) {}
+        } $($rest)*);
+    };
+}
+
+// Passes the arguments `prevent_drop!` accepts on to `prevent_drop_link!`,
+// dropping the message and anything after it, which the link strategy
+// cannot report.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_link_args {
+    ($T:ty, $label:ident, inline = $inline:ident, generics = [$($generics:tt)*] $(, $($msg:tt)+)?) => {
+        $crate::prevent_drop_link!($T, $label, inline = $inline, generics = [$($generics)*]);
+    };
+    ($T:ty, $label:ident, inline = $inline:ident $(, $($msg:tt)+)?) => {
+        $crate::prevent_drop_link!($T, $label, inline = $inline);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*] $(, $($msg:tt)+)?) => {
+        $crate::prevent_drop_link!($T, $label, generics = [$($generics)*]);
+    };
+    ($T:ty, $label:ident $(, $($msg:tt)+)?) => {
+        $crate::prevent_drop_link!($T, $label);
+    };
+}
+
+#[cfg(opt_level_gt_0)]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_link_optimized {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_link_args!($($args)*);
+    };
+}
+
+#[cfg(not(opt_level_gt_0))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_link_optimized {
+    ($($args:tt)*) => {
+        $crate::rt::compile_error!("The link strategy requires you to enable optimizations. See https://docs.rs/prevent_drop/#configuration, or use the const strategy, which works without them.");
+    };
+}
+
+// What `prevent_drop_dispatch!` expands `prevent_drop_default!` to under
+// `dry_run`, without the label where the default is the link strategy.
+#[cfg(any(
+    all(
+        not(feature = "abort"),
+        not(feature = "panic"),
+        not(feature = "runtime_strategy"),
+        opt_level_gt_0,
+        not(prevent_drop_force)
+    ),
+    prevent_drop_force = "link"
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default_inert {
+    ([$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(@inert [$T] [] $($rest)*);
+    };
+}
+
+#[cfg(not(any(
+    all(
+        not(feature = "abort"),
+        not(feature = "panic"),
+        not(feature = "runtime_strategy"),
+        opt_level_gt_0,
+        not(prevent_drop_force)
+    ),
+    prevent_drop_force = "link"
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default_inert {
+    ([$T:ty] [$($label:ident)?] $($rest:tt)*) => {
+        $crate::prevent_drop_dispatch!(@inert [$T] [$($label)?] $($rest)*);
+    };
+}
+
+#[cfg(all(
+    not(feature = "abort"),
+    not(feature = "panic"),
+    not(feature = "runtime_strategy"),
+    opt_level_gt_0,
+    not(prevent_drop_force)
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_link_optimized!($($args)*);
+    };
+}
+
+// A forced link strategy skips the opt-level check, so it fails to link
+// instead of failing to compile without optimizations.
+#[cfg(prevent_drop_force = "link")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_link_args!($($args)*);
+    };
+}
+
+#[cfg(all(
+    not(feature = "abort"),
+    not(feature = "panic"),
+    not(feature = "auto_fallback"),
+    not(feature = "runtime_strategy"),
+    not(opt_level_gt_0),
+    not(prevent_drop_force)
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($($args:tt)*) => {
+        $crate::rt::compile_error!("The `prevent_drop!` macro requires you to enable optimizations or to enable one of the `abort`, `panic` or `auto_fallback` features. See https://docs.rs/prevent_drop/#configuration, or use `prevent_drop_const!`, which works without them.");
+    };
+}
+
+#[cfg(all(
+    not(feature = "abort"),
+    not(feature = "panic"),
+    feature = "auto_fallback",
+    not(feature = "never_panic"),
+    not(feature = "runtime_strategy"),
+    not(opt_level_gt_0),
+    not(prevent_drop_force)
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_panic!($($args)*);
+    };
+}
+
+#[cfg(any(
+    all(
+        feature = "abort",
+        not(feature = "panic"),
+        not(feature = "runtime_strategy"),
+        not(prevent_drop_force)
+    ),
+    prevent_drop_force = "abort",
+    all(
+        feature = "never_panic",
+        not(feature = "runtime_strategy"),
+        any(
+            all(not(feature = "abort"), feature = "panic", not(prevent_drop_force)),
+            prevent_drop_force = "panic",
+            all(
+                not(feature = "abort"),
+                not(feature = "panic"),
+                feature = "auto_fallback",
+                not(opt_level_gt_0),
+                not(prevent_drop_force)
+            )
+        )
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($T:ty, $label:ident) => {
+        $crate::prevent_drop_abort!($T, $label);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*]) => {
+        $crate::prevent_drop_abort!($T, $label, generics = [$($generics)*]);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], error = $error:expr) => {
+        $crate::prevent_drop_abort!($T, $label, generics = [$($generics)*]);
+    };
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $($msg:tt)+) => {
+        $crate::prevent_drop_abort!($T, $label, generics = [$($generics)*], $($msg)+);
+    };
+    ($T:ty, $label:ident, error = $error:expr) => {
+        $crate::prevent_drop_abort!($T, $label);
+    };
+    ($T:ty, $label:ident, $($msg:tt)+) => {
+        $crate::prevent_drop_abort!($T, $label, $($msg)+);
+    };
+}
+
+#[cfg(all(
+    not(feature = "never_panic"),
+    any(
+        all(
+            not(feature = "abort"),
+            feature = "panic",
+            not(feature = "runtime_strategy"),
+            not(prevent_drop_force)
+        ),
+        prevent_drop_force = "panic"
+    )
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_panic!($($args)*);
+    };
+}
+
+// The panic strategy checks `PREVENT_DROP_STRATEGY` with this feature.
+#[cfg(all(feature = "runtime_strategy", not(prevent_drop_force)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! prevent_drop_default {
+    ($($args:tt)*) => {
+        $crate::prevent_drop_panic!($($args)*);
+    };
+}
+
+#[doc(hidden)]
+pub mod count;
+
+pub use count::{leak_count, reset_leak_count};
+#[cfg(any(feature = "std", test))]
+pub use count::{leak_count_for, leak_counts};
+#[cfg(all(feature = "libc", any(feature = "std", test)))]
+pub use count::install_exit_leak_check;
+
+#[cfg(any(feature = "std", test))]
+#[doc(hidden)]
+pub mod record;
+
+#[cfg(any(feature = "std", test))]
+pub use record::{catch_leaks, take_recorded_leaks};
+
+#[cfg(any(feature = "std", test))]
+#[doc(hidden)]
+pub mod recent;
+
+#[cfg(any(feature = "std", test))]
+pub use recent::{clear_recent_leaks, recent_leaks, RECENT_LEAKS_CAPACITY};
+
+#[cfg(any(feature = "std", test))]
+#[doc(hidden)]
+pub mod registry;
+
+#[cfg(any(feature = "std", test))]
+pub use registry::registered_leak_types;
+
+#[cfg(any(feature = "std", test))]
+#[doc(hidden)]
+pub mod shutdown;
+
+#[cfg(any(feature = "std", test))]
+pub use shutdown::{clear_shutdown_deadline, set_shutdown_deadline};
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub mod event;
+
+#[cfg(feature = "serde")]
+pub use event::{set_leak_channel, set_leak_event_handler, LeakEvent};
+
+#[cfg(any(feature = "std", test))]
+mod formatter;
+
+#[cfg(feature = "anyhow")]
+mod context;
+
+#[cfg(feature = "anyhow")]
+pub use context::leak_context;
+
+#[cfg(any(feature = "std", test))]
+pub use formatter::{default_leak_message, set_leak_message_formatter};
+
+#[doc(hidden)]
+pub mod once;
+
+pub use once::{reset_drop_allowance, DropAllowance};
+
+#[doc(hidden)]
+pub mod allow;
+
+#[cfg(any(feature = "std", test))]
+pub use allow::allow_drop;
+
+mod builder;
+
+pub use builder::{BuildGuard, GuardedBuilder};
+
+mod consume;
+
+pub use consume::Consume;
+
+// Guards one of the wrapper types of this crate. Without optimizations
+// or a run-time strategy the link strategy cannot work. `prevent_drop!`
+// reports this with `compile_error!`, but the wrappers live in this
+// crate, so the error is deferred until the drop is actually
+// instantiated. With `dry_run`, or `miri_inert` under Miri, the guard
+// does nothing and needs no optimizations.
+#[cfg(any(
+    feature = "abort",
+    feature = "panic",
+    feature = "auto_fallback",
+    feature = "dry_run",
+    all(feature = "miri_inert", miri),
+    opt_level_gt_0,
+    prevent_drop_force
+))]
+macro_rules! prevent_drop_wrapper {
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $error:literal $(, $($msg:tt)*)?) => {
+        prevent_drop!($T, $label, generics = [$($generics)*] $(, $($msg)*)?);
+    };
+}
+
+#[cfg(not(any(
+    feature = "abort",
+    feature = "panic",
+    feature = "auto_fallback",
+    feature = "dry_run",
+    all(feature = "miri_inert", miri),
+    opt_level_gt_0,
+    prevent_drop_force
+)))]
+macro_rules! prevent_drop_wrapper {
+    ($T:ty, $label:ident, generics = [$($generics:tt)*], $error:literal $(, $($msg:tt)*)?) => {
+        impl<$($generics)*> Drop for $T {
+            #[inline]
+            fn drop(&mut self) {
+                #[allow(clippy::let_unit_value)]
+                let () = RequiresOptimizations::<Self>::ERROR;
+            }
+        }
+
+        impl<$($generics)*> ::PreventDropped for $T {}
+
+        struct RequiresOptimizations<T>(::core::marker::PhantomData<T>);
+
+        impl<T> RequiresOptimizations<T> {
+            const ERROR: () = panic!($error);
+        }
+    };
+}
+
+mod wrapper;
+
+pub use wrapper::PreventDrop;
+
+mod must_consume;
+
+pub use must_consume::MustConsume;
+
+mod guard;
+
+pub use guard::DropGuard;
+
+mod token;
+
+pub use token::{with_token, Consumed, Linear, Token};
+
+mod cleanup;
+
+pub use cleanup::CleanupGuard;
+
+#[cfg(feature = "scopeguard")]
+mod scope;
+
+#[cfg(feature = "scopeguard")]
+pub use scope::into_scope_guard;
+
+mod defuse;
+
+pub use defuse::{defuse_all, DefuseAll};
+#[cfg(any(feature = "std", test))]
+pub use defuse::{defuse_arc, defuse_box, defuse_pin_box, defuse_rc, leak_pin_box};
+
+#[cfg(any(feature = "std", test))]
+mod try_drop;
+
+#[cfg(any(feature = "std", test))]
+pub use try_drop::TryDrop;
+
+mod strategy;
+
+pub use strategy::{strategy, Strategy, OPT_LEVEL, STRATEGY};
+#[cfg(feature = "runtime_strategy")]
+pub use strategy::runtime_strategy;
+
+#[cfg(all(
+    feature = "abort",
+    feature = "panic",
+    not(feature = "runtime_strategy"),
+    not(prevent_drop_force)
+))]
+compile_error!("You cannot use both the abort and the panic strategies at the same time. Choose one or the other, or enable the `runtime_strategy` feature to choose at run time.");
+
+#[cfg(all(feature = "runtime_strategy", feature = "never_panic"))]
+compile_error!("The `runtime_strategy` feature can select the panic strategy and cannot be combined with the `never_panic` feature.");
+
+#[cfg(all(feature = "libc", not(unix)))]
+compile_error!("The `libc` feature is only supported on Unix targets.");
+
+#[cfg(test)]
+mod tests {
+    struct Resource;
+    struct Context;
+    struct Error;
+
+    impl Resource {
+        fn drop(self, _context: &Context) -> Error {
+            let _self = ::std::mem::ManuallyDrop::new(self);
+            Error
+        }
+    }
+
+    prevent_drop!(Resource, prevent_drop_Resource);
+
+    #[test]
+    fn test() {
+        let c = Context;
+        let r = Resource;
+        r.drop(&c);
+    }
+
+    #[derive(Debug)]
+    struct PanicStrategy;
+
+    prevent_drop_panic!(PanicStrategy, forget_to_explicitly_drop_an_instance_of_PanicStrategy);
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of PanicStrategy.")]
+    fn prevent_drop_panic_panics() {
+        let x = PanicStrategy;
+        ::std::mem::drop(x);
+    }
+
+    struct CleanupError {
+        handle: u32,
+    }
+
+    impl ::std::fmt::Debug for CleanupError {
+        fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+            write!(f, "handle {} was not closed", self.handle)
+        }
+    }
+
+    fn leaked_error() -> CleanupError {
+        CleanupError { handle: 7 }
+    }
+
+    struct ErrorStrategy;
+
+    prevent_drop_panic!(
+        ErrorStrategy,
+        forget_to_explicitly_drop_an_instance_of_ErrorStrategy,
+        error = leaked_error
+    );
+
+    #[test]
+    #[should_panic(
+        expected = "Forgot to explicitly drop an instance of ErrorStrategy. handle 7 was not closed"
+    )]
+    fn prevent_drop_panic_formats_error() {
+        ::std::mem::drop(ErrorStrategy);
+    }
+
+    struct GenericErrorStrategy<T>(T);
+
+    prevent_drop_panic!(
+        GenericErrorStrategy<T>,
+        forget_to_explicitly_drop_an_instance_of_GenericErrorStrategy,
+        generics = [T],
+        error = || "closure error"
+    );
+
+    #[test]
+    #[should_panic(expected = "GenericErrorStrategy<T>. \"closure error\"")]
+    fn prevent_drop_panic_formats_error_from_closure() {
+        ::std::mem::drop(GenericErrorStrategy(()));
+    }
+
+    mod invocation_location {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_INVOCATION_LOCATION_CHILD";
+
+        struct LocationStrategy;
+
+        const LINE: u32 = line!() + 1;
+        prevent_drop_panic!(LocationStrategy, forget_to_explicitly_drop_an_instance_of_LocationStrategy);
+
+        #[test]
+        fn prevent_drop_panic_reports_invocation_location_child() {
+            if env::var_os(CHILD).is_some() {
+                ::std::mem::drop(LocationStrategy);
+            }
+        }
+
+        // The panic hook is process-wide, so the location is read from the
+        // default hook's output in a child process instead of replacing it.
+        #[test]
+        fn prevent_drop_panic_reports_invocation_location() {
+            let output = Command::new(env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "tests::invocation_location::prevent_drop_panic_reports_invocation_location_child",
+                    "--nocapture",
+                ])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(!output.status.success(), "unexpected stderr:\n{}", stderr);
+            assert!(
+                stderr.contains(&format!("{}:{}:", file!(), LINE)),
+                "unexpected stderr:\n{}",
+                stderr
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Something else happened that I need to know about!")]
+    #[allow(unreachable_code, unused_variables)]
+    fn prevent_drop_panic_does_not_panic_while_panicking() {
+        let x = PanicStrategy;
+        panic!("Something else happened that I need to know about!");
+        ::std::mem::drop(x);
+    }
+
+    #[test]
+    fn prevent_drop_panic_does_not_panic_if_value_is_dropped() {
+        let _ = ::std::mem::ManuallyDrop::new(PanicStrategy);
+    }
+
+    #[derive(Debug)]
+    struct DescribedPanicStrategy(u32);
+
+    prevent_drop_panic!(
+        DescribedPanicStrategy,
+        forget_to_explicitly_drop_an_instance_of_DescribedPanicStrategy,
+        |this| format!("Leaked DescribedPanicStrategy fd={}.", this.0)
+    );
+
+    #[test]
+    #[should_panic(expected = "Leaked DescribedPanicStrategy fd=42.")]
+    fn prevent_drop_panic_describes_the_value() {
+        ::std::mem::drop(DescribedPanicStrategy(42));
+    }
+
+    struct DescribedPair<A: ::std::fmt::Debug, B>(A, B);
+
+    prevent_drop_panic!(
+        DescribedPair<A, B>,
+        forget_to_explicitly_drop_an_instance_of_DescribedPair,
+        generics = [A: ::std::fmt::Debug, B],
+        |this| format!("Leaked DescribedPair {:?}.", this.0)
+    );
+
+    #[test]
+    #[should_panic(expected = "Leaked DescribedPair \"left\".")]
+    fn prevent_drop_panic_describes_a_generic_value() {
+        ::std::mem::drop(DescribedPair("left", 2u8));
+    }
+
+    #[test]
+    fn prevent_drop_panic_describe_is_not_evaluated_if_value_is_not_dropped() {
+        let _ = ::std::mem::ManuallyDrop::new(DescribedPanicStrategy(1));
+    }
+
+    #[test]
+    fn prevent_drop_panic_messages_have_unique_incident_ids() {
+        fn incident() -> String {
+            let payload = ::std::panic::catch_unwind(|| ::std::mem::drop(PanicStrategy)).unwrap_err();
+            let message = payload.downcast::<String>().unwrap();
+            let (text, id) = message.split_at(message.find(" incident=").unwrap());
+            assert_eq!(text, "Forgot to explicitly drop an instance of PanicStrategy.");
+            id.to_string()
+        }
+
+        let first = incident();
+        let second = incident();
+        assert_eq!(first.len(), " incident=000000".len());
+        assert_ne!(first, second);
+    }
+
+    mod label_fn {
+        use std::fmt::Write;
+
+        pub struct Reported;
+
+        prevent_drop_panic!(Reported, prevent_drop_label_fn_Reported, message_fn = reported_message);
+
+        pub struct Custom<T>(pub T);
+
+        prevent_drop_panic!(
+            Custom<T>,
+            prevent_drop_label_fn_Custom,
+            link_name = "prevent_drop_tests_label_fn_Custom",
+            message_fn = custom_message,
+            generics = [T],
+            "Leaked a Custom."
+        );
+
+        pub struct Aborted;
+
+        prevent_drop_abort!(
+            Aborted,
+            prevent_drop_label_fn_Aborted,
+            message_fn = aborted_message,
+            "Leaked an Aborted."
+        );
+
+        fn message(write: fn(&mut dyn Write) -> ::std::fmt::Result) -> String {
+            let mut message = String::new();
+            write(&mut message).unwrap();
+            message
+        }
+
+        #[test]
+        fn message_fn_writes_the_message() {
+            let _ = ::std::mem::ManuallyDrop::new((Reported, Custom(0u8), Aborted));
+            assert_eq!(
+                message(reported_message),
+                "Forgot to explicitly drop an instance of Reported."
+            );
+            assert_eq!(message(custom_message), "Leaked a Custom.");
+            assert_eq!(message(aborted_message), "Leaked an Aborted.");
+        }
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Reported.")]
+        fn label_can_be_called_directly() {
+            prevent_drop_label_fn_Reported();
+        }
+
+        #[test]
+        #[should_panic(expected = "Leaked a Custom.")]
+        fn label_with_link_name_can_be_called_directly() {
+            prevent_drop_label_fn_Custom();
+        }
+
+        #[test]
+        fn label_respects_allow_drop() {
+            ::allow_drop(prevent_drop_label_fn_Reported);
+        }
+    }
+
+    mod link_name {
+        mod first {
+            pub struct Resource;
+
+            prevent_drop_panic!(
+                Resource,
+                prevent_drop_Resource,
+                link_name = "prevent_drop_tests_first_Resource"
+            );
+
+            pub struct Aborted;
+
+            prevent_drop_abort!(
+                Aborted,
+                prevent_drop_Aborted,
+                link_name = "prevent_drop_tests_first_Aborted",
+                "first::Aborted was dropped."
+            );
+        }
+
+        mod second {
+            pub struct Resource<T>(pub T);
+
+            prevent_drop_panic!(
+                Resource<T>,
+                prevent_drop_Resource,
+                link_name = "prevent_drop_tests_second_Resource",
+                generics = [T],
+                "second::Resource was dropped."
+            );
+
+            pub struct Aborted;
+
+            prevent_drop_abort!(
+                Aborted,
+                prevent_drop_Aborted,
+                link_name = "prevent_drop_tests_second_Aborted"
+            );
+        }
+
+        assert_prevent_drop!(first::Resource, first::Aborted, second::Resource<u8>, second::Aborted);
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Resource.")]
+        fn prevent_drop_panic_with_link_name_panics() {
+            ::std::mem::drop(first::Resource);
+        }
+
+        #[test]
+        #[should_panic(expected = "second::Resource was dropped.")]
+        fn prevent_drop_panic_with_link_name_and_same_label_panics() {
+            ::std::mem::drop(second::Resource(1u8));
+        }
+
+        #[test]
+        fn prevent_drop_with_link_name_does_not_fire_if_value_is_not_dropped() {
+            let _ = ::std::mem::ManuallyDrop::new(first::Aborted);
+            let _ = ::std::mem::ManuallyDrop::new(second::Aborted);
+        }
+    }
+
+    mod same_label {
+        mod first {
+            pub struct Resource;
+
+            prevent_drop_panic!(Resource, prevent_drop_Resource);
+
+            pub struct Aborted;
+
+            prevent_drop_abort!(Aborted, prevent_drop_Aborted);
+        }
+
+        mod second {
+            pub struct Resource;
+
+            prevent_drop_panic!(Resource, prevent_drop_Resource, "second::Resource was dropped.");
+
+            pub struct Aborted;
+
+            prevent_drop_abort!(Aborted, prevent_drop_Aborted);
+        }
+
+        pub struct Exported;
+
+        prevent_drop_panic!(Exported, prevent_drop_tests_same_label_Exported, no_mangle);
+
+        thread_local! {
+            static CALLS: ::std::cell::Cell<u32> = const { ::std::cell::Cell::new(0) };
+        }
+
+        fn called() {
+            CALLS.with(|calls| calls.set(calls.get() + 1));
+        }
+
+        mod third {
+            pub struct Called;
+
+            prevent_drop_callback!(Called, prevent_drop_Called, super::called);
+        }
+
+        mod fourth {
+            pub struct Called;
+
+            prevent_drop_callback!(Called, prevent_drop_Called, super::called);
+        }
+
+        pub struct CalledExported;
+
+        prevent_drop_callback!(
+            CalledExported,
+            prevent_drop_tests_same_label_CalledExported,
+            no_mangle,
+            self::called
+        );
+
+        pub struct CalledRenamed;
+
+        prevent_drop_callback!(
+            CalledRenamed,
+            prevent_drop_CalledRenamed,
+            link_name = "prevent_drop_tests_same_label_renamed",
+            self::called
+        );
+
+        mod symbol {
+            extern "Rust" {
+                pub fn prevent_drop_tests_same_label_Exported();
+                pub fn prevent_drop_tests_same_label_CalledExported();
+                pub fn prevent_drop_tests_same_label_renamed();
+            }
+        }
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Resource.")]
+        fn same_label_in_two_modules_links() {
+            let _ = ::std::mem::ManuallyDrop::new(first::Aborted);
+            let _ = ::std::mem::ManuallyDrop::new(second::Aborted);
+            ::std::mem::drop(first::Resource);
+        }
+
+        #[test]
+        #[should_panic(expected = "second::Resource was dropped.")]
+        fn same_label_keeps_the_message_of_each_module() {
+            ::std::mem::drop(second::Resource);
+        }
+
+        #[test]
+        fn every_strategy_exports_the_label_on_request() {
+            ::std::mem::drop(third::Called);
+            ::std::mem::drop(fourth::Called);
+            unsafe { symbol::prevent_drop_tests_same_label_CalledExported() };
+            unsafe { symbol::prevent_drop_tests_same_label_renamed() };
+            assert_eq!(CALLS.with(|calls| calls.get()), 4);
+            let _ = ::std::mem::ManuallyDrop::new(CalledExported);
+            let _ = ::std::mem::ManuallyDrop::new(CalledRenamed);
+        }
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Exported.")]
+        fn no_mangle_exports_the_label() {
+            let _ = ::std::mem::ManuallyDrop::new(Exported);
+            unsafe { symbol::prevent_drop_tests_same_label_Exported() };
+        }
+    }
+
+    mod inline {
+        pub struct PanicAlways;
+
+        prevent_drop_panic!(PanicAlways, prevent_drop_PanicAlways, inline = always);
+
+        pub struct PanicNever<T>(pub T);
+
+        prevent_drop_panic!(
+            PanicNever<T>,
+            prevent_drop_PanicNever,
+            inline = never,
+            generics = [T],
+            "PanicNever was dropped."
+        );
+
+        pub struct Described;
+
+        prevent_drop_panic!(Described, prevent_drop_Described, inline = always, |_this| "Described");
+
+        pub struct AbortAlways;
+
+        prevent_drop_abort!(AbortAlways, prevent_drop_AbortAlways, no_mangle, inline = always);
+
+        pub struct AbortNever;
+
+        prevent_drop_abort!(AbortNever, prevent_drop_AbortNever, inline = never, "AbortNever was dropped.");
+
+        assert_prevent_drop!(PanicAlways, PanicNever<u8>, Described, AbortAlways, AbortNever);
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of PanicAlways.")]
+        fn inline_always_panics() {
+            ::std::mem::drop(PanicAlways);
+        }
+
+        #[test]
+        #[should_panic(expected = "PanicNever was dropped.")]
+        fn inline_never_panics() {
+            ::std::mem::drop(PanicNever(1u8));
+        }
+
+        #[test]
+        #[should_panic(expected = "Described")]
+        fn inline_always_with_describe_panics() {
+            ::std::mem::drop(Described);
+        }
+
+        #[test]
+        fn inline_does_not_fire_if_value_is_not_dropped() {
+            let _ = ::std::mem::ManuallyDrop::new(AbortAlways);
+            let _ = ::std::mem::ManuallyDrop::new(AbortNever);
+        }
+    }
+
+    mod skipped_leak {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_SKIPPED_LEAK_CHILD";
+
+        struct SkippedLeak;
+
+        prevent_drop_panic!(SkippedLeak, forget_to_explicitly_drop_an_instance_of_SkippedLeak);
+
+        #[test]
+        #[allow(unreachable_code, unused_variables)]
+        fn prevent_drop_panic_skipped_leak_child() {
+            if env::var_os(CHILD).is_some() {
+                let x = SkippedLeak;
+                panic!("the original panic");
+                ::std::mem::drop(x);
+            }
+        }
+
+        #[test]
+        fn prevent_drop_panic_notes_skipped_leak_if_enabled() {
+            let output = Command::new(env::current_exe().unwrap())
+                .args([
+                    "--exact",
+                    "tests::skipped_leak::prevent_drop_panic_skipped_leak_child",
+                    "--nocapture",
+                ])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(!output.status.success());
+            assert!(stderr.contains("the original panic"), "unexpected stderr:\n{}", stderr);
+            assert_eq!(
+                stderr.contains(
+                    "prevent_drop: an instance of SkippedLeak was dropped while panicking"
+                ),
+                cfg!(feature = "note_skipped_leaks"),
+                "unexpected stderr:\n{}",
+                stderr
+            );
+        }
+    }
+
+    #[cfg(feature = "unsafe_unreachable")]
+    mod unreachable_strategy {
+        struct Unreachable;
+
+        impl Unreachable {
+            fn close(self) -> u32 {
+                let _zelf = ::std::mem::ManuallyDrop::new(self);
+                1
+            }
+        }
+
+        prevent_drop_assert_unreachable!(
+            unsafe Unreachable,
+            forget_to_explicitly_drop_an_instance_of_Unreachable
+        );
+
+        struct GenericUnreachable<T>(T);
+
+        prevent_drop_assert_unreachable!(
+            unsafe GenericUnreachable<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericUnreachable,
+            generics = [T],
+            "GenericUnreachable was dropped."
+        );
+
+        assert_prevent_drop!(Unreachable, GenericUnreachable<u8>);
+
+        #[test]
+        fn prevent_drop_assert_unreachable_allows_consumed_values() {
+            assert_eq!(Unreachable.close(), 1);
+            let _ = ::std::mem::ManuallyDrop::new(GenericUnreachable(1u8));
+        }
+
+        // Dropping is undefined behavior without `debug_assertions`, so
+        // only check the debug fallback.
+        #[cfg(debug_assertions)]
+        #[test]
+        #[should_panic(expected = "GenericUnreachable was dropped.")]
+        fn prevent_drop_assert_unreachable_panics_in_debug() {
+            ::std::mem::drop(GenericUnreachable(1u8));
+        }
+    }
+
+    mod explicit_strategy {
+        struct Linked;
+
+        impl Linked {
+            fn drop(self) {
+                let _self = ::std::mem::ManuallyDrop::new(self);
+            }
+        }
+
+        prevent_drop!(Linked, prevent_drop_explicit_Linked, strategy = link);
+
+        #[test]
+        fn prevent_drop_strategy_link() {
+            let x = Linked;
+            x.drop();
+        }
+
+        struct Aborted<T>(T);
+
+        prevent_drop!(
+            Aborted<T>,
+            prevent_drop_explicit_Aborted,
+            strategy = abort,
+            generics = [T]
+        );
+
+        #[test]
+        fn prevent_drop_strategy_abort_does_not_abort_if_value_is_not_dropped() {
+            let _ = ::std::mem::ManuallyDrop::new(Aborted(1u8));
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[derive(Debug)]
+        struct Panicked;
+
+        #[cfg(not(feature = "dry_run"))]
+        prevent_drop!(
+            Panicked,
+            prevent_drop_explicit_Panicked,
+            strategy = panic,
+            "Explicitly panicked."
+        );
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Explicitly panicked.")]
+        fn prevent_drop_strategy_panic_panics() {
+            ::std::mem::drop(Panicked);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[derive(Debug)]
+        struct PanickedDefault;
+
+        #[cfg(not(feature = "dry_run"))]
+        prevent_drop!(PanickedDefault, prevent_drop_explicit_PanickedDefault, strategy = panic);
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of PanickedDefault.")]
+        fn prevent_drop_strategy_panic_default_message_panics() {
+            ::std::mem::drop(PanickedDefault);
+        }
+    }
+
+    mod debug_only_strategy {
+        #[derive(Debug)]
+        struct DebugOnly;
+
+        prevent_drop_debug_only!(DebugOnly, forget_to_explicitly_drop_an_instance_of_DebugOnly);
+
+        struct GenericDebugOnly<T>(T);
+
+        prevent_drop_debug_only!(
+            GenericDebugOnly<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericDebugOnly,
+            generics = [T],
+            "GenericDebugOnly was dropped."
+        );
+
+        #[cfg(debug_assertions)]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of DebugOnly.")]
+        fn prevent_drop_debug_only_panics_with_debug_assertions() {
+            ::std::mem::drop(DebugOnly);
+        }
+
+        #[cfg(debug_assertions)]
+        #[test]
+        #[should_panic(expected = "GenericDebugOnly was dropped.")]
+        fn prevent_drop_debug_only_generic_panics_with_debug_assertions() {
+            ::std::mem::drop(GenericDebugOnly(1u8));
+        }
+
+        #[cfg(not(debug_assertions))]
+        #[test]
+        fn prevent_drop_debug_only_does_nothing_without_debug_assertions() {
+            ::std::mem::drop(DebugOnly);
+            ::std::mem::drop(GenericDebugOnly(1u8));
+        }
+
+        struct DebugOnlyAbort;
+
+        prevent_drop_debug_only!(
+            DebugOnlyAbort,
+            forget_to_explicitly_drop_an_instance_of_DebugOnlyAbort,
+            strategy = abort
+        );
+
+        #[test]
+        fn prevent_drop_debug_only_does_not_panic_if_value_is_not_dropped() {
+            let _ = ::std::mem::ManuallyDrop::new(DebugOnly);
+            let _ = ::std::mem::ManuallyDrop::new(DebugOnlyAbort);
+        }
+    }
+
+    mod qualified_paths {
+        mod inner {
+            #[derive(Debug)]
+            pub struct Resource;
+
+            #[derive(Debug)]
+            pub struct Handle;
+        }
+
+        prevent_drop_panic!(
+            crate::tests::qualified_paths::inner::Resource,
+            forget_to_explicitly_drop_an_instance_of_qualified_Resource
+        );
+
+        type Alias = inner::Handle;
+
+        prevent_drop_panic!(Alias, forget_to_explicitly_drop_an_instance_of_Alias);
+
+        #[test]
+        #[should_panic(
+            expected = "Forgot to explicitly drop an instance of crate::tests::qualified_paths::inner::Resource."
+        )]
+        fn prevent_drop_qualified_path_panics() {
+            ::std::mem::drop(inner::Resource);
+        }
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Alias.")]
+        fn prevent_drop_type_alias_guards_aliased_type() {
+            ::std::mem::drop(inner::Handle);
+        }
+    }
+
+    mod defuse {
+        #[derive(Debug)]
+        struct Defused;
+
+        impl Defused {
+            fn close(self) -> u32 {
+                let _self = self.disarm();
+                42
+            }
+        }
+
+        prevent_drop!(Defused, prevent_drop_defuse_Defused, defuse = disarm, strategy = panic);
+
+        #[derive(Debug)]
+        struct GenericDefused<T>(T);
+
+        impl<T> GenericDefused<T> {
+            fn into_inner(self) -> T {
+                let zelf = self.defuse();
+                unsafe { ::std::ptr::read(&zelf.0) }
+            }
+        }
+
+        prevent_drop!(
+            GenericDefused<T>,
+            prevent_drop_defuse_GenericDefused,
+            defuse = defuse,
+            strategy = panic,
+            generics = [T]
+        );
+
+        struct ManuallyDropStyle {
+            id: u32,
+        }
+
+        impl ManuallyDropStyle {
+            fn close(self) -> u32 {
+                let zelf = self.disarm();
+                zelf.id
+            }
+        }
+
+        prevent_drop!(
+            ManuallyDropStyle,
+            prevent_drop_defuse_ManuallyDropStyle,
+            defuse = disarm,
+            defuse_style = manually_drop,
+            strategy = panic
+        );
+
+        struct ForgetStyle<T>(T);
+
+        impl<T> ForgetStyle<T> {
+            fn close(self) {
+                self.disarm();
+            }
+        }
+
+        prevent_drop!(
+            ForgetStyle<T>,
+            prevent_drop_defuse_ForgetStyle,
+            defuse = disarm,
+            defuse_style = forget,
+            strategy = panic,
+            generics = [T]
+        );
+
+        #[test]
+        fn prevent_drop_defuse_does_not_panic() {
+            assert_eq!(Defused.close(), 42);
+            assert_eq!(GenericDefused(String::from("value")).into_inner(), "value");
+        }
+
+        #[test]
+        fn prevent_drop_defuse_styles_do_not_panic() {
+            assert_eq!(ManuallyDropStyle { id: 7 }.close(), 7);
+            ForgetStyle(String::from("value")).close();
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of ForgetStyle<T>.")]
+        fn prevent_drop_defuse_forget_style_keeps_the_guard() {
+            ::std::mem::drop(ForgetStyle(0u8));
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Defused.")]
+        fn prevent_drop_defuse_keeps_the_guard() {
+            ::std::mem::drop(Defused);
+        }
+    }
+
+    mod batch {
+        #[derive(Debug)]
+        struct First;
+
+        #[derive(Debug)]
+        struct Second;
+
+        #[derive(Debug)]
+        struct Third<T>(T);
+
+        prevent_drop! {
+            First => prevent_drop_batch_First(strategy = panic),
+            Second => prevent_drop_batch_Second(strategy = panic, "Leaked the second guard."),
+            Third<T> => prevent_drop_batch_Third(strategy = panic, generics = [T]),
+        }
+
+        assert_prevent_drop!(First, Second, Third<u8>);
+
+        #[test]
+        fn batch_guards_do_not_fire_when_consumed() {
+            let _ = ::std::mem::ManuallyDrop::new(First);
+            let _ = ::std::mem::ManuallyDrop::new(Second);
+            let _ = ::std::mem::ManuallyDrop::new(Third(0u8));
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of First.")]
+        fn batch_guard_first_fires() {
+            let _ = First;
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Leaked the second guard.")]
+        fn batch_guard_second_fires() {
+            let _ = Second;
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Third<T>.")]
+        fn batch_guard_third_fires() {
+            let _ = Third(0u8);
+        }
+    }
+
+    mod conditional {
+        #[derive(Debug)]
+        struct Handle(Option<u32>);
+
+        prevent_drop_if!(Handle, prevent_drop_conditional_Handle, |this| this.0.is_some());
+
+        #[derive(Debug)]
+        struct Slot<T> {
+            value: Option<T>,
+        }
+
+        impl<T> Slot<T> {
+            fn is_live(&self) -> bool {
+                self.value.is_some()
+            }
+        }
+
+        prevent_drop_if!(
+            Slot<T>,
+            prevent_drop_conditional_Slot,
+            |slot| slot.is_live(),
+            strategy = panic,
+            generics = [T],
+            "Leaked a live Slot."
+        );
+
+        assert_prevent_drop!(Handle, Slot<u8>);
+
+        #[test]
+        fn prevent_drop_if_ignores_values_failing_the_predicate() {
+            drop(Handle(None));
+            drop(Slot::<String> { value: None });
+        }
+
+        // With `never_panic` the default strategy aborts instead.
+        #[cfg(not(feature = "never_panic"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Handle.")]
+        fn prevent_drop_if_fires_when_the_predicate_holds() {
+            drop(Handle(Some(3)));
+        }
+
+        #[test]
+        #[should_panic(expected = "Leaked a live Slot.")]
+        fn prevent_drop_if_fires_for_generic_types() {
+            drop(Slot { value: Some(String::from("value")) });
+        }
+
+        #[test]
+        fn prevent_drop_if_registers_the_leaked_type() {
+            let result = ::std::panic::catch_unwind(|| drop(Slot { value: Some(1u8) }));
+            assert!(result.is_err());
+            assert!(::registered_leak_types().contains(&"Slot<T>"));
+        }
+    }
+
+    mod where_clause {
+        use std::collections::HashMap;
+        use std::hash::Hash;
+
+        #[derive(Debug)]
+        struct Cache<K, V>
+        where
+            K: Hash + Eq,
+            V: Clone,
+        {
+            map: HashMap<K, V>,
+        }
+
+        impl<K, V> Cache<K, V>
+        where
+            K: Hash + Eq,
+            V: Clone,
+        {
+            fn flush(self) -> Vec<V> {
+                let zelf = self.disarm();
+                zelf.map.values().cloned().collect()
+            }
+        }
+
+        prevent_drop!(
+            Cache<K, V>,
+            prevent_drop_where_clause_Cache,
+            defuse = disarm,
+            strategy = panic,
+            generics = [K, V],
+            where = [K: Hash + Eq, V: Clone]
+        );
+
+        #[derive(Debug)]
+        struct Sorted<T: Ord>(Vec<T>)
+        where
+            Vec<T>: Clone;
+
+        prevent_drop!(
+            Sorted<T>,
+            prevent_drop_where_clause_Sorted,
+            strategy = panic,
+            generics = [T: Ord],
+            where = [Vec<T>: Clone],
+            "Leaked a Sorted."
+        );
+
+        assert_prevent_drop!(Cache<u8, String>, Sorted<u8>);
+
+        #[test]
+        fn where_clause_guard_can_be_defused() {
+            let mut map = HashMap::new();
+            map.insert(1u8, String::from("value"));
+            assert_eq!(Cache { map }.flush(), ["value"]);
+            let sorted = ::std::mem::ManuallyDrop::new(Sorted(vec![1u8]));
+            assert_eq!(sorted.0, [1]);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Cache<K, V>.")]
+        fn where_clause_guard_fires() {
+            drop(Cache::<u8, u8> { map: HashMap::new() });
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Leaked a Sorted.")]
+        fn where_clause_guard_fires_with_message() {
+            drop(Sorted(vec![1u8]));
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[derive(Debug)]
+        struct Tagged<T>(T)
+        where
+            T: Copy;
+
+        #[cfg(not(feature = "dry_run"))]
+        prevent_drop!(
+            Tagged<T>,
+            prevent_drop_where_clause_Tagged,
+            strategy = cfg { _ => panic },
+            generics = [T],
+            where = [T: Copy]
+        );
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        fn where_clause_guard_reports_its_own_type() {
+            let result = ::std::panic::catch_unwind(|| drop(Tagged(1u8)));
+            let message = *result.unwrap_err().downcast::<String>().unwrap();
+            assert!(message.starts_with("Forgot to explicitly drop an instance of Tagged<T>."));
+            assert!(::registered_leak_types().contains(&"Tagged<T>"));
+        }
+    }
+
+    mod const_generics {
+        use std::mem::ManuallyDrop;
+
+        struct Ring<const N: usize> {
+            slots: [u32; N],
+        }
+
+        fn drain<const N: usize>(ring: ManuallyDrop<Ring<N>>) -> [u32; N] {
+            ring.slots
+        }
+
+        prevent_drop!(
+            Ring<N>,
+            prevent_drop_const_generics_Ring,
+            strategy = panic,
+            generics = [const N: usize]
+        );
+        prevent_drop_consume!(Ring<N>, generics = [const N: usize], fn drain() -> [u32; N] = drain);
+
+        struct Pair<T: Copy, const N: usize>([T; N]);
+
+        prevent_drop!(
+            Pair<T, N>,
+            prevent_drop_const_generics_Pair,
+            strategy = panic,
+            generics = [T: Copy, const N: usize],
+            where = [T: Copy],
+            "Leaked a Pair."
+        );
+
+        assert_prevent_drop!(Ring<4>, Pair<u8, 2>);
+
+        #[test]
+        fn const_generic_guard_can_be_consumed() {
+            let ring = Ring { slots: [1, 2, 3] };
+            assert_eq!(ring.drain(), [1, 2, 3]);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Ring<N>.")]
+        fn const_generic_guard_fires() {
+            drop(Ring { slots: [0; 8] });
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Leaked a Pair.")]
+        fn const_generic_guard_with_where_clause_fires() {
+            drop(Pair([1u8, 2]));
+        }
+    }
+
+    mod wrap {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static EVENTS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        fn events() -> Vec<String> {
+            EVENTS.with(|events| events.borrow().clone())
+        }
+
+        #[derive(Debug)]
+        struct Registered {
+            id: u32,
+        }
+
+        prevent_drop_wrap!(
+            Registered,
+            prevent_drop_wrap_Registered,
+            |this| {
+                EVENTS.with(|events| events.borrow_mut().push(format!("unregister {}", this.id)));
+            },
+            strategy = panic
+        );
+
+        #[derive(Debug)]
+        struct Tagged<T: ::std::fmt::Debug> {
+            tag: T,
+        }
+
+        prevent_drop_wrap!(
+            Tagged<T>,
+            prevent_drop_wrap_Tagged,
+            |this| {
+                EVENTS.with(|events| events.borrow_mut().push(format!("{:?}", this.tag)));
+            },
+            strategy = panic,
+            generics = [T: ::std::fmt::Debug],
+            "Leaked a Tagged."
+        );
+
+        assert_prevent_drop!(Registered, Tagged<u8>);
+
+        #[test]
+        fn wrap_skips_everything_when_defused() {
+            let registered = ::std::mem::ManuallyDrop::new(Registered { id: 1 });
+            let tagged = ::std::mem::ManuallyDrop::new(Tagged { tag: 'a' });
+            assert_eq!((registered.id, tagged.tag), (1, 'a'));
+            assert!(events().is_empty());
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        fn wrap_runs_the_body_before_the_guard() {
+            let result = ::std::panic::catch_unwind(|| drop(Registered { id: 7 }));
+            let message = result.unwrap_err().downcast::<String>().unwrap();
+            assert!(message.starts_with("Forgot to explicitly drop an instance of Registered."));
+            assert_eq!(events(), ["unregister 7"]);
+            assert!(::registered_leak_types().contains(&"Registered"));
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Leaked a Tagged.")]
+        fn wrap_fires_for_generic_types() {
+            drop(Tagged { tag: 3u8 });
+        }
+    }
+
+    mod async_close {
+        use std::future::{self, Future};
+        use std::mem::ManuallyDrop;
+        use std::pin::pin;
+        use std::ptr;
+        use std::task::{Context, Poll, Waker};
+
+        fn block_on<F: Future>(future: F) -> F::Output {
+            let mut future = pin!(future);
+            let mut cx = Context::from_waker(Waker::noop());
+            loop {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+        }
+
+        #[derive(Debug)]
+        struct Socket {
+            healthy: bool,
+        }
+
+        fn flush(socket: ManuallyDrop<Socket>) -> impl Future<Output = Result<(), String>> {
+            future::ready(if socket.healthy { Ok(()) } else { Err(String::from("broken pipe")) })
+        }
+
+        prevent_drop_async!(
+            Socket,
+            prevent_drop_async_close_Socket,
+            fn close() -> Result<(), String> = flush,
+            strategy = panic
+        );
+
+        #[derive(Debug)]
+        struct Channel<T> {
+            queued: Vec<T>,
+        }
+
+        fn drain<T>(channel: ManuallyDrop<Channel<T>>, limit: usize) -> impl Future<Output = usize> {
+            let queued = unsafe { ptr::read(&channel.queued) };
+            future::ready(queued.len().min(limit))
+        }
+
+        prevent_drop_async!(
+            Channel<T>,
+            prevent_drop_async_close_Channel,
+            fn shutdown(limit: usize) -> usize = drain,
+            strategy = panic,
+            generics = [T],
+            "Leaked a Channel."
+        );
+
+        #[test]
+        fn async_close_does_not_fire() {
+            assert_eq!(block_on(Socket { healthy: true }.close()), Ok(()));
+            assert_eq!(block_on(Socket { healthy: false }.close()), Err(String::from("broken pipe")));
+            assert_eq!(block_on(Channel { queued: vec![1, 2, 3] }.shutdown(2)), 2);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Socket.")]
+        fn dropping_without_async_close_fires() {
+            drop(Socket { healthy: true });
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Leaked a Channel.")]
+        fn dropping_generic_without_async_close_fires() {
+            drop(Channel { queued: vec![String::from("message")] });
+        }
+    }
+
+    mod leak_intentionally {
+        use std::cell::Cell;
+
+        thread_local! {
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct CountsDrops;
+
+        impl Drop for CountsDrops {
+            fn drop(&mut self) {
+                DROPS.with(|drops| drops.set(drops.get() + 1));
+            }
+        }
+
+        struct Leaked(CountsDrops);
+
+        prevent_drop!(
+            Leaked,
+            prevent_drop_leak_intentionally_Leaked,
+            leak_intentionally,
+            strategy = panic
+        );
+
+        struct GenericLeaked<T>(T, CountsDrops);
+
+        impl<T> GenericLeaked<T> {
+            fn close(self) {
+                let _zelf = self.disarm();
+            }
+        }
+
+        prevent_drop!(
+            GenericLeaked<T>,
+            prevent_drop_leak_intentionally_GenericLeaked,
+            leak_intentionally,
+            defuse = disarm,
+            strategy = panic,
+            generics = [T]
+        );
+
+        #[test]
+        fn leak_intentionally_skips_the_guard_and_destructors() {
+            Leaked(CountsDrops).leak_intentionally();
+            GenericLeaked(String::from("value"), CountsDrops).leak_intentionally();
+            GenericLeaked(0u8, CountsDrops).close();
+            assert_eq!(DROPS.with(Cell::get), 0);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Leaked.")]
+        fn leak_intentionally_keeps_the_guard() {
+            let _ = Leaked(CountsDrops);
+        }
+    }
+
+    mod with_cleanup {
+        use std::mem::ManuallyDrop;
+        use std::ptr;
+
+        struct Handle(u32);
+
+        prevent_drop!(Handle, prevent_drop_with_cleanup_Handle, with_cleanup, strategy = panic);
+
+        struct Buffer<T>(Vec<T>);
+
+        prevent_drop!(
+            Buffer<T>,
+            prevent_drop_with_cleanup_Buffer,
+            with_cleanup,
+            strategy = panic,
+            generics = [T]
+        );
+
+        #[test]
+        fn with_cleanup_hands_the_value_to_the_closure() {
+            assert_eq!(Handle(3).with_cleanup(|handle| handle.0), 3);
+            let items = Buffer(vec![1, 2]).with_cleanup(|buffer: ManuallyDrop<Buffer<u8>>| unsafe {
+                ptr::read(&buffer.0)
+            });
+            assert_eq!(items, [1, 2]);
+        }
+
+        #[test]
+        fn with_cleanup_does_not_fire_the_guard() {
+            let mut called = false;
+            Handle(3).with_cleanup(|_handle| called = true);
+            assert!(called);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Handle.")]
+        fn with_cleanup_keeps_the_guard() {
+            let _ = Handle(3);
+        }
+    }
+
+    mod consume {
+        use std::mem::ManuallyDrop;
+        use std::ptr;
+
+        struct Context {
+            healthy: bool,
+        }
+
+        #[derive(Debug, PartialEq)]
+        struct Error(String);
+
+        struct Connection {
+            name: String,
+        }
+
+        fn close(connection: ManuallyDrop<Connection>, context: &Context) -> Result<(), Error> {
+            let name = unsafe { ptr::read(&connection.name) };
+            if context.healthy {
+                Ok(())
+            } else {
+                Err(Error(name))
+            }
+        }
+
+        prevent_drop_panic!(Connection, forget_to_explicitly_drop_an_instance_of_Connection);
+        prevent_drop_consume!(Connection, fn try_consume(context: &Context) -> Result<(), Error> = close);
+
+        struct Buffer<T>(T);
+
+        fn into_value<T>(buffer: ManuallyDrop<Buffer<T>>) -> T {
+            unsafe { ptr::read(&buffer.0) }
+        }
+
+        prevent_drop_panic!(
+            Buffer<T>,
+            forget_to_explicitly_drop_an_instance_of_consume_Buffer,
+            generics = [T]
+        );
+        prevent_drop_consume!(Buffer<T>, generics = [T], fn into_value() -> T = into_value);
+
+        fn reconnect(connection: Connection, context: &Context) -> Result<u32, Error> {
+            connection.try_consume(context)?;
+            Ok(1)
+        }
+
+        #[test]
+        fn prevent_drop_consume_ok() {
+            let connection = Connection { name: String::from("db") };
+            assert_eq!(reconnect(connection, &Context { healthy: true }), Ok(1));
+        }
+
+        #[test]
+        fn prevent_drop_consume_err() {
+            let connection = Connection { name: String::from("db") };
+            assert_eq!(
+                reconnect(connection, &Context { healthy: false }),
+                Err(Error(String::from("db")))
+            );
+        }
+
+        #[test]
+        fn prevent_drop_consume_generic() {
+            assert_eq!(Buffer(String::from("value")).into_value(), "value");
+        }
+    }
+
+    mod ffi {
+        use std::cell::RefCell;
+        use std::os::raw::c_int;
+
+        thread_local! {
+            static CLOSED: RefCell<Vec<c_int>> = const { RefCell::new(Vec::new()) };
+        }
+
+        unsafe fn close(fd: c_int) -> c_int {
+            CLOSED.with(|closed| closed.borrow_mut().push(fd));
+            if fd < 0 {
+                -1
+            } else {
+                0
+            }
+        }
+
+        struct Fd(c_int);
+
+        prevent_drop_ffi!(Fd, forget_to_explicitly_drop_an_instance_of_ffi_Fd, |fd| unsafe {
+            close(fd.0)
+        });
+
+        struct Socket {
+            fd: c_int,
+        }
+
+        prevent_drop_ffi!(
+            Socket,
+            forget_to_explicitly_drop_an_instance_of_ffi_Socket,
+            fn shutdown() -> Result<(), c_int> = |socket| match unsafe { close(socket.fd) } {
+                0 => Ok(()),
+                error => Err(error),
+            },
+            strategy = panic
+        );
+
+        #[test]
+        fn prevent_drop_ffi_returns_close_result() {
+            assert_eq!(Fd(3).close(), 0);
+            assert_eq!(Fd(-1).close(), -1);
+            assert_eq!(CLOSED.with(|closed| closed.borrow().clone()), [3, -1]);
+        }
+
+        #[test]
+        fn prevent_drop_ffi_custom_method() {
+            assert_eq!(Socket { fd: 4 }.shutdown(), Ok(()));
+            assert_eq!(Socket { fd: -2 }.shutdown(), Err(-1));
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Socket.")]
+        fn prevent_drop_ffi_guards_the_handle() {
+            drop(Socket { fd: 5 });
+        }
+    }
+
+    mod config {
+        use std::mem::ManuallyDrop;
+
+        pub struct Defaults;
+
+        prevent_drop! {
+            ty: Defaults,
+            label: prevent_drop_config_Defaults,
+        }
+
+        pub struct Described;
+
+        prevent_drop! {
+            message: "Leaked a Described.",
+            strategy: panic,
+            label: prevent_drop_config_Described,
+            ty: Described,
+            defuse: disarm,
+            defuse_style: forget,
+        }
+
+        pub struct Buffer<T: Clone>(pub Vec<T>);
+
+        prevent_drop! {
+            ty: Buffer<T>,
+            label: prevent_drop_config_Buffer,
+            strategy: panic,
+            generics: [T],
+            where: [T: Clone],
+            methods: [leak_intentionally, with_cleanup],
+        }
+
+        pub struct Inlined;
+
+        prevent_drop!(ty: Inlined, label: prevent_drop_config_Inlined, strategy: abort, inline: always);
+
+        pub struct Linked;
+
+        prevent_drop!(ty: Linked, label: prevent_drop_config_Linked, strategy: link, inline: never);
+
+        pub struct Constant;
+
+        prevent_drop!(ty: Constant, label: unused, strategy: const);
+
+        pub struct Selected;
+
+        prevent_drop! {
+            ty: Selected,
+            label: prevent_drop_config_Selected,
+            strategy: cfg { unix => panic, _ => panic },
+        }
+
+        assert_prevent_drop!(Defaults, Described, Buffer<u8>, Inlined, Linked, Constant, Selected);
+
+        #[test]
+        fn config_with_defaults_does_not_fire_if_value_is_not_dropped() {
+            let _ = ManuallyDrop::new(Defaults);
+            let _ = ManuallyDrop::new(Inlined);
+            let _ = ManuallyDrop::new(Linked);
+            let _ = ManuallyDrop::new(Constant);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Leaked a Described.")]
+        fn config_fields_in_any_order_set_the_message() {
+            ::std::mem::drop(Described);
+        }
+
+        #[test]
+        fn config_generates_the_methods() {
+            Described.disarm();
+            Buffer(vec![1u8]).leak_intentionally();
+            assert_eq!(Buffer(vec![2u8]).with_cleanup(|buffer| buffer.0[0]), 2);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Selected.")]
+        fn config_accepts_cfg_strategies() {
+            ::std::mem::drop(Selected);
+        }
+    }
+
+    mod newtype {
+        mod foreign {
+            #[derive(Debug, PartialEq)]
+            pub struct Socket {
+                pub fd: u32,
+                pub open: bool,
+            }
+        }
+
+        prevent_drop_newtype!(
+            /// A socket that must be closed explicitly.
+            #[derive(Debug)]
+            pub(crate) Socket,
+            foreign::Socket,
+            prevent_drop_newtype_Socket,
+            strategy = panic
+        );
+
+        impl Socket {
+            fn close(mut self) -> foreign::Socket {
+                self.open = false;
+                self.into_inner()
+            }
+        }
+
+        #[test]
+        fn newtype_derefs_and_returns_the_inner_value() {
+            let mut socket = Socket::new(foreign::Socket { fd: 3, open: true });
+            assert_eq!(socket.fd, 3);
+            socket.fd = 4;
+            assert_eq!(socket.close(), foreign::Socket { fd: 4, open: false });
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Socket.")]
+        fn newtype_guards_the_wrapper() {
+            drop(Socket::new(foreign::Socket { fd: 5, open: true }));
+        }
+    }
+
+    mod trait_guard {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static COMMITTED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        trait Transaction {
+            fn insert(&mut self, key: &str);
+            fn commit(self: Box<Self>, message: &str) -> Result<usize, String>;
+            fn rollback(self: Box<Self>);
+        }
+
+        struct InMemory {
+            keys: Vec<String>,
+        }
+
+        impl Transaction for InMemory {
+            fn insert(&mut self, key: &str) {
+                self.keys.push(key.to_string());
+            }
+
+            fn commit(self: Box<Self>, message: &str) -> Result<usize, String> {
+                if self.keys.is_empty() {
+                    return Err(format!("nothing to commit for {}", message));
+                }
+                COMMITTED.with(|committed| committed.borrow_mut().extend(self.keys.iter().cloned()));
+                Ok(self.keys.len())
+            }
+
+            fn rollback(self: Box<Self>) {}
+        }
+
+        struct ReadOnly;
+
+        impl Transaction for ReadOnly {
+            fn insert(&mut self, _: &str) {}
+
+            fn commit(self: Box<Self>, _: &str) -> Result<usize, String> {
+                Ok(0)
+            }
+
+            fn rollback(self: Box<Self>) {}
+        }
+
+        prevent_drop_trait!(Pending, dyn Transaction, prevent_drop_trait_Pending, {
+            fn commit(message: &str) -> Result<usize, String>;
+            fn rollback();
+        }, strategy = panic, "Forgot to commit or roll back a transaction.");
+
+        fn begin(read_only: bool) -> Pending {
+            if read_only {
+                Pending::new(Box::new(ReadOnly))
+            } else {
+                Pending::new(Box::new(InMemory { keys: Vec::new() }))
+            }
+        }
+
+        #[test]
+        fn trait_guard_finalizers_consume_the_transaction() {
+            let mut transaction = begin(false);
+            transaction.insert("a");
+            transaction.insert("b");
+            assert_eq!(transaction.commit("insert"), Ok(2));
+            assert_eq!(COMMITTED.with(|committed| committed.borrow().clone()), ["a", "b"]);
+
+            assert_eq!(begin(false).commit("empty"), Err(String::from("nothing to commit for empty")));
+            assert_eq!(begin(true).commit("read"), Ok(0));
+            let mut transaction = begin(false);
+            transaction.insert("c");
+            transaction.rollback();
+            assert_eq!(COMMITTED.with(|committed| committed.borrow().len()), 2);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to commit or roll back a transaction.")]
+        fn trait_guard_fires_without_finalizer() {
+            let mut transaction = begin(false);
+            transaction.insert("a");
+        }
+    }
+
+    mod ptr {
+        use std::cell::RefCell;
+        use std::os::raw::{c_int, c_void};
+
+        thread_local! {
+            static FREED: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+        }
+
+        unsafe fn free(ptr: *mut c_void) {
+            FREED.with(|freed| freed.borrow_mut().push(ptr as usize));
+        }
+
+        unsafe fn destroy(ptr: *mut c_void) -> c_int {
+            free(ptr);
+            if ptr.is_null() {
+                -1
+            } else {
+                0
+            }
+        }
+
+        struct Handle(*mut c_void);
+
+        prevent_drop_ptr!(
+            Handle,
+            forget_to_explicitly_drop_an_instance_of_ptr_Handle,
+            close,
+            |ptr: *mut c_void| unsafe { free(ptr) },
+            strategy = panic
+        );
+
+        struct Context(*mut c_void);
+
+        prevent_drop_ptr!(
+            Context,
+            forget_to_explicitly_drop_an_instance_of_ptr_Context,
+            destroy,
+            |ptr: *mut c_void| -> c_int { unsafe { destroy(ptr) } },
+            strategy = panic,
+            "Leaked a Context."
+        );
+
+        fn freed() -> Vec<usize> {
+            FREED.with(|freed| freed.borrow().clone())
+        }
+
+        #[test]
+        fn prevent_drop_ptr_frees_once() {
+            Handle(8 as *mut c_void).close();
+            assert_eq!(freed(), [8]);
+            assert_eq!(Context(16 as *mut c_void).destroy(), 0);
+            assert_eq!(Context(::std::ptr::null_mut()).destroy(), -1);
+            assert_eq!(freed(), [8, 16, 0]);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        fn prevent_drop_ptr_guard_does_not_free() {
+            let result = ::std::panic::catch_unwind(|| drop(Context(32 as *mut c_void)));
+            assert!(result.is_err());
+            assert!(freed().is_empty());
+        }
+    }
+
+    mod cfg_strategy {
+        #[derive(Debug)]
+        struct FirstMatch;
+
+        prevent_drop!(
+            FirstMatch,
+            prevent_drop_cfg_FirstMatch,
+            strategy = cfg { not(test) => abort, test => panic, debug_assertions => abort },
+            "FirstMatch used the panic strategy."
+        );
+
+        #[derive(Debug)]
+        struct Fallback<T>(T);
+
+        prevent_drop!(
+            Fallback<T>,
+            prevent_drop_cfg_Fallback,
+            strategy = cfg { not(test) => abort, _ => panic },
+            generics = [T]
+        );
+
+        assert_prevent_drop!(FirstMatch, Fallback<u8>);
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "FirstMatch used the panic strategy.")]
+        fn prevent_drop_cfg_uses_first_matching_branch() {
+            ::std::mem::drop(FirstMatch);
+        }
+
+        #[cfg(not(feature = "dry_run"))]
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of Fallback<T>.")]
+        fn prevent_drop_cfg_uses_fallback() {
+            ::std::mem::drop(Fallback(1u8));
+        }
+    }
+
+    mod const_strategy {
+        struct Constant;
+
+        impl Constant {
+            fn close(self) {
+                let _zelf = ::std::mem::ManuallyDrop::new(self);
+            }
+        }
+
+        prevent_drop_const!(Constant);
+
+        struct GenericConstant<T>(T);
+
+        impl<T> GenericConstant<T> {
+            fn close(self) -> T {
+                let zelf = ::std::mem::ManuallyDrop::new(self);
+                unsafe { ::std::ptr::read(&zelf.0) }
+            }
+        }
+
+        prevent_drop!(
+            GenericConstant<T>,
+            prevent_drop_const_GenericConstant,
+            strategy = const,
+            generics = [T],
+            "GenericConstant was dropped."
+        );
+
+        assert_prevent_drop!(Constant, GenericConstant<u8>);
+
+        #[test]
+        fn prevent_drop_const_compiles_if_value_is_consumed() {
+            let x = Constant;
+            x.close();
+            let y = GenericConstant(1u8);
+            assert_eq!(y.close(), 1);
+        }
+
+        #[test]
+        fn prevent_drop_const_default_message_matches_the_run_time_one() {
+            use constant::ConstDropMessage;
+
+            assert_eq!(
+                <Constant as ConstDropMessage>::MESSAGE,
+                ::default_leak_message("Constant")
+            );
+        }
+    }
+
+    struct Buffer<T>(T);
+
+    impl<T> Buffer<T> {
+        fn drop(self) -> T {
+            let zelf = ::std::mem::ManuallyDrop::new(self);
+            unsafe { ::std::ptr::read(&zelf.0) }
+        }
+    }
+
+    prevent_drop!(Buffer<T>, prevent_drop_Buffer, generics = [T]);
+
+    #[test]
+    fn prevent_drop_generic() {
+        let b = Buffer(String::from("contents"));
+        assert_eq!(b.drop(), "contents");
+    }
+
+    #[derive(Debug)]
+    struct GenericPanicStrategy<T>(T);
+
+    prevent_drop_panic!(
+        GenericPanicStrategy<T>,
+        forget_to_explicitly_drop_an_instance_of_GenericPanicStrategy,
+        generics = [T]
+    );
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of GenericPanicStrategy<T>.")]
+    fn prevent_drop_panic_generic_panics() {
+        let x = GenericPanicStrategy(1u32);
+        ::std::mem::drop(x);
+    }
+
+    #[derive(Debug)]
+    struct PairPanicStrategy<A, B>(A, B);
+
+    prevent_drop_panic!(
+        PairPanicStrategy<A, B>,
+        forget_to_explicitly_drop_an_instance_of_PairPanicStrategy,
+        generics = [A, B],
+        "PairPanicStrategy was dropped."
+    );
+
+    #[test]
+    #[should_panic(expected = "PairPanicStrategy was dropped.")]
+    fn prevent_drop_panic_two_generics_panics() {
+        let x = PairPanicStrategy(1u32, "two");
+        ::std::mem::drop(x);
+    }
+
+    #[test]
+    fn prevent_drop_panic_two_generics_does_not_panic_if_value_is_not_dropped() {
+        let _ = ::std::mem::ManuallyDrop::new(PairPanicStrategy(1u32, "two"));
+        let _ = ::std::mem::ManuallyDrop::new(PairPanicStrategy("one", 2u8));
+    }
+
+    #[derive(Debug)]
+    struct BorrowPanicStrategy<'a>(&'a mut u32);
+
+    prevent_drop_panic!(
+        BorrowPanicStrategy<'a>,
+        forget_to_explicitly_drop_an_instance_of_BorrowPanicStrategy,
+        generics = ['a]
+    );
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of BorrowPanicStrategy<'a>.")]
+    fn prevent_drop_panic_lifetime_panics() {
+        let mut value = 0;
+        let x = BorrowPanicStrategy(&mut value);
+        *x.0 += 1;
+        ::std::mem::drop(x);
+    }
+
+    mod abort_hook {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_ABORT_HOOK_CHILD";
+
+        #[inline(never)]
+        fn hook() {
+            eprintln!("abort hook ran");
+        }
+
+        #[inline(never)]
+        fn diverging_hook() -> ! {
+            ::std::process::exit(3);
+        }
+
+        struct AbortHook;
+
+        const ABORT_HOOK_LINE: u32 = line!() + 1;
+        prevent_drop_abort!(AbortHook, forget_to_explicitly_drop_an_instance_of_AbortHook, hook);
+
+        struct GenericAbortHook<T>(T);
+
+        prevent_drop_abort!(
+            GenericAbortHook<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericAbortHook,
+            generics = [T],
+            self::diverging_hook
+        );
+
+        // Only drops the value when spawned by
+        // `prevent_drop_abort_reports_and_runs_hook_before_aborting`, since
+        // aborting takes the whole test binary down.
+        struct AbortMessage<T>(T);
+
+        prevent_drop_abort!(
+            AbortMessage<T>,
+            forget_to_explicitly_drop_an_instance_of_AbortMessage,
+            generics = [T],
+            "AbortMessage was dropped, check the shutdown order."
+        );
+
+        struct AbortMessageHook;
+
+        prevent_drop_abort!(
+            AbortMessageHook,
+            forget_to_explicitly_drop_an_instance_of_AbortMessageHook,
+            "AbortMessageHook was dropped.",
+            hook
+        );
+
+        #[test]
+        fn prevent_drop_abort_hook_child() {
+            match env::var(CHILD).as_ref().map(String::as_str) {
+                Ok("hook") => ::std::mem::drop(AbortHook),
+                Ok("message") => ::std::mem::drop(AbortMessage(1u8)),
+                Ok("message_hook") => ::std::mem::drop(AbortMessageHook),
+                _ => {}
+            }
+            let _ = ::std::mem::ManuallyDrop::new(GenericAbortHook(1u8));
+        }
+
+        fn abort_child(case: &str) -> String {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", "tests::abort_hook::prevent_drop_abort_hook_child", "--nocapture"])
+                .env(CHILD, case)
+                // Keep backtraces out of the expected output.
+                .env_remove("RUST_BACKTRACE")
+                .output()
+                .unwrap();
+            assert!(!output.status.success());
+            String::from_utf8_lossy(&output.stderr).into_owned()
+        }
+
+        #[test]
+        fn prevent_drop_abort_prints_custom_message() {
+            let stderr = abort_child("message");
+            assert!(
+                stderr.contains("AbortMessage was dropped, check the shutdown order."),
+                "unexpected stderr:\n{}",
+                stderr
+            );
+            assert!(!stderr.contains("prevent_drop: an instance of"));
+            let stderr = abort_child("message_hook");
+            assert!(
+                stderr.contains("AbortMessageHook was dropped.\nabort hook ran"),
+                "unexpected stderr:\n{}",
+                stderr
+            );
+        }
+
+        #[test]
+        fn prevent_drop_abort_reports_and_runs_hook_before_aborting() {
+            let stderr = abort_child("hook");
+            let report = format!(
+                "prevent_drop: an instance of AbortHook (defined at {}:{}) was dropped",
+                file!(),
+                ABORT_HOOK_LINE
+            );
+            // A backtrace may be printed between the report and the hook.
+            let lines: Vec<&str> = stderr.lines().collect();
+            let reported = lines.iter().position(|line| *line == report);
+            let hooked = lines.iter().position(|line| *line == "abort hook ran");
+            assert!(reported.is_some(), "unexpected stderr:\n{}", stderr);
+            assert!(hooked > reported, "unexpected stderr:\n{}", stderr);
+        }
+    }
+
+    mod once_strategy {
+        use reset_drop_allowance;
+
+        #[derive(Debug)]
+        struct DroppedOnce;
+
+        prevent_drop_once!(DroppedOnce, prevent_drop_once_DroppedOnce);
+
+        #[derive(Debug)]
+        struct DroppedTwice;
+
+        prevent_drop_once!(DroppedTwice, prevent_drop_once_DroppedTwice);
+
+        #[derive(Debug)]
+        struct AllowTwo<T>(T);
+
+        prevent_drop_once!(
+            AllowTwo<T>,
+            prevent_drop_once_AllowTwo,
+            allow = 2,
+            generics = [T],
+            "AllowTwo was dropped too often."
+        );
+
+        #[derive(Debug)]
+        struct Reset;
+
+        prevent_drop_once!(Reset, prevent_drop_once_Reset);
+
+        #[test]
+        fn prevent_drop_once_allows_one_drop() {
+            ::std::mem::drop(DroppedOnce);
+            let _ = ::std::mem::ManuallyDrop::new(DroppedOnce);
+        }
+
+        #[test]
+        #[should_panic(expected = "Forgot to explicitly drop an instance of DroppedTwice.")]
+        fn prevent_drop_once_panics_on_second_drop() {
+            ::std::mem::drop(DroppedTwice);
+            ::std::mem::drop(DroppedTwice);
+        }
 
-#[cfg(test)]
-mod tests {
-    struct Resource;
-    struct Context;
-    struct Error;
+        #[test]
+        #[should_panic(expected = "AllowTwo was dropped too often.")]
+        fn prevent_drop_once_allow_counts_across_instantiations() {
+            ::std::mem::drop(AllowTwo(1u8));
+            ::std::mem::drop(AllowTwo("two"));
+            ::std::mem::drop(AllowTwo(3u32));
+        }
 
-    impl Resource {
-        fn drop(self, _context: &Context) -> Error {
-            let _self = ::std::mem::ManuallyDrop::new(self);
-            Error
+        #[test]
+        fn prevent_drop_once_reset_restores_allowance() {
+            ::std::mem::drop(Reset);
+            reset_drop_allowance::<Reset>();
+            ::std::mem::drop(Reset);
         }
     }
 
-    prevent_drop!(Resource, prevent_drop_Resource);
+    mod callback_strategy {
+        use std::sync::atomic::{AtomicUsize, Ordering};
 
-    #[test]
-    fn test() {
-        let c = Context;
-        let r = Resource;
-        r.drop(&c);
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn count_drop() {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        struct CallbackStrategy;
+
+        prevent_drop_callback!(
+            CallbackStrategy,
+            forget_to_explicitly_drop_an_instance_of_CallbackStrategy,
+            count_drop
+        );
+
+        struct GenericCallbackStrategy<T>(T);
+
+        prevent_drop_callback!(
+            GenericCallbackStrategy<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericCallbackStrategy,
+            generics = [T],
+            self::count_drop
+        );
+
+        #[test]
+        fn prevent_drop_callback_calls_callback() {
+            let before = DROPS.load(Ordering::SeqCst);
+            ::std::mem::drop(CallbackStrategy);
+            ::std::mem::drop(GenericCallbackStrategy(1u8));
+            let _ = ::std::mem::ManuallyDrop::new(CallbackStrategy);
+            let _ = ::std::mem::ManuallyDrop::new(GenericCallbackStrategy("value"));
+            assert_eq!(DROPS.load(Ordering::SeqCst) - before, 2);
+        }
     }
 
-    #[derive(Debug)]
-    struct PanicStrategy;
+    mod count_strategy {
+        struct CountStrategy;
 
-    prevent_drop_panic!(PanicStrategy, forget_to_explicitly_drop_an_instance_of_PanicStrategy);
+        prevent_drop_count!(CountStrategy, forget_to_explicitly_drop_an_instance_of_CountStrategy);
 
-    #[test]
-    #[should_panic(expected = "Forgot to explicitly drop an instance of PanicStrategy.")]
-    fn prevent_drop_panic_panics() {
-        let x = PanicStrategy;
-        ::std::mem::drop(x);
+        struct GenericCountStrategy<T>(T);
+
+        prevent_drop_count!(
+            GenericCountStrategy<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericCountStrategy,
+            generics = [T]
+        );
+
+        // The assertions compare exact totals, which a second test
+        // resetting the counter or leaking a counted value would change.
+        #[test]
+        fn prevent_drop_count_counts_drops() {
+            ::reset_leak_count();
+            assert_eq!(::leak_count(), 0);
+            ::std::mem::drop(CountStrategy);
+            ::std::mem::drop(CountStrategy);
+            ::std::mem::drop(GenericCountStrategy(1u8));
+            let _ = ::std::mem::ManuallyDrop::new(CountStrategy);
+            let _ = ::std::mem::ManuallyDrop::new(GenericCountStrategy("value"));
+            assert_eq!(::leak_count(), 3);
+            assert_eq!(::leak_count_for("CountStrategy"), 2);
+            assert_eq!(::leak_count_for("GenericCountStrategy<T>"), 1);
+            assert_eq!(::leak_count_for("Unguarded"), 0);
+            assert_eq!(
+                ::leak_counts(),
+                [("CountStrategy", 2), ("GenericCountStrategy<T>", 1)]
+            );
+            ::reset_leak_count();
+            assert_eq!(::leak_count(), 0);
+            assert_eq!(::leak_count_for("CountStrategy"), 0);
+            assert!(::leak_counts().is_empty());
+        }
     }
 
-    #[test]
-    #[should_panic(expected = "Something else happened that I need to know about!")]
-    #[allow(unreachable_code, unused_variables)]
-    fn prevent_drop_panic_does_not_panic_while_panicking() {
-        let x = PanicStrategy;
-        panic!("Something else happened that I need to know about!");
-        ::std::mem::drop(x);
+    #[cfg(feature = "tracing")]
+    mod trace_strategy {
+        use std::fmt;
+        use std::sync::{Arc, Mutex};
+
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Level, Metadata, Subscriber};
+
+        #[derive(Debug, PartialEq)]
+        struct Captured {
+            level: Level,
+            target: String,
+            type_name: String,
+            message: String,
+        }
+
+        #[derive(Default)]
+        struct Fields {
+            type_name: String,
+            message: String,
+        }
+
+        impl Visit for Fields {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                if field.name() == "type_name" {
+                    self.type_name = value.to_string();
+                }
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.message = format!("{:?}", value);
+                }
+            }
+        }
+
+        #[derive(Clone, Default)]
+        struct CaptureSubscriber {
+            events: Arc<Mutex<Vec<Captured>>>,
+        }
+
+        impl Subscriber for CaptureSubscriber {
+            fn enabled(&self, _metadata: &Metadata) -> bool {
+                true
+            }
+
+            fn new_span(&self, _span: &Attributes) -> Id {
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _span: &Id, _values: &Record) {}
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+            fn event(&self, event: &Event) {
+                let mut fields = Fields::default();
+                event.record(&mut fields);
+                self.events.lock().unwrap().push(Captured {
+                    level: *event.metadata().level(),
+                    target: event.metadata().target().to_string(),
+                    type_name: fields.type_name,
+                    message: fields.message,
+                });
+            }
+
+            fn enter(&self, _span: &Id) {}
+
+            fn exit(&self, _span: &Id) {}
+        }
+
+        fn capture<F: FnOnce()>(f: F) -> Vec<Captured> {
+            let subscriber = CaptureSubscriber::default();
+            let events = subscriber.events.clone();
+            tracing::subscriber::with_default(subscriber, f);
+            let events = events.lock().unwrap().drain(..).collect();
+            events
+        }
+
+        struct TraceStrategy;
+
+        prevent_drop_trace!(TraceStrategy, forget_to_explicitly_drop_an_instance_of_TraceStrategy);
+
+        struct TraceStrategyWithMessage<T>(T);
+
+        prevent_drop_trace!(
+            TraceStrategyWithMessage<T>,
+            forget_to_explicitly_drop_an_instance_of_TraceStrategyWithMessage,
+            generics = [T],
+            "TraceStrategyWithMessage was leaked!"
+        );
+
+        #[test]
+        fn prevent_drop_trace_emits_error_event() {
+            assert_eq!(
+                capture(|| ::std::mem::drop(TraceStrategy)),
+                vec![Captured {
+                    level: Level::ERROR,
+                    target: module_path!().to_string(),
+                    type_name: "TraceStrategy".to_string(),
+                    message: "Forgot to explicitly drop an instance of TraceStrategy.".to_string(),
+                }]
+            );
+        }
+
+        #[test]
+        fn prevent_drop_trace_emits_custom_message() {
+            let events = capture(|| ::std::mem::drop(TraceStrategyWithMessage(1u8)));
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].type_name, "TraceStrategyWithMessage<T>");
+            assert_eq!(events[0].message, "TraceStrategyWithMessage was leaked!");
+        }
+
+        #[test]
+        fn prevent_drop_trace_does_not_emit_if_value_is_not_dropped() {
+            assert!(capture(|| {
+                let _ = ::std::mem::ManuallyDrop::new(TraceStrategy);
+            })
+            .is_empty());
+        }
     }
 
-    #[test]
-    fn prevent_drop_panic_does_not_panic_if_value_is_dropped() {
-        let _ = ::std::mem::ManuallyDrop::new(PanicStrategy);
+    #[cfg(not(feature = "dry_run"))]
+    mod eprintln_strategy {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_EPRINTLN_CHILD";
+
+        struct Printed;
+
+        prevent_drop_eprintln!(Printed, forget_to_explicitly_drop_an_instance_of_Printed);
+
+        struct GenericPrinted<T>(T);
+
+        prevent_drop!(
+            GenericPrinted<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericPrinted,
+            strategy = eprintln,
+            generics = [T],
+            "GenericPrinted was dropped."
+        );
+
+        #[test]
+        fn prevent_drop_eprintln_child() {
+            if env::var_os(CHILD).is_some() {
+                ::std::mem::drop(Printed);
+                ::std::mem::drop(GenericPrinted(1u8));
+                eprintln!("child continued");
+            }
+        }
+
+        #[test]
+        fn prevent_drop_eprintln_prints_and_continues() {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", "tests::eprintln_strategy::prevent_drop_eprintln_child", "--nocapture"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "unexpected stderr:\n{}", stderr);
+            let expected = [
+                "Forgot to explicitly drop an instance of Printed.",
+                "GenericPrinted was dropped.",
+                "child continued",
+            ];
+            let lines: Vec<&str> = stderr.lines().filter(|line| expected.contains(line)).collect();
+            assert_eq!(lines, expected, "unexpected stderr:\n{}", stderr);
+        }
+    }
+
+    #[cfg(not(feature = "dry_run"))]
+    mod deprecated_strategy {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_DEPRECATED_CHILD";
+
+        struct Deprecated;
+
+        prevent_drop_deprecated!(Deprecated, forget_to_explicitly_drop_an_instance_of_Deprecated);
+
+        struct GenericDeprecated<T>(T);
+
+        prevent_drop!(
+            GenericDeprecated<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericDeprecated,
+            strategy = deprecated,
+            generics = [T],
+            "Close GenericDeprecated explicitly."
+        );
+
+        #[test]
+        fn prevent_drop_deprecated_child() {
+            if env::var_os(CHILD).is_some() {
+                ::std::mem::drop(Deprecated);
+                ::std::mem::drop(GenericDeprecated(1u8));
+                ::std::mem::drop(Deprecated);
+                ::std::mem::drop(GenericDeprecated("two"));
+                eprintln!("child continued");
+            }
+        }
+
+        #[test]
+        fn prevent_drop_deprecated_warns_once_per_type() {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", "tests::deprecated_strategy::prevent_drop_deprecated_child", "--nocapture"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(output.status.success(), "unexpected stderr:\n{}", stderr);
+            let expected = [
+                "warning: dropping an instance of Deprecated is deprecated, consume it explicitly instead",
+                "Close GenericDeprecated explicitly.",
+                "child continued",
+            ];
+            // A warning printed twice would show up twice here.
+            let lines: Vec<&str> = stderr.lines().filter(|line| expected.contains(line)).collect();
+            assert_eq!(lines, expected, "unexpected stderr:\n{}", stderr);
+        }
+    }
+
+    mod leak_message_formatter {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_FORMATTER_CHILD";
+
+        struct Formatted;
+
+        prevent_drop_panic!(Formatted, forget_to_explicitly_drop_an_instance_of_Formatted);
+
+        #[derive(Debug)]
+        struct LeakError;
+
+        struct Failed;
+
+        prevent_drop_panic!(
+            Failed,
+            forget_to_explicitly_drop_an_instance_of_Failed,
+            error = || LeakError
+        );
+
+        struct Explicit;
+
+        prevent_drop_panic!(
+            Explicit,
+            forget_to_explicitly_drop_an_instance_of_Explicit,
+            "Explicit keeps its message."
+        );
+
+        struct Printed;
+
+        prevent_drop_eprintln!(Printed, forget_to_explicitly_drop_an_instance_of_formatter_Printed);
+
+        fn format(type_name: &'static str) -> String {
+            format!("[leak] type={}", type_name)
+        }
+
+        // The formatter is global, so it is only set in a child process.
+        #[test]
+        fn leak_message_formatter_child() {
+            if env::var_os(CHILD).is_some() {
+                ::set_leak_message_formatter(format).unwrap();
+                assert!(::set_leak_message_formatter(|_| String::new()).is_err());
+                let _ = ::std::panic::catch_unwind(|| drop(Explicit));
+                let _ = ::std::panic::catch_unwind(|| drop(Failed));
+                drop(Printed);
+                drop(Formatted);
+            }
+        }
+
+        #[test]
+        fn default_leak_message_matches_the_panic_message() {
+            let payload = ::std::panic::catch_unwind(|| drop(Formatted)).unwrap_err();
+            let message = payload.downcast::<String>().unwrap();
+            let expected = format!("{} incident=", ::default_leak_message("Formatted"));
+            assert!(message.starts_with(&expected), "unexpected message: {}", message);
+        }
+
+        #[test]
+        fn default_leak_message_matches_the_panic_message_with_an_error() {
+            let payload = ::std::panic::catch_unwind(|| drop(Failed)).unwrap_err();
+            let message = payload.downcast::<String>().unwrap();
+            let expected = format!("{} {:?} incident=", ::default_leak_message("Failed"), LeakError);
+            assert!(message.starts_with(&expected), "unexpected message: {}", message);
+        }
+
+        #[test]
+        fn leak_message_formatter_formats_default_messages() {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", "tests::leak_message_formatter::leak_message_formatter_child", "--nocapture"])
+                .env(CHILD, "1")
+                .output()
+                .unwrap();
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(!output.status.success());
+            assert!(stderr.contains("Explicit keeps its message. incident="), "unexpected stderr:\n{}", stderr);
+            assert!(
+                stderr.contains("Forgot to explicitly drop an instance of Failed. LeakError incident="),
+                "unexpected stderr:\n{}",
+                stderr
+            );
+            assert!(stderr.contains("[leak] type=Formatted incident="), "unexpected stderr:\n{}", stderr);
+            assert!(stderr.contains("[leak] type=Printed\n"), "unexpected stderr:\n{}", stderr);
+        }
+    }
+
+    #[cfg(feature = "libc")]
+    mod exit_leak_check {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_EXIT_LEAK_CHECK_CHILD";
+
+        struct Counted;
+
+        prevent_drop_count!(Counted, forget_to_explicitly_drop_an_instance_of_exit_leak_check_Counted);
+
+        fn report(leaks: u64) {
+            eprintln!("exit check: {} leaked", leaks);
+        }
+
+        // The check runs at exit, so it is only installed in a child
+        // process.
+        #[test]
+        fn exit_leak_check_child() {
+            if let Ok(case) = env::var(CHILD) {
+                ::install_exit_leak_check(report).unwrap();
+                assert!(::install_exit_leak_check(report).is_err());
+                ::reset_leak_count();
+                if case == "leak" {
+                    drop(Counted);
+                }
+            }
+        }
+
+        fn run_child(case: &str) -> String {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", "tests::exit_leak_check::exit_leak_check_child", "--nocapture"])
+                .env(CHILD, case)
+                .output()
+                .unwrap();
+            assert!(output.status.success());
+            String::from_utf8_lossy(&output.stderr).into_owned()
+        }
+
+        #[test]
+        fn exit_leak_check_reports_leaks() {
+            let stderr = run_child("leak");
+            assert!(stderr.contains("exit check: 1 leaked"), "unexpected stderr:\n{}", stderr);
+        }
+
+        #[test]
+        fn exit_leak_check_is_silent_without_leaks() {
+            let stderr = run_child("clean");
+            assert!(!stderr.contains("exit check"), "unexpected stderr:\n{}", stderr);
+        }
+    }
+
+    #[cfg(feature = "libc")]
+    mod raw_abort_strategy {
+        use std::env;
+        use std::process::Command;
+
+        const CHILD: &str = "PREVENT_DROP_RAW_ABORT_CHILD";
+
+        struct RawAborted;
+
+        prevent_drop_raw_abort!(RawAborted, forget_to_explicitly_drop_an_instance_of_RawAborted);
+
+        struct GenericRawAborted<T>(T);
+
+        prevent_drop!(
+            GenericRawAborted<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericRawAborted,
+            strategy = raw_abort,
+            generics = [T],
+            "GenericRawAborted was dropped."
+        );
+
+        #[test]
+        fn prevent_drop_raw_abort_child() {
+            match env::var(CHILD).as_ref().map(String::as_str) {
+                Ok("plain") => ::std::mem::drop(RawAborted),
+                Ok("generic") => ::std::mem::drop(GenericRawAborted(1u8)),
+                _ => {
+                    let _ = ::std::mem::ManuallyDrop::new(RawAborted);
+                    let _ = ::std::mem::ManuallyDrop::new(GenericRawAborted(1u8));
+                }
+            }
+        }
+
+        fn run_child(case: &str) -> (bool, Vec<u8>) {
+            let output = Command::new(env::current_exe().unwrap())
+                .args(["--exact", "tests::raw_abort_strategy::prevent_drop_raw_abort_child", "--nocapture"])
+                .env(CHILD, case)
+                .output()
+                .unwrap();
+            (output.status.success(), output.stderr)
+        }
+
+        fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+            haystack.windows(needle.len()).any(|window| window == needle)
+        }
+
+        #[test]
+        fn prevent_drop_raw_abort_writes_and_aborts() {
+            let (success, stderr) = run_child("plain");
+            assert!(!success);
+            let prefix: &[u8] = b"prevent_drop: an instance of RawAborted (defined at src/lib.rs:";
+            assert!(contains(&stderr, prefix), "unexpected stderr:\n{}", String::from_utf8_lossy(&stderr));
+            assert!(contains(&stderr, b") was dropped\n"));
+
+            let (success, stderr) = run_child("generic");
+            assert!(!success);
+            assert!(contains(&stderr, b"GenericRawAborted was dropped.\n"));
+        }
+    }
+
+    #[cfg(feature = "runtime_strategy")]
+    mod runtime_strategy {
+        use std::env;
+        use std::process::Command;
+
+        use Strategy;
+
+        const CHILD: &str = "PREVENT_DROP_RUNTIME_STRATEGY_CHILD";
+
+        struct Switched;
+
+        prevent_drop!(Switched, forget_to_explicitly_drop_an_instance_of_runtime_Switched);
+
+        struct Described(u32);
+
+        prevent_drop_panic!(
+            Described,
+            forget_to_explicitly_drop_an_instance_of_runtime_Described,
+            |this| format!("Leaked Described({}).", this.0)
+        );
+
+        #[test]
+        fn prevent_drop_runtime_strategy_child() {
+            match env::var(CHILD).as_ref().map(String::as_str) {
+                Ok("plain") => {
+                    eprintln!("strategy={:?}", ::runtime_strategy());
+                    ::std::mem::drop(Switched);
+                }
+                Ok("describe") => ::std::mem::drop(Described(7)),
+                _ => {
+                    let _ = ::std::mem::ManuallyDrop::new(Switched);
+                    let _ = ::std::mem::ManuallyDrop::new(Described(7));
+                }
+            }
+        }
+
+        fn run_child(case: &str, strategy: Option<&str>) -> (Option<i32>, String) {
+            let mut command = Command::new(env::current_exe().unwrap());
+            command
+                .args(["--exact", "tests::runtime_strategy::prevent_drop_runtime_strategy_child", "--nocapture"])
+                .env(CHILD, case)
+                .env_remove("PREVENT_DROP_STRATEGY");
+            if let Some(strategy) = strategy {
+                command.env("PREVENT_DROP_STRATEGY", strategy);
+            }
+            let output = command.output().unwrap();
+            (output.status.code(), String::from_utf8_lossy(&output.stderr).into_owned())
+        }
+
+        #[test]
+        fn prevent_drop_runtime_strategy_is_selected() {
+            assert_eq!(::STRATEGY, Strategy::Runtime);
+        }
+
+        #[test]
+        fn prevent_drop_runtime_strategy_aborts() {
+            let (code, stderr) = run_child("plain", Some("abort"));
+            assert_eq!(code, None, "expected an abort:\n{}", stderr);
+            assert!(stderr.contains("strategy=Abort"), "unexpected stderr:\n{}", stderr);
+            assert!(stderr.contains("Forgot to explicitly drop an instance of Switched."));
+            assert!(!stderr.contains("incident="));
+
+            let (code, stderr) = run_child("describe", Some("abort"));
+            assert_eq!(code, None, "expected an abort:\n{}", stderr);
+            assert!(stderr.contains("Leaked Described(7)."), "unexpected stderr:\n{}", stderr);
+        }
+
+        #[test]
+        fn prevent_drop_runtime_strategy_panics() {
+            for strategy in [Some("panic"), None, Some("unknown")] {
+                let (code, stderr) = run_child("plain", strategy);
+                assert_eq!(code, Some(101), "expected a panic for {:?}:\n{}", strategy, stderr);
+                assert!(stderr.contains("strategy=Panic"), "unexpected stderr:\n{}", stderr);
+                assert!(stderr.contains("Forgot to explicitly drop an instance of Switched. incident="));
+            }
+
+            let (_, stderr) = run_child("plain", Some("unknown"));
+            assert!(stderr.contains("PREVENT_DROP_STRATEGY must be `abort` or `panic`, not `unknown`"));
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    mod metric_strategy {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::{Arc, Mutex};
+
+        use metrics::{
+            self, Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder,
+            SharedString, Unit,
+        };
+
+        #[derive(Default)]
+        struct TestRecorder {
+            counters: Mutex<Vec<(Key, Arc<AtomicU64>)>>,
+        }
+
+        struct TestCounter(Arc<AtomicU64>);
+
+        impl CounterFn for TestCounter {
+            fn increment(&self, value: u64) {
+                self.0.fetch_add(value, Ordering::SeqCst);
+            }
+
+            fn absolute(&self, value: u64) {
+                self.0.store(value, Ordering::SeqCst);
+            }
+        }
+
+        impl TestRecorder {
+            fn count(&self, type_name: &str) -> u64 {
+                self.counters
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(key, _)| {
+                        key.name() == "prevent_drop.leaks"
+                            && key.labels().any(|label| label.key() == "type" && label.value() == type_name)
+                    })
+                    .map(|(_, value)| value.load(Ordering::SeqCst))
+                    .sum()
+            }
+        }
+
+        impl Recorder for TestRecorder {
+            fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+            fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+            fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+            fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+                let value = Arc::new(AtomicU64::new(0));
+                self.counters.lock().unwrap().push((key.clone(), value.clone()));
+                Counter::from_arc(Arc::new(TestCounter(value)))
+            }
+
+            fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+                Gauge::noop()
+            }
+
+            fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+                Histogram::noop()
+            }
+        }
+
+        struct Measured;
+
+        prevent_drop_metric!(Measured, forget_to_explicitly_drop_an_instance_of_Measured);
+
+        struct GenericMeasured<T>(T);
+
+        prevent_drop!(
+            GenericMeasured<T>,
+            forget_to_explicitly_drop_an_instance_of_GenericMeasured,
+            strategy = metric,
+            generics = [T]
+        );
+
+        #[test]
+        fn prevent_drop_metric_increments_counter() {
+            let recorder = TestRecorder::default();
+            metrics::with_local_recorder(&recorder, || {
+                ::std::mem::drop(Measured);
+                ::std::mem::drop(Measured);
+                ::std::mem::drop(GenericMeasured(1u8));
+            });
+            assert_eq!(recorder.count("Measured"), 2);
+            assert_eq!(recorder.count("GenericMeasured<T>"), 1);
+        }
+
+        #[test]
+        fn prevent_drop_metric_does_not_increment_if_value_is_not_dropped() {
+            let recorder = TestRecorder::default();
+            metrics::with_local_recorder(&recorder, || {
+                let _ = ::std::mem::ManuallyDrop::new(Measured);
+            });
+            assert_eq!(recorder.count("Measured"), 0);
+        }
+    }
+
+    #[cfg(feature = "log")]
+    mod log_strategy {
+        use std::cell::RefCell;
+        use std::sync::Once;
+
+        use log::{self, Log, Metadata, Record};
+
+        struct TestLogger;
+
+        thread_local! {
+            static RECORDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        impl Log for TestLogger {
+            fn enabled(&self, metadata: &Metadata) -> bool {
+                metadata.level() <= log::Level::Error
+            }
+
+            fn log(&self, record: &Record) {
+                if self.enabled(record.metadata()) {
+                    RECORDS.with(|records| records.borrow_mut().push(record.args().to_string()));
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: TestLogger = TestLogger;
+        static INIT: Once = Once::new();
+
+        fn take_records() -> Vec<String> {
+            INIT.call_once(|| {
+                log::set_logger(&LOGGER).unwrap();
+                log::set_max_level(log::LevelFilter::Error);
+            });
+            RECORDS.with(|records| records.borrow_mut().drain(..).collect())
+        }
+
+        struct LogStrategy;
+
+        prevent_drop_log!(LogStrategy, forget_to_explicitly_drop_an_instance_of_LogStrategy);
+
+        struct LogStrategyWithMessage;
+
+        prevent_drop_log!(
+            LogStrategyWithMessage,
+            forget_to_explicitly_drop_an_instance_of_LogStrategyWithMessage,
+            "LogStrategyWithMessage was leaked!"
+        );
+
+        #[test]
+        fn prevent_drop_log_logs_once() {
+            take_records();
+            ::std::mem::drop(LogStrategy);
+            assert_eq!(
+                take_records(),
+                vec!["Forgot to explicitly drop an instance of LogStrategy.".to_string()]
+            );
+        }
+
+        #[test]
+        fn prevent_drop_log_logs_custom_message() {
+            take_records();
+            ::std::mem::drop(LogStrategyWithMessage);
+            assert_eq!(
+                take_records(),
+                vec!["LogStrategyWithMessage was leaked!".to_string()]
+            );
+        }
+
+        struct ExplicitLogStrategy;
+
+        prevent_drop!(
+            ExplicitLogStrategy,
+            forget_to_explicitly_drop_an_instance_of_ExplicitLogStrategy,
+            strategy = log
+        );
+
+        #[test]
+        fn prevent_drop_strategy_log_logs() {
+            take_records();
+            ::std::mem::drop(ExplicitLogStrategy);
+            assert_eq!(
+                take_records(),
+                vec!["Forgot to explicitly drop an instance of ExplicitLogStrategy.".to_string()]
+            );
+        }
+
+        #[test]
+        fn prevent_drop_log_does_not_log_if_value_is_not_dropped() {
+            take_records();
+            let _ = ::std::mem::ManuallyDrop::new(LogStrategy);
+            assert!(take_records().is_empty());
+        }
     }
 }
@@ -0,0 +1,106 @@
+//! A field wrapper that guards one field instead of the whole type.
+
+use rt;
+
+/// Guard a single field of a struct.
+///
+/// Wrap the field that holds the must-consume resource in
+/// `MustConsume<T>` and leave the struct itself without a guard. The
+/// struct can then be dropped freely once the field has been handled,
+/// while dropping the field without consuming it triggers the strategy
+/// selected through the features, just like `prevent_drop!`. Because
+/// the struct does not implement `Drop`, its teardown can move the
+/// field out and `defuse` it.
+///
+/// ```
+/// use prevent_drop::MustConsume;
+///
+/// struct Connection {
+///     socket: MustConsume<u32>,
+///     peer: &'static str,
+/// }
+///
+/// impl Connection {
+///     fn close(self) -> &'static str {
+///         let socket = self.socket.defuse();
+///         // Close the socket here.
+///         let _ = socket;
+///         self.peer
+///     }
+/// }
+///
+/// let connection = Connection { socket: MustConsume::new(3), peer: "localhost" };
+/// assert_eq!(connection.close(), "localhost");
+/// ```
+pub struct MustConsume<T> {
+    value: T,
+}
+
+impl<T> MustConsume<T> {
+    /// Guard `value`.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        MustConsume { value }
+    }
+
+    /// A reference to the guarded value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// A mutable reference to the guarded value.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Take the value out without triggering the guard.
+    #[inline]
+    pub fn defuse(self) -> T {
+        rt::take_field(self, |this| &this.value)
+    }
+}
+
+prevent_drop_wrapper!(
+    MustConsume<T>,
+    prevent_drop_MustConsume,
+    generics = [T],
+    "The `MustConsume` wrapper requires you to enable optimizations or to enable one of the `abort`, `panic` or `auto_fallback` features.",
+    "Forgot to consume a field wrapped in MustConsume<T>."
+);
+
+#[cfg(test)]
+mod tests {
+    use super::MustConsume;
+
+    struct Connection {
+        socket: MustConsume<(u32, u32)>,
+        sent: usize,
+    }
+
+    impl Connection {
+        fn close(self) -> ((u32, u32), usize) {
+            (self.socket.defuse(), self.sent)
+        }
+    }
+
+    // The drop of the field is tested in `tests/wrappers.rs`, where the
+    // guard panics in every build.
+    #[test]
+    fn teardown_defuses_the_guarded_field() {
+        // Nothing may panic while the field is guarded, otherwise the
+        // link strategy sees a drop on the unwinding path.
+        let mut connection = Connection {
+            socket: MustConsume::new((1, 2)),
+            sent: 0,
+        };
+        connection.socket.get_mut().0 = 3;
+        connection.sent += 1;
+        let first = connection.socket.get().0;
+        let (socket, sent) = connection.close();
+        assert_eq!(first, 3);
+        assert_eq!(socket, (3, 2));
+        assert_eq!(sent, 1);
+    }
+}
@@ -0,0 +1,26 @@
+//! The per-type drop counters behind `prevent_drop_once!`.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Implemented by `prevent_drop_once!` for the guarded type.
+///
+/// You should not implement this trait yourself. Use
+/// `reset_drop_allowance` to restore the allowance of a type.
+pub trait DropAllowance {
+    #[doc(hidden)]
+    fn drop_counter() -> &'static AtomicUsize;
+}
+
+/// Restore the drop allowance of a type guarded by `prevent_drop_once!`.
+///
+/// Use this between tests that each perform a controlled teardown.
+#[inline]
+pub fn reset_drop_allowance<T: ?Sized + DropAllowance>() {
+    T::drop_counter().store(0, Ordering::SeqCst);
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn exceeds_allowance(counter: &AtomicUsize, allowance: usize) -> bool {
+    counter.fetch_add(1, Ordering::SeqCst) >= allowance
+}
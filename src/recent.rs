@@ -0,0 +1,122 @@
+//! The process-wide ring buffer behind `prevent_drop_recent!`.
+
+use core::panic::Location;
+use std::sync::{Mutex, PoisonError};
+
+/// The number of leaks `recent_leaks` remembers.
+pub const RECENT_LEAKS_CAPACITY: usize = 64;
+
+type Leak = (&'static str, &'static Location<'static>);
+
+struct Ring {
+    leaks: [Option<Leak>; RECENT_LEAKS_CAPACITY],
+    next: usize,
+}
+
+static RECENT: Mutex<Ring> = Mutex::new(Ring {
+    leaks: [None; RECENT_LEAKS_CAPACITY],
+    next: 0,
+});
+
+/// The type names and locations of the last `RECENT_LEAKS_CAPACITY`
+/// leaks reported by `prevent_drop_recent!` guards, oldest first.
+///
+/// For a value that was dropped the location is that of the
+/// `prevent_drop_recent!` invocation, not of the drop.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_recent!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     drop(Resource);
+///     let (type_name, location) = prevent_drop::recent_leaks()[0];
+///     assert_eq!(type_name, "Resource");
+///     println!("leaked a {} guarded at {}", type_name, location);
+/// }
+/// ```
+#[inline]
+pub fn recent_leaks() -> Vec<Leak> {
+    let ring = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+    let (newer, older) = ring.leaks.split_at(ring.next);
+    older.iter().chain(newer).filter_map(|leak| *leak).collect()
+}
+
+/// Forget all leaks remembered for `recent_leaks`.
+#[inline]
+pub fn clear_recent_leaks() {
+    let mut ring = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+    ring.leaks = [None; RECENT_LEAKS_CAPACITY];
+    ring.next = 0;
+}
+
+/// Remember a leak, overwriting the oldest one if the buffer is full.
+#[doc(hidden)]
+#[inline]
+pub fn record_leak(type_name: &'static str, location: &'static Location<'static>) {
+    let mut ring = RECENT.lock().unwrap_or_else(PoisonError::into_inner);
+    let next = ring.next;
+    ring.leaks[next] = Some((type_name, location));
+    ring.next = (next + 1) % RECENT_LEAKS_CAPACITY;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_recent_leaks, recent_leaks, RECENT_LEAKS_CAPACITY};
+
+    struct First;
+    struct Second<T>(T);
+
+    prevent_drop_recent!(First, forget_to_explicitly_drop_an_instance_of_recent_First);
+    const FIRST_LINE: u32 = line!() - 1;
+
+    prevent_drop_recent!(
+        Second<T>,
+        forget_to_explicitly_drop_an_instance_of_recent_Second,
+        generics = [T]
+    );
+
+    fn names() -> Vec<&'static str> {
+        recent_leaks().iter().map(|&(type_name, _)| type_name).collect()
+    }
+
+    // The assertions depend on the order of the entries, which a second
+    // test leaking values on another thread would interleave with.
+    #[test]
+    fn recent_leaks_fill_and_wrap() {
+        clear_recent_leaks();
+        assert!(recent_leaks().is_empty());
+
+        drop(First);
+        drop(Second(1u8));
+        let _ = ::std::mem::ManuallyDrop::new(First);
+        assert_eq!(names(), ["First", "Second<T>"]);
+
+        let (_, location) = recent_leaks()[0];
+        assert_eq!((location.file(), location.line()), (file!(), FIRST_LINE));
+        forget_to_explicitly_drop_an_instance_of_recent_First();
+        let line = line!() - 1;
+        let (_, location) = recent_leaks()[2];
+        assert_eq!((location.file(), location.line()), (file!(), line));
+
+        for _ in 0..RECENT_LEAKS_CAPACITY - 3 {
+            drop(Second("value"));
+        }
+        assert_eq!(recent_leaks().len(), RECENT_LEAKS_CAPACITY);
+        assert_eq!(names()[..3], ["First", "Second<T>", "First"]);
+
+        drop(First);
+        drop(First);
+        let names = names();
+        assert_eq!(names.len(), RECENT_LEAKS_CAPACITY);
+        assert_eq!(names[..2], ["First", "Second<T>"]);
+        assert_eq!(names[RECENT_LEAKS_CAPACITY - 3..], ["Second<T>", "First", "First"]);
+
+        clear_recent_leaks();
+        assert!(recent_leaks().is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! The thread-local leak log behind `prevent_drop_record!`.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static LEAKS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Take the names of the types whose `prevent_drop_record!` guard fired
+/// on this thread since the last call.
+///
+/// The log is per thread, so tests running in parallel do not see each
+/// other's leaks.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_record!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     drop(Resource);
+///     assert_eq!(prevent_drop::take_recorded_leaks(), ["Resource"]);
+///     assert!(prevent_drop::take_recorded_leaks().is_empty());
+/// }
+/// ```
+#[inline]
+pub fn take_recorded_leaks() -> Vec<&'static str> {
+    LEAKS.with(|leaks| leaks.replace(Vec::new()))
+}
+
+/// Run `f` and fail with the names of the types whose
+/// `prevent_drop_record!` guard fired on this thread while it ran.
+///
+/// This turns a leak into a test failure without `#[should_panic]`.
+/// Leaks recorded before the call stay in the log for
+/// `take_recorded_leaks`. Calls can be nested: a leak is reported by the
+/// innermost `catch_leaks` it happened in. If `f` panics, its leaks are
+/// left in the log.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_record!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     assert_eq!(prevent_drop::catch_leaks(|| 1), Ok(1));
+///     assert_eq!(prevent_drop::catch_leaks(|| drop(Resource)), Err(vec!["Resource"]));
+/// }
+/// ```
+pub fn catch_leaks<F: FnOnce() -> T, T>(f: F) -> Result<T, Vec<&'static str>> {
+    struct Restore(Vec<&'static str>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LEAKS.with(|leaks| {
+                let caught = leaks.replace(::std::mem::take(&mut self.0));
+                leaks.borrow_mut().extend(caught);
+            });
+        }
+    }
+
+    let mut restore = Restore(take_recorded_leaks());
+    let value = f();
+    let caught = LEAKS.with(|leaks| leaks.replace(::std::mem::take(&mut restore.0)));
+    if caught.is_empty() {
+        Ok(value)
+    } else {
+        Err(caught)
+    }
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn record_leak(type_name: &'static str) {
+    LEAKS.with(|leaks| leaks.borrow_mut().push(type_name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{catch_leaks, take_recorded_leaks};
+
+    struct Recorded;
+
+    prevent_drop_record!(Recorded, forget_to_explicitly_drop_an_instance_of_record_Recorded);
+
+    struct GenericRecorded<T>(T);
+
+    prevent_drop_record!(
+        GenericRecorded<T>,
+        forget_to_explicitly_drop_an_instance_of_record_GenericRecorded,
+        generics = [T]
+    );
+
+    #[test]
+    fn records_no_leaks() {
+        take_recorded_leaks();
+        let _ = ::std::mem::ManuallyDrop::new(Recorded);
+        assert!(take_recorded_leaks().is_empty());
+    }
+
+    #[test]
+    fn records_one_leak() {
+        take_recorded_leaks();
+        drop(Recorded);
+        assert_eq!(take_recorded_leaks(), ["Recorded"]);
+    }
+
+    #[test]
+    fn records_multiple_leaks_in_order() {
+        take_recorded_leaks();
+        drop(GenericRecorded(1u8));
+        drop(Recorded);
+        drop(GenericRecorded("two"));
+        assert_eq!(
+            take_recorded_leaks(),
+            ["GenericRecorded<T>", "Recorded", "GenericRecorded<T>"]
+        );
+        assert!(take_recorded_leaks().is_empty());
+    }
+
+    #[test]
+    fn catch_leaks_without_leaks() {
+        assert_eq!(catch_leaks(|| ::std::mem::forget(Recorded)), Ok(()));
+    }
+
+    #[test]
+    fn catch_leaks_reports_leaks() {
+        take_recorded_leaks();
+        drop(Recorded);
+        let caught = catch_leaks(|| {
+            drop(GenericRecorded(1u8));
+            drop(Recorded);
+            2
+        });
+        assert_eq!(caught, Err(vec!["GenericRecorded<T>", "Recorded"]));
+        assert_eq!(take_recorded_leaks(), ["Recorded"]);
+    }
+
+    #[test]
+    fn catch_leaks_nests() {
+        let outer = catch_leaks(|| {
+            drop(Recorded);
+            let inner = catch_leaks(|| drop(GenericRecorded(1u8)));
+            assert_eq!(inner, Err(vec!["GenericRecorded<T>"]));
+            drop(Recorded);
+            assert_eq!(catch_leaks(|| 3), Ok(3));
+        });
+        assert_eq!(outer, Err(vec!["Recorded", "Recorded"]));
+    }
+
+    #[test]
+    fn catch_leaks_keeps_leaks_of_a_panic() {
+        take_recorded_leaks();
+        drop(Recorded);
+        let result = ::std::panic::catch_unwind(|| {
+            catch_leaks(|| {
+                drop(GenericRecorded(1u8));
+                panic!("boom");
+            })
+        });
+        assert!(result.is_err());
+        assert_eq!(take_recorded_leaks(), ["Recorded", "GenericRecorded<T>"]);
+    }
+
+    #[test]
+    fn records_leaks_per_thread() {
+        take_recorded_leaks();
+        ::std::thread::spawn(|| drop(Recorded)).join().unwrap();
+        assert!(take_recorded_leaks().is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+//! The process-wide list of guarded types whose guard has fired.
+
+use std::sync::{Mutex, PoisonError};
+
+static TYPES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+/// The names of the types whose abort, panic or log guard has fired in
+/// this process, in the order they first fired.
+///
+/// Each type is listed once, no matter how often it leaked. Guards that
+/// were silenced with `allow_drop` are not listed. Use it to report
+/// which guards are active in a running program, e.g. on a status page.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// struct Resource;
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+///
+/// fn main() {
+///     assert!(std::panic::catch_unwind(|| drop(Resource)).is_err());
+///     assert!(prevent_drop::registered_leak_types().contains(&"Resource"));
+/// }
+/// ```
+#[inline]
+pub fn registered_leak_types() -> Vec<&'static str> {
+    TYPES.lock().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Add `type_name` to the list if it is not in it yet.
+#[doc(hidden)]
+#[inline]
+pub fn register(type_name: &'static str) {
+    let mut types = TYPES.lock().unwrap_or_else(PoisonError::into_inner);
+    // Skip the name rather than panic if there is no room for it, so the
+    // abort strategy stays free of panics.
+    if !types.contains(&type_name) && types.try_reserve(1).is_ok() {
+        types.push(type_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use super::registered_leak_types;
+
+    struct First;
+    struct Second<T>(T);
+    struct Silenced;
+    struct Untouched;
+
+    prevent_drop_panic!(First, forget_to_explicitly_drop_an_instance_of_registry_First);
+    prevent_drop_panic!(
+        Second<T>,
+        forget_to_explicitly_drop_an_instance_of_registry_Second,
+        generics = [T],
+        "Leaked a Second."
+    );
+    prevent_drop_panic!(Silenced, forget_to_explicitly_drop_an_instance_of_registry_Silenced);
+    prevent_drop_panic!(Untouched, forget_to_explicitly_drop_an_instance_of_registry_Untouched);
+
+    #[test]
+    fn fired_guards_are_registered_once() {
+        let _ = ::std::mem::ManuallyDrop::new(Untouched);
+        assert!(panic::catch_unwind(|| drop(First)).is_err());
+        assert!(panic::catch_unwind(|| drop(Second(1u8))).is_err());
+        assert!(panic::catch_unwind(|| drop(First)).is_err());
+        ::allow_drop(|| drop(Silenced));
+
+        // Other tests register their own types concurrently.
+        let types = registered_leak_types();
+        let ours: Vec<&str> = types
+            .iter()
+            .cloned()
+            .filter(|name| ["First", "Second<T>", "Silenced", "Untouched"].contains(name))
+            .collect();
+        assert_eq!(ours, ["First", "Second<T>"]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Run-time support for the code generated by the strategy macros.
+//!
+//! These functions abstract over the differences between `std` and
+//! `no_std` builds so the macros expand to the same code in both.
+
+pub use core::ffi::c_int;
+pub use core::fmt::{self, Display, Write};
+pub use core::hint::unreachable_unchecked;
+pub use core::ops::{Deref, DerefMut, Drop};
+pub use core::panic::Location;
+pub use core::ptr;
+pub use core::{compile_error, concat, file, format_args, line, panic, stringify};
+pub use core::mem::{forget, size_of, ManuallyDrop};
+pub use core::sync::atomic::{AtomicBool, AtomicUsize};
+pub use core::convert::Into;
+pub use core::future::Future;
+pub use core::result::Result;
+#[cfg(any(feature = "std", test))]
+pub use std::boxed::Box;
+#[cfg(any(feature = "std", test))]
+pub use std::error::Error;
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+static INCIDENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Abort the process.
+///
+/// Without `std` there is no stable way to abort, so we panic from a
+/// function that cannot unwind, which makes the runtime abort instead.
+#[inline]
+pub fn abort() -> ! {
+    #[cfg(any(feature = "std", test))]
+    ::std::process::abort();
+
+    #[cfg(not(any(feature = "std", test)))]
+    abort_without_std()
+}
+
+#[cfg(not(any(feature = "std", test)))]
+#[inline(never)]
+extern "C" fn abort_without_std() -> ! {
+    panic!("prevent_drop: aborting");
+}
+
+/// Write a message to stderr.
+///
+/// Unlike `eprintln!` this ignores errors instead of panicking, so the
+/// abort strategy cannot panic. Without `std` there is no stderr to
+/// write to, so this does nothing.
+#[inline]
+pub fn report<M: ::core::fmt::Display>(message: M) {
+    #[cfg(any(feature = "std", test))]
+    {
+        use std::io::Write;
+
+        let _ = writeln!(::std::io::stderr(), "{}", message);
+    }
+
+    #[cfg(not(any(feature = "std", test)))]
+    let _ = message;
+}
+
+/// Write a message to stderr unless `warned` is already set, and set it.
+#[inline]
+pub fn warn_once<M: ::core::fmt::Display>(warned: &AtomicBool, message: M) {
+    if !warned.swap(true, Ordering::Relaxed) {
+        report(message);
+    }
+}
+
+/// Whether the current thread is panicking.
+///
+/// Always `false` without `std` because `core` cannot tell.
+#[inline]
+pub fn panicking() -> bool {
+    #[cfg(any(feature = "std", test))]
+    return ::std::thread::panicking();
+
+    #[cfg(not(any(feature = "std", test)))]
+    return false;
+}
+
+/// Tell the user that a guard stayed silent because the thread was
+/// already panicking.
+///
+/// This runs in `drop` during unwinding, where a panic would abort the
+/// process, so it writes with `report`, which cannot panic. Does nothing
+/// unless the `note_skipped_leaks` feature is enabled.
+#[inline]
+pub fn note_skipped_leak(type_name: &str) {
+    #[cfg(feature = "note_skipped_leaks")]
+    report(format_args!(
+        "prevent_drop: an instance of {} was dropped while panicking, the leak is not reported",
+        type_name
+    ));
+
+    #[cfg(not(feature = "note_skipped_leaks"))]
+    let _ = type_name;
+}
+
+/// Move a field out of `this` without running the `Drop` impl of `this`.
+///
+/// `field` must return a field stored in `this` itself, not something
+/// `this` points to, or the value would be read out of memory that
+/// `this` does not own. The guard wrappers and `prevent_drop_newtype!`
+/// give the guarded value back through this.
+#[inline]
+pub fn take_field<S, F, P>(this: S, field: P) -> F
+where
+    P: FnOnce(&S) -> &F,
+{
+    let this = ManuallyDrop::new(this);
+    // Safe because `this` is never dropped, so the field is read exactly
+    // once.
+    unsafe { ptr::read(field(&this)) }
+}
+
+/// The wording of the default message for a leak of the type it holds.
+///
+/// This is the single source of the default message of every run-time
+/// strategy. `prevent_drop_default_message!` spells out the same words
+/// for the const strategy, which needs them at compile time.
+pub struct DefaultLeakMessage<'a>(pub &'a str);
+
+impl<'a> Display for DefaultLeakMessage<'a> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Forgot to explicitly drop an instance of {}.", self.0)
+    }
+}
+
+/// The default message for a leak of `type_name`.
+///
+/// Uses the formatter set with `set_leak_message_formatter`, or
+/// `default_leak_message` if there is none. Without `std` there is no
+/// formatter.
+#[inline]
+pub fn leak_message(type_name: &'static str) -> impl Display {
+    #[cfg(any(feature = "std", test))]
+    return ::formatter::leak_message(type_name);
+
+    #[cfg(not(any(feature = "std", test)))]
+    DefaultLeakMessage(type_name)
+}
+
+/// Add `type_name` to the types listed by `registered_leak_types`.
+///
+/// Does nothing without `std`.
+#[inline]
+pub fn register_leak_type(type_name: &'static str) {
+    #[cfg(any(feature = "std", test))]
+    ::registry::register(type_name);
+
+    #[cfg(not(any(feature = "std", test)))]
+    let _ = type_name;
+}
+
+/// Pass a `LeakEvent` to the handler set with `set_leak_event_handler`.
+///
+/// Does nothing without the `serde` feature.
+#[inline]
+pub fn leak_event<M: Display>(type_name: &'static str, message: M) {
+    #[cfg(feature = "serde")]
+    ::event::emit(type_name, message);
+
+    #[cfg(not(feature = "serde"))]
+    let _ = (type_name, message);
+}
+
+/// Whether the panic strategy should abort instead, as chosen by
+/// `PREVENT_DROP_STRATEGY` with the `runtime_strategy` feature.
+///
+/// Always `false` without that feature, so the check compiles away.
+#[inline]
+pub fn runtime_aborts() -> bool {
+    #[cfg(feature = "runtime_strategy")]
+    return ::strategy::runtime_strategy() == ::Strategy::Abort;
+
+    #[cfg(not(feature = "runtime_strategy"))]
+    return false;
+}
+
+/// The next incident ID for a panic message, starting at 1.
+#[inline]
+pub fn next_incident() -> u64 {
+    INCIDENTS.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// Write bytes to stderr with a raw `write(2)` call.
+///
+/// Does not allocate or take locks, so it can be called from a signal
+/// handler. Partial writes are retried and errors are ignored.
+#[cfg(all(feature = "libc", unix))]
+#[inline]
+pub fn write_stderr(mut bytes: &[u8]) {
+    while !bytes.is_empty() {
+        let written = unsafe { ::libc::write(2, bytes.as_ptr() as *const ::libc::c_void, bytes.len()) };
+        if written <= 0 {
+            return;
+        }
+        bytes = &bytes[written as usize..];
+    }
+}
+
+/// Abort the process with `abort(3)`, which is async-signal-safe.
+#[cfg(all(feature = "libc", unix))]
+#[inline]
+pub fn raw_abort() -> ! {
+    unsafe { ::libc::abort() }
+}
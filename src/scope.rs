@@ -0,0 +1,129 @@
+//! Interop with the scope guards of the `scopeguard` crate.
+
+use scopeguard::{guard, Always, ScopeGuard};
+use PreventDropped;
+
+/// Hand a guarded value to a `scopeguard::ScopeGuard` that runs
+/// `cleanup` at the end of the scope.
+///
+/// The scope guard passes the value to `cleanup` when it goes out of
+/// scope, including during a panic. Access the value through `Deref` in
+/// the meantime. `ScopeGuard::into_inner` skips the cleanup and hands
+/// the value back.
+///
+/// This is `scopeguard::guard` for types guarded by one of the strategy
+/// macros, which the `PreventDropped` bound checks. Use
+/// `scopeguard::guard` itself for other types.
+///
+/// The cleanup receives the value itself, not a `ManuallyDrop<T>`, and
+/// defuses the drop guard the same way any other code does: by calling
+/// the method that consumes the value. A cleanup that drops the value
+/// instead triggers the guard, just like a scope without a scope guard
+/// would, and `into_inner` returns a value that is still guarded, so
+/// skipping the cleanup cannot skip the guard. Handing over a
+/// `ManuallyDrop<T>` would silence both mistakes.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::cell::Cell;
+/// use std::mem::ManuallyDrop;
+///
+/// struct Connection(u32);
+///
+/// impl Connection {
+///     fn close(self) -> u32 {
+///         ManuallyDrop::new(self).0
+///     }
+/// }
+///
+/// prevent_drop_panic!(Connection, prevent_drop_Connection);
+///
+/// fn main() {
+///     let closed = Cell::new(None);
+///     {
+///         let connection = prevent_drop::into_scope_guard(Connection(3), |connection| {
+///             closed.set(Some(connection.close()));
+///         });
+///         assert_eq!(connection.0, 3);
+///     }
+///     assert_eq!(closed.get(), Some(3));
+/// }
+/// ```
+///
+/// A type without a guard does not compile:
+///
+/// ```compile_fail
+/// struct Connection(u32);
+///
+/// let connection = prevent_drop::into_scope_guard(Connection(3), |_| ());
+/// ```
+#[inline]
+pub fn into_scope_guard<T, F>(value: T, cleanup: F) -> ScopeGuard<T, F, Always>
+where
+    T: PreventDropped,
+    F: FnOnce(T),
+{
+    guard(value, cleanup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::into_scope_guard;
+    use scopeguard::ScopeGuard;
+    use std::cell::Cell;
+    use std::mem::ManuallyDrop;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    struct Connection(u32);
+
+    impl Connection {
+        fn close(self) -> u32 {
+            ManuallyDrop::new(self).0
+        }
+    }
+
+    prevent_drop_panic!(Connection, prevent_drop_scope_Connection);
+
+    #[test]
+    fn cleanup_runs_at_the_end_of_the_scope_without_firing_the_guard() {
+        let closed = Cell::new(None);
+        {
+            let connection = into_scope_guard(Connection(3), |connection| closed.set(Some(connection.close())));
+            assert_eq!(connection.0, 3);
+            assert_eq!(closed.get(), None);
+        }
+        assert_eq!(closed.get(), Some(3));
+    }
+
+    #[test]
+    fn cleanup_runs_during_a_panic() {
+        let closed = Cell::new(None);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _connection = into_scope_guard(Connection(3), |connection| closed.set(Some(connection.close())));
+            panic!("request failed");
+        }));
+        let message = result.unwrap_err().downcast::<&str>().unwrap();
+        assert_eq!(*message, "request failed");
+        assert_eq!(closed.get(), Some(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Connection.")]
+    fn cleanup_that_drops_the_value_fires_the_guard() {
+        let _connection = into_scope_guard(Connection(3), |_| ());
+    }
+
+    #[test]
+    fn into_inner_skips_the_cleanup() {
+        let closed = Cell::new(false);
+        let connection = into_scope_guard(Connection(3), |connection| {
+            connection.close();
+            closed.set(true);
+        });
+        let connection = ScopeGuard::into_inner(connection);
+        assert!(!closed.get());
+        assert_eq!(connection.close(), 3);
+    }
+}
@@ -0,0 +1,95 @@
+//! The process-wide shutdown deadline behind `prevent_drop_timeout!`.
+
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+static DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Make the `prevent_drop_timeout!` guards fire for drops from
+/// `deadline` on.
+///
+/// Drops before the deadline, or while no deadline is set, are treated
+/// as legitimate late drops. Setting the deadline again replaces it.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use std::time::{Duration, Instant};
+///
+/// struct Connection;
+///
+/// prevent_drop_timeout!(Connection, prevent_drop_Connection);
+///
+/// fn main() {
+///     prevent_drop::set_shutdown_deadline(Instant::now() + Duration::from_secs(60));
+///     drop(Connection);
+/// }
+/// ```
+#[inline]
+pub fn set_shutdown_deadline(deadline: Instant) {
+    *DEADLINE.lock().unwrap_or_else(PoisonError::into_inner) = Some(deadline);
+}
+
+/// Remove the shutdown deadline, so the `prevent_drop_timeout!` guards
+/// stop firing.
+#[inline]
+pub fn clear_shutdown_deadline() {
+    *DEADLINE.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Whether a shutdown deadline is set and has passed.
+#[doc(hidden)]
+#[inline]
+pub fn deadline_passed() -> bool {
+    match *DEADLINE.lock().unwrap_or_else(PoisonError::into_inner) {
+        Some(deadline) => Instant::now() >= deadline,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+    use std::time::{Duration, Instant};
+
+    use super::{clear_shutdown_deadline, set_shutdown_deadline};
+
+    struct Late;
+    struct Buffer<T>(T);
+
+    prevent_drop_timeout!(
+        Late,
+        forget_to_explicitly_drop_an_instance_of_shutdown_Late,
+        strategy = panic
+    );
+    prevent_drop_timeout!(
+        Buffer<T>,
+        forget_to_explicitly_drop_an_instance_of_shutdown_Buffer,
+        strategy = panic,
+        generics = [T],
+        "Buffer outlived the shutdown deadline."
+    );
+
+    // Whether a drop panics depends on the deadline at that moment, so a
+    // second test moving it would flip the outcome of these drops.
+    #[test]
+    fn timeout_fires_only_after_the_deadline() {
+        drop(Late);
+
+        set_shutdown_deadline(Instant::now() + Duration::from_secs(3600));
+        drop(Late);
+        drop(Buffer(1u8));
+
+        set_shutdown_deadline(Instant::now() - Duration::from_secs(1));
+        let payload = panic::catch_unwind(|| drop(Late)).unwrap_err();
+        let message = payload.downcast::<String>().unwrap();
+        assert!(message.starts_with("Forgot to explicitly drop an instance of Late."));
+        let payload = panic::catch_unwind(|| drop(Buffer("value"))).unwrap_err();
+        let message = payload.downcast::<String>().unwrap();
+        assert!(message.starts_with("Buffer outlived the shutdown deadline."));
+
+        clear_shutdown_deadline();
+        drop(Late);
+    }
+}
@@ -0,0 +1,192 @@
+//! The strategy that `prevent_drop!` expands to in this build.
+
+#[cfg(feature = "runtime_strategy")]
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The strategies that `prevent_drop!` can pick by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    /// Drops fail to link. Requires optimizations.
+    Link,
+    /// Drops abort the process.
+    Abort,
+    /// Drops panic.
+    Panic,
+    /// Drops do nothing. Selected by the `dry_run` feature, or by the
+    /// `miri_inert` feature under Miri.
+    DryRun,
+    /// Drops abort or panic, as `PREVENT_DROP_STRATEGY` says at run time.
+    /// Selected by the `runtime_strategy` feature.
+    Runtime,
+}
+
+/// The strategy that `prevent_drop!` uses when no `strategy = ...` is
+/// given.
+///
+/// It is selected by the same environment variable, features and
+/// optimization level as the macro. When neither optimizations nor a
+/// strategy feature are enabled, `prevent_drop!` refuses to compile and
+/// this is `Strategy::Link`. With the `never_panic` feature it is never
+/// `Strategy::Panic`, and with the `dry_run` feature, or `miri_inert`
+/// under Miri, it is always `Strategy::DryRun`. The `runtime_strategy`
+/// feature makes it `Strategy::Runtime` unless the strategy is forced.
+///
+/// ```
+/// use prevent_drop::Strategy;
+///
+/// if prevent_drop::STRATEGY == Strategy::Abort {
+///     // Skip tests that rely on catching the drop panic.
+/// }
+/// ```
+pub const STRATEGY: Strategy = match DEFAULT {
+    _ if cfg!(any(feature = "dry_run", all(feature = "miri_inert", miri))) => Strategy::DryRun,
+    _ if cfg!(all(feature = "runtime_strategy", not(prevent_drop_force))) => Strategy::Runtime,
+    Strategy::Panic if cfg!(feature = "never_panic") => Strategy::Abort,
+    strategy => strategy,
+};
+
+#[cfg(prevent_drop_force = "link")]
+const DEFAULT: Strategy = Strategy::Link;
+
+#[cfg(prevent_drop_force = "abort")]
+const DEFAULT: Strategy = Strategy::Abort;
+
+#[cfg(prevent_drop_force = "panic")]
+const DEFAULT: Strategy = Strategy::Panic;
+
+#[cfg(all(feature = "abort", not(prevent_drop_force)))]
+const DEFAULT: Strategy = Strategy::Abort;
+
+#[cfg(all(not(feature = "abort"), feature = "panic", not(prevent_drop_force)))]
+const DEFAULT: Strategy = Strategy::Panic;
+
+#[cfg(all(
+    not(feature = "abort"),
+    not(feature = "panic"),
+    feature = "auto_fallback",
+    not(opt_level_gt_0),
+    not(prevent_drop_force)
+))]
+const DEFAULT: Strategy = Strategy::Panic;
+
+#[cfg(all(
+    not(feature = "abort"),
+    not(feature = "panic"),
+    any(not(feature = "auto_fallback"), opt_level_gt_0),
+    not(prevent_drop_force)
+))]
+const DEFAULT: Strategy = Strategy::Link;
+
+/// The opt-level `prevent_drop` was built with, as cargo passes it to
+/// build scripts, e.g. `Some("2")` or `Some("s")`.
+///
+/// The build script also emits it as the `prevent_drop_opt_level` cfg,
+/// but cargo only applies that cfg to `prevent_drop` itself. Other
+/// crates can read this constant instead. It is `None` if the build
+/// script did not receive a known opt-level.
+pub const OPT_LEVEL: Option<&str> = if cfg!(prevent_drop_opt_level = "0") {
+    Some("0")
+} else if cfg!(prevent_drop_opt_level = "1") {
+    Some("1")
+} else if cfg!(prevent_drop_opt_level = "2") {
+    Some("2")
+} else if cfg!(prevent_drop_opt_level = "3") {
+    Some("3")
+} else if cfg!(prevent_drop_opt_level = "s") {
+    Some("s")
+} else if cfg!(prevent_drop_opt_level = "z") {
+    Some("z")
+} else {
+    None
+};
+
+/// The strategy the guards use in this process when the
+/// `runtime_strategy` feature is enabled: `Strategy::Abort` if the
+/// `PREVENT_DROP_STRATEGY` environment variable is `abort`, otherwise
+/// `Strategy::Panic`.
+///
+/// The variable is read once, the first time this is called or a guard
+/// fires, and the result is cached for the rest of the process. A value
+/// other than `abort` or `panic` is reported on stderr and treated as
+/// `panic`.
+#[cfg(feature = "runtime_strategy")]
+pub fn runtime_strategy() -> Strategy {
+    match RUNTIME.load(Ordering::Relaxed) {
+        RUNTIME_PANIC => Strategy::Panic,
+        RUNTIME_ABORT => Strategy::Abort,
+        _ => {
+            let strategy = match ::std::env::var("PREVENT_DROP_STRATEGY") {
+                Ok(ref value) if value.trim() == "abort" => Strategy::Abort,
+                Ok(ref value) if value.trim() == "panic" || value.trim().is_empty() => Strategy::Panic,
+                Ok(value) => {
+                    ::rt::report(format_args!(
+                        "prevent_drop: PREVENT_DROP_STRATEGY must be `abort` or `panic`, not `{}`, using `panic`",
+                        value
+                    ));
+                    Strategy::Panic
+                }
+                Err(_) => Strategy::Panic,
+            };
+            let cached = if strategy == Strategy::Abort { RUNTIME_ABORT } else { RUNTIME_PANIC };
+            RUNTIME.store(cached, Ordering::Relaxed);
+            strategy
+        }
+    }
+}
+
+#[cfg(feature = "runtime_strategy")]
+const RUNTIME_PANIC: u8 = 1;
+
+#[cfg(feature = "runtime_strategy")]
+const RUNTIME_ABORT: u8 = 2;
+
+/// `PREVENT_DROP_STRATEGY` as read by `runtime_strategy`, or 0 if it has
+/// not been read yet.
+#[cfg(feature = "runtime_strategy")]
+static RUNTIME: AtomicU8 = AtomicU8::new(0);
+
+/// Returns `STRATEGY`.
+#[inline]
+pub const fn strategy() -> Strategy {
+    STRATEGY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategy_matches_the_enabled_features() {
+        let expected = if cfg!(prevent_drop_force = "link") {
+            Strategy::Link
+        } else if cfg!(prevent_drop_force = "abort") {
+            Strategy::Abort
+        } else if cfg!(prevent_drop_force = "panic") {
+            Strategy::Panic
+        } else if cfg!(feature = "abort") {
+            Strategy::Abort
+        } else if cfg!(feature = "panic")
+            || (cfg!(feature = "auto_fallback") && !cfg!(opt_level_gt_0))
+        {
+            Strategy::Panic
+        } else {
+            Strategy::Link
+        };
+        let expected = if cfg!(any(feature = "dry_run", all(feature = "miri_inert", miri))) {
+            Strategy::DryRun
+        } else if cfg!(all(feature = "runtime_strategy", not(prevent_drop_force))) {
+            Strategy::Runtime
+        } else if cfg!(feature = "never_panic") && expected == Strategy::Panic {
+            Strategy::Abort
+        } else {
+            expected
+        };
+        assert_eq!(STRATEGY, expected);
+        assert_eq!(strategy(), STRATEGY);
+    }
+
+    #[test]
+    fn opt_level_matches_opt_level_gt_0() {
+        assert_eq!(OPT_LEVEL.is_some_and(|opt_level| opt_level != "0"), cfg!(opt_level_gt_0));
+    }
+}
@@ -0,0 +1,132 @@
+//! A token-threading API that checks consumption in the type system.
+
+use core::marker::PhantomData;
+
+/// An invariant lifetime that brands the values of one `with_token` call.
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// The permission to guard one value in a `with_token` closure.
+///
+/// Each call of `with_token` creates exactly one token with a lifetime
+/// `'id` that no other call shares. Pass it to `wrap` to guard a value.
+#[must_use = "wrap a value with the token"]
+pub struct Token<'id> {
+    brand: Brand<'id>,
+}
+
+/// A value guarded by the `Token` of a `with_token` call.
+///
+/// The only way to get the `Consumed` proof that the closure has to
+/// return is `consume`, so forgetting, dropping or leaking the value
+/// instead is a type error. Unlike the other strategies this does not
+/// depend on optimizations or on which drops the compiler generates.
+#[must_use = "the `with_token` closure has to return the result of `consume`"]
+pub struct Linear<'id, T> {
+    value: T,
+    brand: Brand<'id>,
+}
+
+/// Proof that the value of a `with_token` call was consumed, along with
+/// the result of its teardown.
+#[must_use = "return the proof from the `with_token` closure"]
+pub struct Consumed<'id, R> {
+    result: R,
+    brand: Brand<'id>,
+}
+
+/// Run `f` with a fresh `Token` and return the result of consuming the
+/// value it guards.
+///
+/// Returning from `f` requires a `Consumed` with the brand of this call,
+/// which only `Linear::consume` creates. A panic in `f` drops the value
+/// normally, and a closure that never returns proves nothing.
+///
+/// The proof is about the value passed to `Token::wrap` and nothing else.
+/// A closure that forgets a connection it captured and returns
+/// `token.wrap(()).consume(|_| ())` compiles, so wrap the value that has
+/// to be torn down itself rather than a stand-in for it.
+///
+/// ```
+/// use prevent_drop::with_token;
+///
+/// struct Connection(u32);
+///
+/// let id = with_token(|token| {
+///     let connection = token.wrap(Connection(3));
+///     assert_eq!(connection.get().0, 3);
+///     connection.consume(|connection| connection.0)
+/// });
+/// assert_eq!(id, 3);
+/// ```
+///
+/// A closure that does not consume the value does not compile:
+///
+/// ```compile_fail
+/// use prevent_drop::with_token;
+///
+/// let id = with_token(|token| {
+///     let connection = token.wrap(3u32);
+///     std::mem::forget(connection);
+/// });
+/// ```
+#[inline]
+pub fn with_token<R, F>(f: F) -> R
+where
+    F: for<'id> FnOnce(Token<'id>) -> Consumed<'id, R>,
+{
+    f(Token { brand: PhantomData }).result
+}
+
+impl<'id> Token<'id> {
+    /// Guard `value` with this token.
+    #[inline]
+    pub fn wrap<T>(self, value: T) -> Linear<'id, T> {
+        Linear {
+            value,
+            brand: self.brand,
+        }
+    }
+}
+
+impl<'id, T> Linear<'id, T> {
+    /// A reference to the guarded value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// A mutable reference to the guarded value.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Tear the value down and get the proof that it was consumed.
+    #[inline]
+    pub fn consume<R, F: FnOnce(T) -> R>(self, teardown: F) -> Consumed<'id, R> {
+        Consumed {
+            result: teardown(self.value),
+            brand: self.brand,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::with_token;
+
+    #[test]
+    fn consume_returns_the_result_of_the_teardown() {
+        let mut closed = Vec::new();
+        let sent = with_token(|token| {
+            let mut connection = token.wrap((7u32, 0usize));
+            connection.get_mut().1 += 2;
+            connection.consume(|(id, sent)| {
+                closed.push(id);
+                sent
+            })
+        });
+        assert_eq!(sent, 2);
+        assert_eq!(closed, [7]);
+    }
+}
@@ -0,0 +1,126 @@
+//! The `TryDrop` trait for cleaning up guarded values behind trait
+//! objects.
+
+use std::error::Error;
+
+/// An explicit drop function that can be called through a trait object.
+///
+/// `Consume` takes `self` by value, so it cannot be called on a
+/// `Box<dyn Trait>`. `TryDrop` takes `self: Box<Self>` instead, which
+/// lets you keep differently typed guarded values in one
+/// `Vec<Box<dyn TryDrop>>` and clean all of them up. Implement it with
+/// `prevent_drop_try_drop!`, which defuses the guard before running your
+/// cleanup function.
+///
+/// ```
+/// #[macro_use]
+/// extern crate prevent_drop;
+///
+/// use prevent_drop::TryDrop;
+/// use std::error::Error;
+/// use std::mem::ManuallyDrop;
+///
+/// struct Resource;
+///
+/// fn cleanup(_resource: ManuallyDrop<Resource>) -> Result<(), Box<dyn Error>> {
+///     // Perform cleanup.
+///     Ok(())
+/// }
+///
+/// prevent_drop_panic!(Resource, prevent_drop_Resource);
+/// prevent_drop_try_drop!(Resource, cleanup);
+///
+/// fn main() {
+///     let resources: Vec<Box<dyn TryDrop>> = vec![Box::new(Resource), Box::new(Resource)];
+///     let errors: Vec<_> = resources.into_iter().filter_map(|r| r.try_drop().err()).collect();
+///     assert!(errors.is_empty());
+/// }
+/// ```
+pub trait TryDrop {
+    /// Defuse the drop guard and clean up.
+    fn try_drop(self: Box<Self>) -> Result<(), Box<dyn Error>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::error::Error;
+    use std::fmt;
+    use std::mem::ManuallyDrop;
+    use std::ptr;
+
+    use super::TryDrop;
+
+    thread_local! {
+        static CLOSED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[derive(Debug)]
+    struct CloseError(u32);
+
+    impl fmt::Display for CloseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "failed to close socket {}", self.0)
+        }
+    }
+
+    impl Error for CloseError {}
+
+    struct File {
+        path: String,
+    }
+
+    fn close_file(file: ManuallyDrop<File>) -> Result<(), Box<dyn Error>> {
+        let path = unsafe { ptr::read(&file.path) };
+        CLOSED.with(|closed| closed.borrow_mut().push(path));
+        Ok(())
+    }
+
+    prevent_drop_panic!(File, forget_to_explicitly_drop_an_instance_of_try_drop_File);
+    prevent_drop_try_drop!(File, close_file);
+
+    struct Socket<T> {
+        id: u32,
+        buffer: T,
+    }
+
+    fn close_socket<T>(socket: ManuallyDrop<Socket<T>>) -> Result<(), CloseError> {
+        let _buffer = unsafe { ptr::read(&socket.buffer) };
+        if socket.id < 3 {
+            CLOSED.with(|closed| closed.borrow_mut().push(format!("socket {}", socket.id)));
+            Ok(())
+        } else {
+            Err(CloseError(socket.id))
+        }
+    }
+
+    prevent_drop_panic!(
+        Socket<T>,
+        forget_to_explicitly_drop_an_instance_of_try_drop_Socket,
+        generics = [T]
+    );
+    prevent_drop_try_drop!(Socket<T>, generics = [T], close_socket);
+
+    #[test]
+    fn try_drop_drains_trait_objects() {
+        let resources: Vec<Box<dyn TryDrop>> = vec![
+            Box::new(File { path: String::from("a.txt") }),
+            Box::new(Socket { id: 2, buffer: vec![0u8; 4] }),
+            Box::new(Socket { id: 3, buffer: String::from("pending") }),
+        ];
+        let errors: Vec<String> = resources
+            .into_iter()
+            .filter_map(|resource| resource.try_drop().err())
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(errors, ["failed to close socket 3"]);
+        CLOSED.with(|closed| assert_eq!(*closed.borrow(), ["a.txt", "socket 2"]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Socket<T>.")]
+    fn dropping_the_trait_object_trips_the_guard() {
+        let resource: Box<dyn TryDrop> = Box::new(Socket { id: 2, buffer: () });
+        drop(resource);
+    }
+}
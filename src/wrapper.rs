@@ -0,0 +1,79 @@
+//! A wrapper type that guards values of types you do not own.
+
+use core::ops::{Deref, DerefMut};
+use rt;
+
+/// Wrap a value so that it cannot be dropped implicitly.
+///
+/// `PreventDrop<T>` is the drop guard counterpart of the macros for
+/// types you cannot implement `Drop` for, such as types from other
+/// crates. It uses the strategy selected through the features, just like
+/// `prevent_drop!`. Consume the wrapper with `into_inner` to get the
+/// value back without triggering the guard.
+///
+/// ```
+/// use prevent_drop::PreventDrop;
+///
+/// let guarded = PreventDrop::new(vec![1, 2, 3]);
+/// let value = guarded.into_inner();
+/// assert_eq!(value, [1, 2, 3]);
+/// ```
+pub struct PreventDrop<T> {
+    value: T,
+}
+
+impl<T> PreventDrop<T> {
+    /// Guard `value`.
+    #[inline]
+    pub fn new(value: T) -> Self {
+        PreventDrop { value }
+    }
+
+    /// Take the value out of the wrapper without triggering the guard.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        rt::take_field(self, |this| &this.value)
+    }
+}
+
+impl<T> Deref for PreventDrop<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for PreventDrop<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+prevent_drop_wrapper!(
+    PreventDrop<T>,
+    prevent_drop_PreventDrop,
+    generics = [T],
+    "The `PreventDrop` wrapper requires you to enable optimizations or to enable one of the `abort`, `panic` or `auto_fallback` features."
+);
+
+#[cfg(test)]
+mod tests {
+    use super::PreventDrop;
+
+    // The drop of the wrapper is tested in `tests/wrappers.rs`, where the
+    // guard panics in every build.
+    #[test]
+    fn into_inner_returns_value() {
+        // Nothing may panic while the value is guarded, otherwise the
+        // link strategy sees a drop on the unwinding path.
+        let mut guarded = PreventDrop::new((1u32, 2u32));
+        guarded.0 = 3;
+        let first = guarded.0;
+        let value = guarded.into_inner();
+        assert_eq!(first, 3);
+        assert_eq!(value, (3, 2));
+    }
+}
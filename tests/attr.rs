@@ -0,0 +1,73 @@
+#![cfg(feature = "derive")]
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+mod guarded {
+    use prevent_drop::attr::prevent_drop;
+
+    #[prevent_drop]
+    pub struct Resource;
+
+    impl Resource {
+        pub fn drop(self) {
+            let _self = ::std::mem::ManuallyDrop::new(self);
+        }
+    }
+
+    #[derive(Debug)]
+    #[prevent_drop(strategy = "panic")]
+    pub struct Unit;
+
+    #[derive(Debug)]
+    #[prevent_drop(strategy = "panic", message = "Leaked a Tuple!")]
+    pub struct Tuple(pub u32, pub &'static str);
+
+    #[derive(Debug)]
+    #[prevent_drop(strategy = "panic")]
+    pub struct Fields<T> {
+        pub value: T,
+        pub count: usize,
+    }
+}
+
+use guarded::{Fields, Resource, Tuple, Unit};
+
+assert_prevent_drop!(Resource, Unit, Tuple, Fields<u8>);
+
+#[test]
+fn attr_default_strategy() {
+    let r = Resource;
+    r.drop();
+}
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of Unit.")]
+fn attr_unit_struct_panics() {
+    ::std::mem::drop(Unit);
+}
+
+#[test]
+#[should_panic(expected = "Leaked a Tuple!")]
+fn attr_tuple_struct_panics() {
+    let x = Tuple(1, "two");
+    assert_eq!((x.0, x.1), (1, "two"));
+    ::std::mem::drop(x);
+}
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of Fields")]
+fn attr_struct_with_fields_panics() {
+    let x = Fields { value: 1u8, count: 2 };
+    assert_eq!((x.value, x.count), (1, 2));
+    ::std::mem::drop(x);
+}
+
+#[test]
+fn attr_does_not_panic_if_value_is_not_dropped() {
+    let _ = ManuallyDrop::new(Unit);
+    let _ = ManuallyDrop::new(Tuple(1, "two"));
+    let _ = ManuallyDrop::new(Fields { value: "value", count: 0 });
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::process::Output;
+
+fn cargo_run(opt_level: u32) -> Output {
+    common::cargo(&["run"], "auto_fallback", &format!("opt_level_{}", opt_level))
+        .env("CARGO_PROFILE_DEV_OPT_LEVEL", opt_level.to_string())
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn auto_fallback_panics_without_optimizations() {
+    let output = cargo_run(0);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "expected a panic");
+    assert!(
+        stderr.contains("Forgot to explicitly drop an instance of Resource."),
+        "expected the panic strategy:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn auto_fallback_links_with_optimizations() {
+    let output = cargo_run(1);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "expected a linker error");
+    assert!(
+        stderr.contains("PREVENT_DROP_you_forgot_to_consume_Resource"),
+        "expected the link strategy:\n{}",
+        stderr
+    );
+    assert!(!stderr.contains("Forgot to explicitly drop"));
+}
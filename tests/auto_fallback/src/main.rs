@@ -0,0 +1,16 @@
+//! Test for the `auto_fallback` feature.
+//!
+//! `tests/auto_fallback.rs` builds this crate with and without
+//! optimizations. With them it must fail to link, without them it must
+//! panic at run time.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+prevent_drop!(Resource, PREVENT_DROP_you_forgot_to_consume_Resource);
+
+fn main() {
+    let _r = Resource;
+}
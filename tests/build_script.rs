@@ -0,0 +1,165 @@
+#[path = "../build.rs"]
+#[allow(dead_code)]
+mod build_script;
+
+use std::collections::HashMap;
+
+use build_script::{directives, force_strategy, opt_level_cfg, opt_level_gt_0, rustc_cfg};
+
+fn cfgs(env: &[(&str, &str)]) -> Vec<String> {
+    let env: HashMap<&str, &str> = env.iter().cloned().collect();
+    directives(|name| env.get(name).map(|value| value.to_string()))
+        .into_iter()
+        .filter(|directive| directive.starts_with("cargo:rustc-cfg="))
+        .collect()
+}
+
+#[test]
+fn opt_level_unset() {
+    assert!(!opt_level_gt_0(None));
+}
+
+#[test]
+fn opt_level_numeric() {
+    assert!(!opt_level_gt_0(Some("0")));
+    assert!(opt_level_gt_0(Some("1")));
+    assert!(opt_level_gt_0(Some("2")));
+    assert!(opt_level_gt_0(Some("3")));
+}
+
+#[test]
+fn opt_level_size() {
+    assert!(opt_level_gt_0(Some("s")));
+    assert!(opt_level_gt_0(Some("z")));
+}
+
+#[test]
+fn opt_level_unrecognized() {
+    assert!(!opt_level_gt_0(Some("")));
+    assert!(!opt_level_gt_0(Some("fast")));
+}
+
+#[test]
+fn opt_level_size_emits_cfg() {
+    for opt_level in &["s", "z"] {
+        assert_eq!(
+            rustc_cfg(Some(opt_level)),
+            Some("cargo:rustc-cfg=opt_level_gt_0")
+        );
+    }
+}
+
+#[test]
+fn opt_level_0_emits_no_cfg() {
+    assert_eq!(rustc_cfg(Some("0")), None);
+    assert_eq!(rustc_cfg(None), None);
+}
+
+#[test]
+fn directives_are_prefixed() {
+    for directive in directives(|_| None) {
+        assert!(directive.starts_with("cargo:"), "{}", directive);
+    }
+}
+
+#[test]
+fn directives_follow_opt_level() {
+    assert_eq!(
+        cfgs(&[("OPT_LEVEL", "3")]),
+        ["cargo:rustc-cfg=opt_level_gt_0", "cargo:rustc-cfg=prevent_drop_opt_level=\"3\""]
+    );
+    assert_eq!(cfgs(&[("OPT_LEVEL", "0")]), ["cargo:rustc-cfg=prevent_drop_opt_level=\"0\""]);
+    assert!(cfgs(&[]).is_empty());
+}
+
+#[test]
+fn directives_ignore_profile_name() {
+    // A release build of a build script dependency uses the
+    // `build-override` profile, which defaults to opt-level 0.
+    assert_eq!(
+        cfgs(&[("PROFILE", "release"), ("OPT_LEVEL", "0")]),
+        ["cargo:rustc-cfg=prevent_drop_opt_level=\"0\""]
+    );
+    // A debug build with `opt-level = 1`, as in this crate's test profile.
+    assert_eq!(
+        cfgs(&[("PROFILE", "debug"), ("OPT_LEVEL", "1")]),
+        ["cargo:rustc-cfg=opt_level_gt_0", "cargo:rustc-cfg=prevent_drop_opt_level=\"1\""]
+    );
+}
+
+#[test]
+fn directives_ignore_host_and_target() {
+    let env = [("HOST", "x86_64-unknown-linux-gnu"), ("TARGET", "aarch64-unknown-linux-gnu")];
+    assert!(cfgs(&env).is_empty());
+    let env = [env[0], env[1], ("OPT_LEVEL", "2")];
+    assert_eq!(
+        cfgs(&env),
+        ["cargo:rustc-cfg=opt_level_gt_0", "cargo:rustc-cfg=prevent_drop_opt_level=\"2\""]
+    );
+}
+
+#[test]
+fn opt_level_cfg_matches_opt_level() {
+    for opt_level in &["0", "1", "2", "3", "s", "z"] {
+        let expected = format!("cargo:rustc-cfg=prevent_drop_opt_level=\"{}\"", opt_level);
+        assert_eq!(opt_level_cfg(Some(opt_level)), Some(expected.clone()));
+        assert!(cfgs(&[("OPT_LEVEL", opt_level)]).contains(&expected));
+    }
+}
+
+#[test]
+fn opt_level_cfg_unrecognized() {
+    assert_eq!(opt_level_cfg(None), None);
+    assert_eq!(opt_level_cfg(Some("")), None);
+    assert_eq!(opt_level_cfg(Some("fast")), None);
+}
+
+#[test]
+fn force_strategy_known() {
+    assert_eq!(force_strategy("link"), Some("link"));
+    assert_eq!(force_strategy("abort"), Some("abort"));
+    assert_eq!(force_strategy(" panic\n"), Some("panic"));
+}
+
+#[test]
+fn force_strategy_unknown() {
+    assert_eq!(force_strategy("log"), None);
+    assert_eq!(force_strategy("Panic"), None);
+}
+
+#[test]
+fn directives_force_strategy() {
+    for strategy in &["link", "abort", "panic"] {
+        assert_eq!(
+            cfgs(&[("PREVENT_DROP_FORCE_STRATEGY", strategy)]),
+            [
+                "cargo:rustc-cfg=prevent_drop_force".to_string(),
+                format!("cargo:rustc-cfg=prevent_drop_force=\"{}\"", strategy),
+            ]
+        );
+    }
+}
+
+#[test]
+fn directives_force_strategy_keeps_opt_level() {
+    assert_eq!(
+        cfgs(&[("PREVENT_DROP_FORCE_STRATEGY", "abort"), ("OPT_LEVEL", "1")]),
+        [
+            "cargo:rustc-cfg=prevent_drop_force",
+            "cargo:rustc-cfg=prevent_drop_force=\"abort\"",
+            "cargo:rustc-cfg=opt_level_gt_0",
+            "cargo:rustc-cfg=prevent_drop_opt_level=\"1\"",
+        ]
+    );
+}
+
+#[test]
+fn directives_force_strategy_empty_is_unset() {
+    assert!(cfgs(&[("PREVENT_DROP_FORCE_STRATEGY", "")]).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "PREVENT_DROP_FORCE_STRATEGY must be one of")]
+fn directives_force_strategy_unknown_panics() {
+    cfgs(&[("PREVENT_DROP_FORCE_STRATEGY", "log")]);
+}
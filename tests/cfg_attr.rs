@@ -0,0 +1,31 @@
+#![cfg(feature = "derive")]
+
+mod common;
+
+use std::process::Output;
+
+fn cargo_run(features: &[&str], variant: &str) -> Output {
+    common::cargo(&["run"], "cfg_attr", variant)
+        .args(features.iter().flat_map(|feature| ["--features", feature]))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn cfg_attr_installs_the_guard_with_the_feature() {
+    let output = cargo_run(&["strict"], "strict");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(101), "expected a panic:\n{}", stderr);
+    assert!(
+        stderr.contains("Forgot to explicitly drop an instance of Resource."),
+        "expected the guard to fire:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn cfg_attr_leaves_the_type_unguarded_without_the_feature() {
+    let output = cargo_run(&[], "lenient");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
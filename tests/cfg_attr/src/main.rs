@@ -0,0 +1,15 @@
+//! Test for `#[cfg_attr(feature = "strict", prevent_drop(...))]`.
+//!
+//! `tests/cfg_attr.rs` runs this crate with and without the `strict`
+//! feature. With it dropping the value must panic, without it the type
+//! must not be guarded at all.
+
+extern crate prevent_drop;
+
+#[cfg_attr(feature = "strict", prevent_drop::attr::prevent_drop(strategy = "panic"))]
+struct Resource;
+
+fn main() {
+    let _r = Resource;
+    println!("ok");
+}
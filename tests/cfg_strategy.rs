@@ -0,0 +1,34 @@
+mod common;
+
+use std::process::Output;
+
+fn cargo_run(rustflags: &str, variant: &str) -> Output {
+    common::cargo(&["run"], "cfg_strategy", variant)
+        .env("RUSTFLAGS", rustflags)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn cfg_strategy_selects_panic_branch() {
+    let output = cargo_run("--cfg prevent_drop_demo_panic", "panic");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(101), "expected a panic:\n{}", stderr);
+    assert!(
+        stderr.contains("Forgot to explicitly drop an instance of Resource."),
+        "expected the panic strategy:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn cfg_strategy_selects_fallback_branch() {
+    let output = cargo_run("", "fallback");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "expected an abort");
+    assert!(
+        stderr.contains("prevent_drop: an instance of Resource"),
+        "expected the abort strategy:\n{}",
+        stderr
+    );
+}
@@ -0,0 +1,20 @@
+//! Test for `strategy = cfg { ... }`.
+//!
+//! `tests/cfg_strategy.rs` runs this crate with and without
+//! `--cfg prevent_drop_demo_panic`. With it the value must panic, without
+//! it the value must abort.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+prevent_drop!(
+    Resource,
+    prevent_drop_cfg_strategy_Resource,
+    strategy = cfg { prevent_drop_demo_panic => panic, _ => abort }
+);
+
+fn main() {
+    let _r = Resource;
+}
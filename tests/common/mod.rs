@@ -0,0 +1,54 @@
+//! Builds and runs the fixture crates in `tests/`, each of which depends
+//! on `prevent_drop` with its own features, profile or flags.
+
+// Every test crate that declares `mod common` uses only part of it.
+#![allow(dead_code)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The root of this crate.
+pub fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The target directory of the `variant` build of `tests/<fixture>`.
+///
+/// Every variant gets its own directory, so builds that differ in
+/// features, profile or flags do not invalidate each other.
+pub fn target_dir(fixture: &str, variant: &str) -> PathBuf {
+    root().join("target").join(fixture).join(variant)
+}
+
+/// A `cargo` command that runs `subcommand` on `tests/<fixture>`, with
+/// its target directory from `target_dir`.
+///
+/// The environment variables that change how `prevent_drop` or the
+/// fixture behaves are removed, so set those on the returned command.
+/// Arguments added to it come after the manifest path.
+pub fn cargo(subcommand: &[&str], fixture: &str, variant: &str) -> Command {
+    let mut command = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    command
+        .args(subcommand)
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(root().join("tests").join(fixture).join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target_dir(fixture, variant))
+        .env_remove("PREVENT_DROP_FORCE_STRATEGY")
+        .env_remove("RUSTFLAGS")
+        .env_remove("RUST_BACKTRACE");
+    command
+}
+
+/// Build the binary `bin` of `tests/<fixture>` in the dev profile, panic
+/// if that fails, and return its path.
+pub fn build_binary(fixture: &str, variant: &str, bin: &str) -> PathBuf {
+    let output = cargo(&["build"], fixture, variant)
+        .arg("--bin")
+        .arg(bin)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    target_dir(fixture, variant).join("debug").join(bin)
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use std::process::Output;
+
+fn build(bin: &str) -> Output {
+    common::cargo(&["build"], "const_strategy", "default")
+        .arg("--bin")
+        .arg(bin)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn const_strategy_builds_without_optimizations() {
+    let output = build("consumed");
+    assert!(
+        output.status.success(),
+        "expected the build to succeed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn const_strategy_names_the_dropped_type_without_optimizations() {
+    let output = build("dropped");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "expected a compile error");
+    assert!(
+        stderr.contains("Forgot to explicitly drop an instance of Resource."),
+        "compile error does not name the type:\n{}",
+        stderr
+    );
+}
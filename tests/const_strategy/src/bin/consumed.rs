@@ -0,0 +1,23 @@
+//! Compile test for the const strategy without optimizations.
+//!
+//! This binary must build. `tests/const_strategy.rs` builds it at
+//! opt-level 0.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+impl Resource {
+    fn close(self) {
+        let _zelf = std::mem::ManuallyDrop::new(self);
+    }
+}
+
+prevent_drop_const!(Resource);
+
+fn main() {
+    let r = Resource;
+    println!("using the resource");
+    r.close();
+}
@@ -0,0 +1,15 @@
+//! Compile test for the const strategy without optimizations.
+//!
+//! This binary must fail to build. `tests/const_strategy.rs` builds it at
+//! opt-level 0 and checks that the error names the dropped type.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+prevent_drop_const!(Resource);
+
+fn main() {
+    let _r = Resource;
+}
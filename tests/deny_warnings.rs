@@ -0,0 +1,55 @@
+// The labels generated by the abort and panic strategies are `pub fn`s,
+// often in private modules. A crate that denies warnings must be able to
+// use them without allow-listing anything. Removed lints in the
+// attributes the macros emit are only reported within this crate, so
+// the unit tests, which also deny warnings, catch those.
+#![deny(warnings)]
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+mod private {
+    pub struct Aborts;
+
+    prevent_drop_abort!(Aborts, deny_warnings_Aborts);
+
+    pub struct AbortsWithMessage<T>(pub T);
+
+    prevent_drop_abort!(
+        AbortsWithMessage<T>,
+        deny_warnings_AbortsWithMessage,
+        generics = [T],
+        "Leaked an AbortsWithMessage."
+    );
+
+    #[derive(Debug)]
+    pub struct Panics;
+
+    prevent_drop_panic!(Panics, deny_warnings_Panics, message_fn = deny_warnings_Panics_message);
+
+    pub struct Described(pub u32);
+
+    prevent_drop_panic!(
+        Described,
+        deny_warnings_Described,
+        |this| format!("Leaked Described({}).", this.0)
+    );
+}
+
+#[test]
+fn abort_guards_compile_without_warnings() {
+    let _ = ManuallyDrop::new(private::Aborts);
+    let _ = ManuallyDrop::new(private::AbortsWithMessage(1u8));
+}
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of Panics.")]
+fn panic_guards_compile_without_warnings() {
+    let mut message = String::new();
+    private::deny_warnings_Panics_message(&mut message).unwrap();
+    assert_eq!(message, "Forgot to explicitly drop an instance of Panics.");
+    let _ = ManuallyDrop::new(private::Described(1));
+    drop(private::Panics);
+}
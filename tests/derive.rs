@@ -0,0 +1,129 @@
+#![cfg(feature = "derive")]
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::env;
+use std::mem::ManuallyDrop;
+use std::process::Command;
+
+#[derive(PreventDrop)]
+struct Resource;
+
+impl Resource {
+    fn drop(self) {
+        let _self = ManuallyDrop::new(self);
+    }
+}
+
+#[test]
+fn derive_default_strategy() {
+    let r = Resource;
+    r.drop();
+}
+
+#[derive(Debug, PreventDrop)]
+#[prevent_drop(strategy = "panic")]
+struct PanicStrategy;
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of PanicStrategy.")]
+fn derive_strategy_override_panics() {
+    let x = PanicStrategy;
+    ::std::mem::drop(x);
+}
+
+#[test]
+fn derive_strategy_override_does_not_panic_if_value_is_not_dropped() {
+    let _ = ManuallyDrop::new(PanicStrategy);
+}
+
+#[derive(Debug, PreventDrop)]
+#[prevent_drop(strategy = "panic", message = "Leaked a MessageStrategy!")]
+struct MessageStrategy;
+
+#[test]
+#[should_panic(expected = "Leaked a MessageStrategy!")]
+fn derive_message_override_panics() {
+    let x = MessageStrategy;
+    ::std::mem::drop(x);
+}
+
+#[derive(Debug, PreventDrop)]
+#[prevent_drop(strategy = "abort", message = "Leaked an AbortMessage!")]
+struct AbortMessage;
+
+const CHILD: &str = "PREVENT_DROP_DERIVE_CHILD";
+
+// Only drops the value when spawned by `derive_abort_message_aborts`,
+// since aborting takes the whole test binary down.
+#[test]
+fn derive_abort_child() {
+    if env::var(CHILD).is_ok() {
+        ::std::mem::drop(AbortMessage);
+    }
+}
+
+#[test]
+fn derive_abort_message_aborts() {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "derive_abort_child", "--nocapture"])
+        .env(CHILD, "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "dropping AbortMessage did not abort:\n{}", stderr);
+    assert!(stderr.contains("Leaked an AbortMessage!"), "unexpected stderr:\n{}", stderr);
+}
+
+#[derive(Debug, PreventDrop)]
+#[prevent_drop(strategy = "panic")]
+struct Generic<'a, T: Clone, const N: usize = 4>(&'a [T; N]);
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of Generic")]
+fn derive_generic_panics() {
+    let values = [1u32; 4];
+    let x: Generic<u32> = Generic(&values);
+    assert_eq!(x.0.len(), 4);
+    ::std::mem::drop(x);
+}
+
+#[derive(Debug, PreventDrop)]
+#[prevent_drop(strategy = "panic", message = "Leaked a Bounded!")]
+struct Bounded<T>(T)
+where
+    T: Clone + ::std::fmt::Debug;
+
+#[test]
+#[should_panic(expected = "Leaked a Bounded!")]
+fn derive_where_clause_panics() {
+    let x = Bounded(1u8);
+    assert_eq!(x.0, 1);
+    ::std::mem::drop(x);
+}
+
+mod same_name {
+    #[derive(Debug, PreventDrop)]
+    #[prevent_drop(strategy = "panic")]
+    pub struct PanicStrategy;
+}
+
+#[test]
+fn derive_types_defined_in_functions_get_unique_labels() {
+    #[derive(Debug, PreventDrop)]
+    #[prevent_drop(strategy = "panic")]
+    struct Local;
+
+    fn other() {
+        #[derive(Debug, PreventDrop)]
+        #[prevent_drop(strategy = "panic")]
+        struct Local;
+
+        let _ = ManuallyDrop::new(Local);
+    }
+
+    let _ = ManuallyDrop::new(Local);
+    let _ = ManuallyDrop::new(same_name::PanicStrategy);
+    other();
+}
@@ -0,0 +1,24 @@
+mod common;
+
+fn assert_inert(features: &[&str], variant: &str) {
+    let output = common::cargo(&["run"], "dry_run", variant)
+        .args(features)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "expected the guards to stay inert:\n{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+    assert!(stderr.is_empty(), "expected no guard to report:\n{}", stderr);
+}
+
+// The fixture builds without optimizations, where the wrappers would
+// otherwise refuse to compile.
+#[test]
+fn dry_run_guards_do_nothing() {
+    assert_inert(&[], "default");
+}
+
+#[test]
+fn dry_run_guards_do_nothing_with_the_panic_feature() {
+    assert_inert(&["--features", "prevent_drop/panic"], "panic");
+}
@@ -0,0 +1,86 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Default;
+prevent_drop!(Default, dry_run_Default);
+
+struct Linked;
+prevent_drop!(Linked, dry_run_Linked, strategy = link);
+
+struct Aborted;
+prevent_drop!(Aborted, dry_run_Aborted, strategy = abort, "Aborted was dropped.");
+
+struct Panicked<T>(T);
+prevent_drop!(Panicked<T>, dry_run_Panicked, strategy = panic, generics = [T]);
+
+struct Described;
+prevent_drop!(Described, dry_run_Described, strategy = panic, |this| format!("Leaked a Described at {:p}.", this));
+
+struct Reported;
+prevent_drop!(Reported, dry_run_Reported, strategy = abort, message_fn = dry_run_Reported_message);
+
+struct Constant;
+prevent_drop!(Constant, dry_run_Constant, strategy = const);
+
+struct Printed;
+prevent_drop!(Printed, dry_run_Printed, strategy = eprintln);
+
+struct Selected;
+prevent_drop!(Selected, dry_run_Selected, strategy = cfg { unix => abort, _ => panic });
+
+struct Bounded<T: Clone>(T);
+prevent_drop!(Bounded<T>, dry_run_Bounded, generics = [T], where = [T: Clone]);
+
+struct Defused;
+prevent_drop!(Defused, dry_run_Defused, defuse = disarm, strategy = panic);
+
+struct First;
+struct Second;
+prevent_drop! {
+    First => dry_run_First(strategy = abort),
+    Second => dry_run_Second(strategy = panic, "Leaked the second guard."),
+}
+
+assert_prevent_drop!(
+    Default,
+    Linked,
+    Aborted,
+    Panicked<u8>,
+    Described,
+    Reported,
+    Constant,
+    Printed,
+    Selected,
+    Bounded<u8>,
+    Defused,
+    First,
+    Second,
+);
+
+fn main() {
+    assert_eq!(prevent_drop::STRATEGY, prevent_drop::Strategy::DryRun);
+
+    drop(prevent_drop::PreventDrop::new(1u8));
+    drop(prevent_drop::MustConsume::new(1u8));
+    let mut slot = Some(1u8);
+    drop(prevent_drop::DropGuard::new(&mut slot));
+
+    drop(Default);
+    drop(Linked);
+    drop(Aborted);
+    drop(Panicked(1u8));
+    drop(Described);
+    drop(Reported);
+    drop(Constant);
+    drop(Printed);
+    drop(Selected);
+    drop(Bounded(1u8));
+    let _ = Defused.disarm();
+    drop(First);
+    drop(Second);
+    dry_run_Aborted();
+    dry_run_Described(&"Leaked a Described.");
+    dry_run_Reported_message(&mut String::new()).unwrap();
+
+    println!("ok");
+}
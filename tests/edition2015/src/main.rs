@@ -0,0 +1,7 @@
+//! Every public macro used from a 2015 edition crate through
+//! `#[macro_use]`.
+
+#[macro_use]
+extern crate prevent_drop;
+
+include!("../../editions/guards.rs");
@@ -0,0 +1,14 @@
+//! Every public macro used from a 2018 edition crate through path
+//! imports, without `extern crate`.
+
+use prevent_drop::{
+    assert_prevent_drop, guard_or_cleanup, prevent_drop, prevent_drop_abort, prevent_drop_async,
+    prevent_drop_builder, prevent_drop_callback, prevent_drop_const, prevent_drop_consume,
+    prevent_drop_count, prevent_drop_debug_only, prevent_drop_deprecated, prevent_drop_eprintln,
+    prevent_drop_ffi, prevent_drop_if, prevent_drop_link, prevent_drop_newtype, prevent_drop_once,
+    prevent_drop_panic, prevent_drop_ptr, prevent_drop_recent, prevent_drop_record,
+    prevent_drop_static_assert, prevent_drop_timeout, prevent_drop_trait, prevent_drop_try_drop,
+    prevent_drop_wrap,
+};
+
+include!("../../editions/guards.rs");
@@ -0,0 +1,34 @@
+//! The same guards, written once in `tests/editions/guards.rs`, work
+//! from a 2015 edition crate using `#[macro_use]` and from a 2018 edition
+//! crate using path imports.
+
+mod common;
+
+use std::process::Output;
+
+fn cargo_run(edition: &str) -> Output {
+    common::cargo(&["run"], &format!("edition{}", edition), "default")
+        .output()
+        .unwrap()
+}
+
+fn assert_runs(edition: &str) {
+    let output = cargo_run(edition);
+    assert!(
+        output.status.success(),
+        "expected the {} edition crate to run:\n{}",
+        edition,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
+
+#[test]
+fn edition_2015_macro_use() {
+    assert_runs("2015");
+}
+
+#[test]
+fn edition_2018_path_imports() {
+    assert_runs("2018");
+}
@@ -0,0 +1,277 @@
+// Shared by the `edition2015` and `edition2018` test crates, which bring
+// the macros into scope before including this file: the 2015 crate with
+// `#[macro_use] extern crate`, the 2018 crate with a `use` declaration.
+
+use std::error::Error;
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::panic;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+use std::time::Instant;
+
+use prevent_drop::{BuildGuard, TryDrop};
+
+struct Dispatched;
+prevent_drop!(Dispatched, editions_Dispatched);
+
+struct Linked;
+prevent_drop!(Linked, editions_Linked, strategy = link);
+
+struct Aborted;
+prevent_drop!(Aborted, editions_Aborted, strategy = abort, "Aborted was dropped.");
+
+struct Panicked<T>(T);
+prevent_drop!(Panicked<T>, editions_Panicked, strategy = panic, generics = [T]);
+
+struct Constant;
+prevent_drop!(Constant, editions_Constant, strategy = const);
+
+struct Printed;
+prevent_drop!(Printed, editions_Printed, strategy = eprintln);
+
+struct Selected;
+prevent_drop!(Selected, editions_Selected, strategy = cfg { unix => abort, _ => panic });
+
+struct Bounded<T: Clone>(T);
+prevent_drop!(
+    Bounded<T>,
+    editions_Bounded,
+    strategy = panic,
+    generics = [T],
+    where = [T: Clone]
+);
+
+struct Defused;
+prevent_drop!(Defused, editions_Defused, defuse = disarm, strategy = panic);
+
+struct Leaked;
+prevent_drop!(Leaked, editions_Leaked, leak_intentionally, strategy = panic);
+
+struct First;
+struct Second;
+prevent_drop! {
+    First => editions_First(strategy = panic),
+    Second => editions_Second(strategy = panic, "Leaked the second guard."),
+}
+
+struct Called;
+fn callback() {}
+prevent_drop_callback!(Called, editions_Called, callback);
+
+struct Counted;
+prevent_drop_count!(Counted, editions_Counted);
+
+struct Recorded;
+prevent_drop_record!(Recorded, editions_Recorded);
+
+struct Once;
+prevent_drop_once!(Once, editions_Once);
+
+struct Debug;
+prevent_drop_debug_only!(Debug, editions_Debug, strategy = panic);
+
+struct Marker;
+prevent_drop_static_assert!(Marker, editions_Marker, strategy = panic);
+
+struct Handle(Option<u32>);
+prevent_drop_if!(Handle, editions_Handle, |this| this.0.is_some());
+
+struct Wrapped;
+prevent_drop_wrap!(Wrapped, editions_Wrapped, |this| { let _ = this; }, strategy = panic);
+
+#[derive(Default)]
+struct Builder {
+    guard: BuildGuard<Builder>,
+}
+prevent_drop_builder!(Builder, editions_Builder, strategy = panic);
+
+struct Consumed(u32);
+prevent_drop_panic!(Consumed, editions_Consumed);
+fn consume(consumed: ManuallyDrop<Consumed>) -> u32 {
+    consumed.0
+}
+prevent_drop_consume!(Consumed, fn close() -> u32 = consume);
+
+struct Tried;
+prevent_drop_panic!(Tried, editions_Tried);
+fn try_cleanup(_tried: ManuallyDrop<Tried>) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+prevent_drop_try_drop!(Tried, try_cleanup);
+
+struct Fd(i32);
+prevent_drop_ffi!(Fd, editions_Fd, |fd| fd.0);
+
+struct Socket(usize);
+fn flush(socket: ManuallyDrop<Socket>) -> ::std::future::Ready<usize> {
+    ::std::future::ready(socket.0)
+}
+prevent_drop_async!(Socket, editions_Socket, fn close() -> usize = flush, strategy = panic);
+
+struct Abort;
+prevent_drop_abort!(Abort, editions_Abort);
+
+struct Panic;
+prevent_drop_panic!(Panic, editions_Panic);
+
+struct Link;
+prevent_drop_link!(Link, editions_Link);
+
+struct Eprintln;
+prevent_drop_eprintln!(Eprintln, editions_Eprintln);
+
+struct Const;
+prevent_drop_const!(Const);
+
+struct Deprecated;
+prevent_drop_deprecated!(Deprecated, editions_Deprecated);
+
+prevent_drop_newtype!(Wrapper, u32, editions_Wrapper, strategy = panic);
+
+struct Pointer(*mut u8);
+prevent_drop_ptr!(Pointer, editions_Pointer, close, |ptr: *mut u8| -> bool { ptr.is_null() });
+
+trait Transaction {
+    fn commit(self: Box<Self>) -> usize;
+}
+struct Counter(usize);
+impl Transaction for Counter {
+    fn commit(self: Box<Self>) -> usize {
+        self.0
+    }
+}
+prevent_drop_trait!(Pending, dyn Transaction, editions_Pending, {
+    fn commit() -> usize;
+}, strategy = panic);
+
+struct Timed;
+prevent_drop_timeout!(Timed, editions_Timed);
+
+struct Recent;
+prevent_drop_recent!(Recent, editions_Recent);
+
+assert_prevent_drop!(
+    Dispatched,
+    Linked,
+    Aborted,
+    Panicked<u8>,
+    Constant,
+    Printed,
+    Selected,
+    Bounded<u8>,
+    Defused,
+    Leaked,
+    First,
+    Second,
+    Called,
+    Counted,
+    Recorded,
+    Once,
+    Debug,
+    Marker,
+    Handle,
+    Wrapped,
+    Builder,
+    Consumed,
+    Tried,
+    Fd,
+    Socket,
+    Abort,
+    Panic,
+    Link,
+    Eprintln,
+    Const,
+    Deprecated,
+    Wrapper,
+    Pointer,
+    Pending,
+    Timed,
+    Recent,
+);
+
+fn panics<F: FnOnce() + panic::UnwindSafe>(f: F, expected: &str) {
+    let payload = panic::catch_unwind(f).unwrap_err();
+    let message = payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|message| message.to_string()))
+        .unwrap();
+    assert!(message.starts_with(expected), "unexpected message: {}", message);
+}
+
+fn main() {
+    // Nothing may panic while a value guarded by the link or const
+    // strategy is alive, so those are never kept around.
+    let _ = ManuallyDrop::new(Dispatched);
+    let _ = ManuallyDrop::new(Linked);
+    let _ = ManuallyDrop::new(Aborted);
+    let _ = ManuallyDrop::new(Constant);
+    let _ = ManuallyDrop::new(Selected);
+    let _ = ManuallyDrop::new(Marker);
+    let _ = ManuallyDrop::new(Abort);
+    let _ = ManuallyDrop::new(Link);
+    let _ = ManuallyDrop::new(Const);
+
+    panic::set_hook(Box::new(|_| {}));
+
+    panics(|| drop(Panicked(1u8)), "Forgot to explicitly drop an instance of Panicked<T>.");
+    panics(|| drop(Bounded(1u8)), "Forgot to explicitly drop an instance of Bounded<T>.");
+    panics(|| drop(First), "Forgot to explicitly drop an instance of First.");
+    panics(|| drop(Second), "Leaked the second guard.");
+    panics(|| drop(Wrapped), "Forgot to explicitly drop an instance of Wrapped.");
+    panics(|| drop(Panic), "Forgot to explicitly drop an instance of Panic.");
+    panics(|| drop(Builder::default()), "Forgot to build an instance of Builder.");
+    panics(|| drop(Debug), "Forgot to explicitly drop an instance of Debug.");
+
+    let _ = Defused.disarm();
+    Leaked.leak_intentionally();
+    drop(Printed);
+    drop(Eprintln);
+    drop(Deprecated);
+    drop(Called);
+
+    prevent_drop::reset_leak_count();
+    drop(Counted);
+    assert_eq!(prevent_drop::leak_count(), 1);
+
+    drop(Recorded);
+    assert_eq!(prevent_drop::take_recorded_leaks(), ["Recorded"]);
+
+    drop(Once);
+    panics(|| drop(Once), "Forgot to explicitly drop an instance of Once.");
+
+    drop(Handle(None));
+    panics(|| drop(Handle(Some(1))), "Forgot to explicitly drop an instance of Handle.");
+
+    let Builder { guard } = Builder::default();
+    guard.defuse();
+
+    assert_eq!(Consumed(3).close(), 3);
+    assert!(Box::new(Tried).try_drop().is_ok());
+    assert_eq!(Fd(4).close(), 4);
+
+    let mut close = Socket(5).close();
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(Pin::new(&mut close).poll(&mut cx), Poll::Ready(5));
+
+    let guarded = guard_or_cleanup!(Consumed(6), |consumed| {
+        consumed.close();
+    });
+    assert_eq!(guarded.defuse().close(), 6);
+
+    let wrapper = Wrapper::new(7);
+    assert_eq!(*wrapper, 7);
+    assert_eq!(wrapper.into_inner(), 7);
+    assert!(Pointer(::std::ptr::null_mut()).close());
+    assert_eq!(Pending::new(Box::new(Counter(8))).commit(), 8);
+
+    drop(Recent);
+    assert_eq!(prevent_drop::recent_leaks()[0].0, "Recent");
+
+    drop(Timed);
+    prevent_drop::set_shutdown_deadline(Instant::now());
+    panics(|| drop(Timed), "Forgot to explicitly drop an instance of Timed.");
+
+    println!("ok");
+}
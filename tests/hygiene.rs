@@ -0,0 +1,103 @@
+//! The macros keep working in a module that shadows the names their
+//! expansions could otherwise pick up from the invocation site.
+
+#[macro_use]
+extern crate prevent_drop;
+
+mod shadowed {
+    #![allow(dead_code, unused_macros)]
+
+    mod std {}
+    mod core {}
+
+    trait Drop {}
+
+    macro_rules! panic {
+        ($($args:tt)*) => {
+            compile_error!("the local `panic!` was used")
+        };
+    }
+    macro_rules! concat {
+        ($($args:tt)*) => {
+            compile_error!("the local `concat!` was used")
+        };
+    }
+    macro_rules! stringify {
+        ($($args:tt)*) => {
+            compile_error!("the local `stringify!` was used")
+        };
+    }
+    macro_rules! assert {
+        ($($args:tt)*) => {
+            compile_error!("the local `assert!` was used")
+        };
+    }
+    macro_rules! format_args {
+        ($($args:tt)*) => {
+            compile_error!("the local `format_args!` was used")
+        };
+    }
+
+    pub struct Default;
+    prevent_drop!(Default, hygiene_Default);
+
+    pub struct Aborted;
+    prevent_drop_abort!(Aborted, hygiene_Aborted);
+
+    pub struct Panicked<T>(pub T);
+    prevent_drop_panic!(Panicked<T>, hygiene_Panicked, generics = [T]);
+
+    pub struct Printed;
+    prevent_drop_eprintln!(Printed, hygiene_Printed);
+
+    pub struct Once;
+    prevent_drop_once!(Once, hygiene_Once, allow = 2);
+
+    pub struct Constant;
+    prevent_drop_const!(Constant);
+
+    pub struct Debug;
+    prevent_drop_debug_only!(Debug, hygiene_Debug);
+
+    pub struct Marker;
+    prevent_drop_static_assert!(Marker, hygiene_Marker, strategy = panic);
+
+    pub struct Selected;
+    prevent_drop!(
+        Selected,
+        hygiene_Selected,
+        strategy = cfg { unix => abort, _ => panic }
+    );
+
+    assert_prevent_drop!(
+        Default,
+        Aborted,
+        Panicked<u8>,
+        Printed,
+        Once,
+        Constant,
+        Debug,
+        Marker,
+        Selected
+    );
+}
+
+use shadowed::*;
+
+#[test]
+fn macros_expand_in_a_shadowing_module() {
+    let _ = ::std::mem::ManuallyDrop::new(Default);
+    let _ = ::std::mem::ManuallyDrop::new(Aborted);
+    let _ = ::std::mem::ManuallyDrop::new(Printed);
+    let _ = ::std::mem::ManuallyDrop::new(Once);
+    let _ = ::std::mem::ManuallyDrop::new(Constant);
+    let _ = ::std::mem::ManuallyDrop::new(Debug);
+    let _ = ::std::mem::ManuallyDrop::new(Marker);
+    let _ = ::std::mem::ManuallyDrop::new(Selected);
+}
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of Panicked<T>.")]
+fn panic_strategy_panics_in_a_shadowing_module() {
+    drop(Panicked(1u8));
+}
@@ -0,0 +1,225 @@
+mod common;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn build(bin: &str) -> Output {
+    common::cargo(&["build"], "link_strategy", "default")
+        .arg("--bin")
+        .arg(bin)
+        .output()
+        .unwrap()
+}
+
+/// Build `bin` with the panic feature and the link strategy forced over
+/// it by `PREVENT_DROP_FORCE_STRATEGY`.
+fn build_forced(bin: &str) -> Output {
+    common::cargo(&["build"], "link_strategy", "forced")
+        .arg("--bin")
+        .arg(bin)
+        .arg("--features")
+        .arg("prevent_drop/panic")
+        .env("PREVENT_DROP_FORCE_STRATEGY", "link")
+        .output()
+        .unwrap()
+}
+
+fn assert_link_error(bin: &str, symbol: &str) {
+    assert_link_error_in(build(bin), symbol);
+}
+
+fn assert_link_error_in(output: Output, symbol: &str) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "expected a linker error");
+    assert!(
+        stderr.contains(symbol),
+        "linker error does not name the type:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn link_strategy_builds_if_value_is_consumed() {
+    let output = build("consumed");
+    assert!(
+        output.status.success(),
+        "expected the build to succeed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn link_error_names_the_dropped_type() {
+    assert_link_error("dropped", "PREVENT_DROP_link_strategy_dropped_Resource");
+}
+
+#[test]
+fn link_error_names_the_dropped_type_with_derive() {
+    assert_link_error("dropped_derive", "PREVENT_DROP_you_forgot_to_consume_Resource_");
+}
+
+#[test]
+fn forced_link_strategy_keeps_generics_and_inline() {
+    let output = build_forced("forced_consumed");
+    assert!(
+        output.status.success(),
+        "expected the build to succeed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn forced_link_strategy_overrides_the_panic_feature() {
+    assert_link_error_in(
+        build_forced("forced_dropped"),
+        "PREVENT_DROP_link_strategy_forced_dropped_Never",
+    );
+}
+
+// `false_positive` and `false_positive_fixed` are a cookbook example: the
+// first keeps a guarded value alive across a call that may panic, the
+// second is the rewrite that makes it link.
+#[test]
+fn link_error_for_value_alive_across_a_panic() {
+    assert_link_error("false_positive", "PREVENT_DROP_link_strategy_false_positive_Connection");
+}
+
+#[test]
+fn link_strategy_builds_after_rewriting_the_false_positive() {
+    let output = build("false_positive_fixed");
+    assert!(
+        output.status.success(),
+        "expected the build to succeed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Compile `bin` of the fixture to an object file with the given
+/// opt-level and return the path of the object. The object is written
+/// before linking, so it exists even if a drop fails to link.
+fn build_object(bin: &str, opt_level: &str) -> PathBuf {
+    let variant = format!("opt_level_{}", opt_level);
+    let object = common::target_dir("link_strategy", &variant).join(format!("{}.o", bin));
+    let output = common::cargo(&["rustc"], "link_strategy", &variant)
+        .arg("--bin")
+        .arg(bin)
+        .arg("--")
+        .arg("-C")
+        .arg("codegen-units=1")
+        .arg(format!("--emit=obj={}", object.display()))
+        .env("CARGO_PROFILE_DEV_OPT_LEVEL", opt_level)
+        .output()
+        .unwrap();
+    assert!(
+        object.exists(),
+        "expected the object to build:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    object
+}
+
+/// The undefined symbols of an object file according to `nm`, or `None`
+/// if `nm` is not available. Set `NM` to use another `nm`.
+fn undefined_symbols(object: &Path) -> Option<Vec<String>> {
+    let output = Command::new(env::var("NM").unwrap_or_else(|_| "nm".to_string()))
+        .arg("--undefined-only")
+        .arg(object)
+        .output()
+        .ok()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let symbols = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        // Mach-O prefixes symbols with an underscore.
+        .map(|symbol| symbol.trim_start_matches('_').to_string())
+        .collect();
+    Some(symbols)
+}
+
+/// Whether the object file for `bin` still calls the label `symbol`, which
+/// is the case if and only if the drop was not optimized away.
+fn references(bin: &str, opt_level: &str, symbol: &str) -> Option<bool> {
+    let symbols = undefined_symbols(&build_object(bin, opt_level))?;
+    Some(symbols.iter().any(|undefined| undefined == symbol))
+}
+
+// The link strategy only works with optimizations, so only optimized
+// builds are inspected.
+#[cfg(unix)]
+#[test]
+fn optimized_builds_elide_the_label_of_consumed_values() {
+    for opt_level in ["1", "3"] {
+        let consumed = references("consumed", opt_level, "PREVENT_DROP_link_strategy_consumed_Resource")
+            .expect("this test needs `nm`, set `NM` if it is not on the path");
+        let dropped = references("dropped", opt_level, "PREVENT_DROP_link_strategy_dropped_Resource")
+            .expect("this test needs `nm`, set `NM` if it is not on the path");
+        assert!(!consumed, "the consumed value still references its label at opt-level {}", opt_level);
+        assert!(dropped, "the dropped value does not reference its label at opt-level {}", opt_level);
+    }
+}
+
+// Unlike the drop of `dropped`, the false positive is not an optimizer
+// limitation that a higher opt-level fixes.
+#[cfg(unix)]
+#[test]
+fn false_positive_is_reported_at_every_opt_level() {
+    for opt_level in ["1", "3"] {
+        let before = references("false_positive", opt_level, "PREVENT_DROP_link_strategy_false_positive_Connection")
+            .expect("this test needs `nm`, set `NM` if it is not on the path");
+        let after = references(
+            "false_positive_fixed",
+            opt_level,
+            "PREVENT_DROP_link_strategy_false_positive_fixed_Connection",
+        )
+        .expect("this test needs `nm`, set `NM` if it is not on the path");
+        assert!(before, "the false positive went away at opt-level {}", opt_level);
+        assert!(!after, "the rewrite still references its label at opt-level {}", opt_level);
+    }
+}
+
+/// The sizes in bytes of the functions defined in an object file
+/// according to `nm`. Set `NM` to use another `nm`.
+fn function_sizes(object: &Path) -> Vec<(String, u64)> {
+    let output = Command::new(env::var("NM").unwrap_or_else(|_| "nm".to_string()))
+        .arg("--defined-only")
+        .arg("--print-size")
+        .arg(object)
+        .output()
+        .expect("this test needs `nm`, set `NM` if it is not on the path");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [_, size, _, symbol] => Some((
+                symbol.trim_start_matches('_').to_string(),
+                u64::from_str_radix(size, 16).unwrap(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+// The consumed path of the link strategy has no overhead: a function that
+// consumes a guarded value is exactly as large as one that consumes an
+// unguarded value, and neither references the label.
+#[cfg(unix)]
+#[test]
+fn consuming_a_guarded_value_costs_as_much_as_an_unguarded_one() {
+    for opt_level in ["1", "3"] {
+        let object = build_object("overhead", opt_level);
+        let sizes = function_sizes(&object);
+        let size = |name: &str| {
+            sizes
+                .iter()
+                .find(|&(symbol, _)| symbol == name)
+                .map(|&(_, size)| size)
+                .unwrap_or_else(|| panic!("`{}` is missing at opt-level {}", name, opt_level))
+        };
+        let baseline = size("link_strategy_overhead_baseline");
+        assert!(baseline > 0);
+        assert_eq!(size("link_strategy_overhead_linked"), baseline, "at opt-level {}", opt_level);
+        let symbols = undefined_symbols(&object).expect("this test needs `nm`");
+        assert!(!symbols.iter().any(|symbol| symbol == "PREVENT_DROP_link_strategy_overhead_Linked"));
+    }
+}
@@ -0,0 +1,53 @@
+//! Link test for the link strategy.
+//!
+//! This binary must build. `tests/link_strategy.rs` builds it to make
+//! sure consumed values do not reference the label.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+struct Resource(u32);
+
+impl Resource {
+    fn close(self) -> u32 {
+        let zelf = ManuallyDrop::new(self);
+        zelf.0
+    }
+}
+
+prevent_drop_link!(Resource, PREVENT_DROP_link_strategy_consumed_Resource);
+
+struct Pair<T>(T, T);
+
+impl<T> Pair<T> {
+    fn into_inner(self) -> (T, T) {
+        let zelf = ManuallyDrop::new(self);
+        unsafe { (std::ptr::read(&zelf.0), std::ptr::read(&zelf.1)) }
+    }
+}
+
+prevent_drop_link!(Pair<T>, PREVENT_DROP_link_strategy_consumed_Pair, generics = [T]);
+
+struct Always(u32);
+
+prevent_drop_link!(Always, PREVENT_DROP_link_strategy_consumed_Always, inline = always);
+
+struct Never<T>(T);
+
+prevent_drop_link!(
+    Never<T>,
+    PREVENT_DROP_link_strategy_consumed_Never,
+    inline = never,
+    generics = [T]
+);
+
+fn main() {
+    let r = Resource(1);
+    let id = r.close();
+    let (a, b) = Pair(id, 2).into_inner();
+    let c = ManuallyDrop::new(Always(3)).0;
+    let d = ManuallyDrop::new(Never(4)).0;
+    std::process::exit((a + b + c + d) as i32 - 10);
+}
@@ -0,0 +1,15 @@
+//! Link test for the link strategy.
+//!
+//! This binary must fail to link. `tests/link_strategy.rs` builds it and
+//! checks that the linker error names the label.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+prevent_drop_link!(Resource, PREVENT_DROP_link_strategy_dropped_Resource);
+
+fn main() {
+    let _r = Resource;
+}
@@ -0,0 +1,15 @@
+//! Link test for the link strategy selected through the derive.
+//!
+//! This binary must fail to link. `tests/link_strategy.rs` builds it and
+//! checks that the linker error names the dropped type.
+
+#[macro_use]
+extern crate prevent_drop;
+
+#[derive(PreventDrop)]
+#[prevent_drop(strategy = "link")]
+struct Resource;
+
+fn main() {
+    let _r = Resource;
+}
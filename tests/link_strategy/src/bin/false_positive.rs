@@ -0,0 +1,39 @@
+//! Cookbook: a false positive of the link strategy, before the rewrite.
+//!
+//! This binary must fail to link even though the connection is always
+//! closed. `send` may panic while `connection` is alive, so the unwinding
+//! path has to drop it and no opt-level can elide that drop.
+//! `false_positive_fixed.rs` shows the rewrite. `tests/link_strategy.rs`
+//! builds both.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+struct Connection(u32);
+
+impl Connection {
+    fn close(self) -> u32 {
+        let zelf = ManuallyDrop::new(self);
+        zelf.0
+    }
+}
+
+prevent_drop_link!(Connection, PREVENT_DROP_link_strategy_false_positive_Connection);
+
+/// Stands for any call the optimizer cannot see through that may panic.
+#[inline(never)]
+fn send(id: u32) -> u32 {
+    if std::env::args().count() > 1 {
+        panic!("cannot send on connection {}", id);
+    }
+    id
+}
+
+fn main() {
+    let connection = Connection(1);
+    let sent = send(connection.0);
+    let id = connection.close();
+    std::process::exit((id + sent) as i32 - 2);
+}
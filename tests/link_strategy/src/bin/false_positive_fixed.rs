@@ -0,0 +1,37 @@
+//! Cookbook: a false positive of the link strategy, after the rewrite.
+//!
+//! This binary must build. Compared to `false_positive.rs` it copies what
+//! `send` needs out of the connection and closes it before calling `send`,
+//! so nothing owns a `Connection` while a panic can happen.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+struct Connection(u32);
+
+impl Connection {
+    fn close(self) -> u32 {
+        let zelf = ManuallyDrop::new(self);
+        zelf.0
+    }
+}
+
+prevent_drop_link!(Connection, PREVENT_DROP_link_strategy_false_positive_fixed_Connection);
+
+/// Stands for any call the optimizer cannot see through that may panic.
+#[inline(never)]
+fn send(id: u32) -> u32 {
+    if std::env::args().count() > 1 {
+        panic!("cannot send on connection {}", id);
+    }
+    id
+}
+
+fn main() {
+    let connection = Connection(1);
+    let id = connection.close();
+    let sent = send(id);
+    std::process::exit((id + sent) as i32 - 2);
+}
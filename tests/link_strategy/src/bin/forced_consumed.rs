@@ -0,0 +1,35 @@
+//! Link test for the link strategy forced by `PREVENT_DROP_FORCE_STRATEGY`.
+//!
+//! This binary must build. `tests/link_strategy.rs` builds it with the
+//! link strategy forced over the panic feature, to make sure every
+//! argument of `prevent_drop!` reaches the link strategy.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+struct Never<T>(T);
+
+prevent_drop!(
+    Never<T>,
+    PREVENT_DROP_link_strategy_forced_consumed_Never,
+    inline = never,
+    generics = [T]
+);
+
+struct Always<T>(T);
+
+prevent_drop!(
+    Always<T>,
+    PREVENT_DROP_link_strategy_forced_consumed_Always,
+    inline = always,
+    generics = [T],
+    "Leaked an Always."
+);
+
+fn main() {
+    let a = ManuallyDrop::new(Never(1)).0;
+    let b = ManuallyDrop::new(Always(2)).0;
+    std::process::exit(a + b - 3);
+}
@@ -0,0 +1,21 @@
+//! Link test for the link strategy forced by `PREVENT_DROP_FORCE_STRATEGY`.
+//!
+//! This binary must fail to link. `tests/link_strategy.rs` builds it with
+//! the link strategy forced over the panic feature and checks that the
+//! linker error names the label.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Never<T>(T);
+
+prevent_drop!(
+    Never<T>,
+    PREVENT_DROP_link_strategy_forced_dropped_Never,
+    inline = never,
+    generics = [T]
+);
+
+fn main() {
+    let _n = Never(1);
+}
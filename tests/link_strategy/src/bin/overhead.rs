@@ -0,0 +1,49 @@
+//! Overhead test for the link strategy.
+//!
+//! `tests/link_strategy.rs` compares the size of the two exported
+//! functions, which consume a value with and without a guard. The
+//! `Drop` implementation of the link strategy is never generated for a
+//! consumed value, so they compile to the same instructions.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+struct Baseline(u64);
+
+impl Baseline {
+    fn close(self) -> u64 {
+        let zelf = ManuallyDrop::new(self);
+        zelf.0
+    }
+}
+
+struct Linked(u64);
+
+impl Linked {
+    fn close(self) -> u64 {
+        let zelf = ManuallyDrop::new(self);
+        zelf.0
+    }
+}
+
+prevent_drop_link!(Linked, PREVENT_DROP_link_strategy_overhead_Linked);
+
+#[no_mangle]
+#[inline(never)]
+pub fn link_strategy_overhead_baseline(value: u64) -> u64 {
+    Baseline(value).close() * 3
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn link_strategy_overhead_linked(value: u64) -> u64 {
+    Linked(value).close() * 3
+}
+
+fn main() {
+    let value = std::env::args().count() as u64;
+    let total = link_strategy_overhead_baseline(value) + link_strategy_overhead_linked(value);
+    std::process::exit((total != 6 * value) as i32);
+}
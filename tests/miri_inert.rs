@@ -0,0 +1,32 @@
+mod common;
+
+use std::process::Output;
+
+fn run(subcommand: &[&str], variant: &str, rustflags: Option<&str>) -> Output {
+    let mut command = common::cargo(subcommand, "miri_inert", variant);
+    if let Some(rustflags) = rustflags {
+        command.env("RUSTFLAGS", rustflags);
+    }
+    command.output().unwrap()
+}
+
+fn assert_inert(output: &Output) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "expected the guards to stay inert:\n{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
+
+// `cargo miri` builds every crate with `--cfg miri`, so this checks the
+// expansion without needing Miri itself.
+#[test]
+fn miri_inert_guards_do_nothing_with_cfg_miri() {
+    assert_inert(&run(&["run"], "cfg_miri", Some("--cfg miri")));
+}
+
+// Run with `cargo test --test miri_inert -- --ignored` where Miri is
+// installed.
+#[test]
+#[ignore = "needs `cargo miri`"]
+fn miri_inert_guards_do_nothing_under_miri() {
+    assert_inert(&run(&["miri", "run"], "miri", None));
+}
@@ -0,0 +1,27 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Default;
+prevent_drop!(Default, miri_inert_Default);
+
+struct Linked;
+prevent_drop!(Linked, miri_inert_Linked, strategy = link);
+
+struct Aborted;
+prevent_drop!(Aborted, miri_inert_Aborted, strategy = abort);
+
+struct Panicked<T>(T);
+prevent_drop!(Panicked<T>, miri_inert_Panicked, strategy = panic, generics = [T]);
+
+// Only meant to run under Miri, or with `--cfg miri` like `cargo miri`
+// passes it.
+fn main() {
+    assert_eq!(prevent_drop::STRATEGY, prevent_drop::Strategy::DryRun);
+
+    drop(Default);
+    drop(Linked);
+    drop(Aborted);
+    drop(Panicked(1u8));
+
+    println!("ok");
+}
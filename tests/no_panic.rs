@@ -0,0 +1,152 @@
+//! The abort strategy must not introduce a potential panic, so it can be
+//! used from `#[no_panic]` code. These tests drop guarded values inside
+//! `catch_unwind` and check that the drop aborts or returns, but never
+//! unwinds. The `tests/no_panic` crate checks the same at compile time.
+
+#[macro_use]
+extern crate prevent_drop;
+
+mod common;
+
+use prevent_drop::allow_drop;
+use std::env;
+use std::panic;
+use std::path::PathBuf;
+use std::process::Command;
+
+const CHILD: &str = "PREVENT_DROP_NO_PANIC_CHILD";
+
+struct Aborted;
+
+prevent_drop_abort!(Aborted, prevent_drop_no_panic_Aborted);
+
+struct Hooked;
+
+fn hook() {
+    eprintln!("hook ran");
+}
+
+prevent_drop_abort!(Hooked, prevent_drop_no_panic_Hooked, "Hooked was dropped.", hook);
+
+#[cfg(all(feature = "never_panic", any(feature = "panic", feature = "auto_fallback")))]
+struct Defaulted;
+
+#[cfg(all(feature = "never_panic", any(feature = "panic", feature = "auto_fallback")))]
+prevent_drop!(Defaulted, prevent_drop_no_panic_Defaulted);
+
+/// Drop the value of `case` inside `catch_unwind` and report it if the
+/// drop unwound.
+fn drop_caught(case: &str) {
+    let result = panic::catch_unwind(|| match case {
+        "abort" => ::std::mem::drop(Aborted),
+        "hook" => ::std::mem::drop(Hooked),
+        #[cfg(all(feature = "never_panic", any(feature = "panic", feature = "auto_fallback")))]
+        "default" => ::std::mem::drop(Defaulted),
+        _ => {}
+    });
+    if result.is_err() {
+        eprintln!("drop unwound");
+    }
+}
+
+// Only drops a value when spawned by `assert_aborts_without_panicking`,
+// since aborting takes the whole test binary down.
+#[test]
+fn no_panic_child() {
+    if let Ok(case) = env::var(CHILD) {
+        drop_caught(&case);
+    }
+}
+
+fn assert_aborts_without_panicking(case: &str, report: &str) {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "no_panic_child", "--nocapture"])
+        .env(CHILD, case)
+        .env_remove("RUST_BACKTRACE")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "dropping {} did not abort:\n{}", case, stderr);
+    // The test harness exits with 101 when a test panics.
+    assert_ne!(output.status.code(), Some(101), "unexpected stderr:\n{}", stderr);
+    assert!(!stderr.contains("panicked"), "unexpected stderr:\n{}", stderr);
+    assert!(!stderr.contains("drop unwound"), "unexpected stderr:\n{}", stderr);
+    assert!(stderr.contains(report), "unexpected stderr:\n{}", stderr);
+}
+
+#[test]
+fn abort_strategy_aborts_without_panicking() {
+    assert_aborts_without_panicking("abort", "prevent_drop: an instance of Aborted");
+}
+
+#[test]
+fn abort_hook_runs_without_panicking() {
+    assert_aborts_without_panicking("hook", "Hooked was dropped.\nhook ran");
+}
+
+#[cfg(all(feature = "never_panic", any(feature = "panic", feature = "auto_fallback")))]
+#[test]
+fn never_panic_makes_prevent_drop_abort_without_panicking() {
+    assert_aborts_without_panicking("default", "prevent_drop: an instance of Defaulted");
+}
+
+#[test]
+fn allowed_drops_return_without_panicking() {
+    let result = panic::catch_unwind(|| {
+        allow_drop(|| {
+            ::std::mem::drop(Aborted);
+            ::std::mem::drop(Hooked);
+            #[cfg(all(feature = "never_panic", any(feature = "panic", feature = "auto_fallback")))]
+            ::std::mem::drop(Defaulted);
+        })
+    });
+    assert!(result.is_ok());
+}
+
+/// Compile the `tests/no_panic` library with only the function of `case`
+/// to an object file with the given opt-level and return its path.
+fn build_object(case: &str, opt_level: &str) -> PathBuf {
+    let variant = format!("opt_level_{}", opt_level);
+    let object = common::target_dir("no_panic", &variant).join(format!("{}.o", case));
+    let output = common::cargo(&["rustc"], "no_panic", &variant)
+        .arg("--lib")
+        .arg("--")
+        .arg("-C")
+        .arg("codegen-units=1")
+        .arg("--cfg")
+        .arg(format!("no_panic_case=\"{}\"", case))
+        .arg(format!("--emit=obj={}", object.display()))
+        .env("CARGO_PROFILE_DEV_OPT_LEVEL", opt_level)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    object
+}
+
+/// Whether the function of `case` can unwind, which is the case if and
+/// only if its object still references the symbol of the `Unwinding`
+/// guard. Set `NM` to use another `nm`.
+fn unwinds(case: &str, opt_level: &str) -> bool {
+    let output = Command::new(env::var("NM").unwrap_or_else(|_| "nm".to_string()))
+        .arg("--undefined-only")
+        .arg(build_object(case, opt_level))
+        .output()
+        .expect("this test needs `nm`, set `NM` if it is not on the path");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        // Mach-O prefixes symbols with an underscore.
+        .any(|symbol| symbol.trim_start_matches('_') == "prevent_drop_no_panic_unwound")
+}
+
+#[cfg(unix)]
+#[test]
+fn abort_and_link_strategy_drops_cannot_unwind() {
+    for opt_level in ["1", "3"] {
+        assert!(unwinds("unwinds", opt_level), "the check missed a panic at opt-level {}", opt_level);
+        for case in ["aborted", "hooked", "defaulted", "linked"] {
+            assert!(!unwinds(case, opt_level), "{} can unwind at opt-level {}", case, opt_level);
+        }
+    }
+}
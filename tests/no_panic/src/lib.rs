@@ -0,0 +1,93 @@
+//! Expansion check for the abort strategy.
+//!
+//! Each exported function drops a guarded value while holding an
+//! `Unwinding` guard, the same trick `#[no_panic]` uses: the guard only
+//! runs, and only references `prevent_drop_no_panic_unwound`, if the drop
+//! can unwind. `tests/no_panic.rs` compiles this library to an object
+//! file and checks that the symbol is gone.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::marker::PhantomData;
+use std::mem;
+
+// Generic, so that `drop` is only instantiated where the guard is used.
+struct Unwinding<T>(PhantomData<T>);
+
+impl<T> Drop for Unwinding<T> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        extern "C" {
+            fn prevent_drop_no_panic_unwound();
+        }
+        unsafe { prevent_drop_no_panic_unwound() }
+    }
+}
+
+pub struct Aborted;
+
+prevent_drop_abort!(Aborted, no_panic_Aborted);
+
+pub struct Hooked;
+
+fn hook() {}
+
+prevent_drop_abort!(Hooked, no_panic_Hooked, "Hooked was dropped.", hook);
+
+pub struct Defaulted;
+
+prevent_drop!(Defaulted, no_panic_Defaulted);
+
+pub struct Linked;
+
+prevent_drop!(Linked, no_panic_Linked, strategy = link);
+
+impl Linked {
+    pub fn close(self) {
+        mem::forget(self);
+    }
+}
+
+#[cfg(no_panic_case = "aborted")]
+#[no_mangle]
+pub fn no_panic_drop_aborted(value: Aborted) {
+    let guard = Unwinding::<()>(PhantomData);
+    drop(value);
+    mem::forget(guard);
+}
+
+#[cfg(no_panic_case = "hooked")]
+#[no_mangle]
+pub fn no_panic_drop_hooked(value: Hooked) {
+    let guard = Unwinding::<()>(PhantomData);
+    drop(value);
+    mem::forget(guard);
+}
+
+#[cfg(no_panic_case = "defaulted")]
+#[no_mangle]
+pub fn no_panic_drop_defaulted(value: Defaulted) {
+    let guard = Unwinding::<()>(PhantomData);
+    drop(value);
+    mem::forget(guard);
+}
+
+#[cfg(no_panic_case = "linked")]
+#[no_mangle]
+pub fn no_panic_close_linked(value: Linked) {
+    let guard = Unwinding::<()>(PhantomData);
+    value.close();
+    mem::forget(guard);
+}
+
+// Makes sure the check can fail.
+#[cfg(no_panic_case = "unwinds")]
+#[no_mangle]
+pub fn no_panic_unwinds(value: u8) {
+    let guard = Unwinding::<()>(PhantomData);
+    if value == 0 {
+        panic!("zero");
+    }
+    mem::forget(guard);
+}
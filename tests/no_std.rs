@@ -0,0 +1,51 @@
+//! Builds the `tests/no_std` crate, which depends on `prevent_drop` without
+//! its default `std` feature. On the host `std` is still around, so the
+//! build uses a bare-metal target when one is installed, where anything
+//! that needs `std` fails to link or resolve.
+
+mod common;
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Targets without `std`, in order of preference.
+const BARE_METAL_TARGETS: &[&str] = &[
+    "thumbv7em-none-eabihf",
+    "thumbv7m-none-eabi",
+    "thumbv6m-none-eabi",
+    "riscv32imac-unknown-none-elf",
+    "x86_64-unknown-none",
+    "aarch64-unknown-none",
+];
+
+/// The first bare-metal target whose standard library is installed in the
+/// sysroot of `rustc`. Set `RUSTC` to use another `rustc`.
+fn bare_metal_target() -> Option<&'static str> {
+    let output = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .args(["--print", "sysroot"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let rustlib = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+        .join("lib")
+        .join("rustlib");
+    BARE_METAL_TARGETS
+        .iter()
+        .copied()
+        .find(|target| rustlib.join(target).is_dir())
+}
+
+#[test]
+fn builds_without_std() {
+    let target = bare_metal_target();
+    let mut command = common::cargo(&["build"], "no_std", target.unwrap_or("host"));
+    command.arg("--lib");
+    if let Some(target) = target {
+        command.arg("--target").arg(target);
+    }
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
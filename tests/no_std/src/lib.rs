@@ -0,0 +1,34 @@
+//! Compile test for `prevent_drop` in a `#![no_std]` crate.
+//!
+//! `tests/no_std.rs` builds it, for a bare-metal target when one is installed.
+
+#![no_std]
+
+#[macro_use]
+extern crate prevent_drop;
+
+use core::mem::ManuallyDrop;
+
+pub struct Linked;
+
+impl Linked {
+    pub fn drop(self) {
+        let _self = ManuallyDrop::new(self);
+    }
+}
+
+prevent_drop_link!(Linked, prevent_drop_no_std_Linked);
+
+pub struct Aborted;
+
+impl Aborted {
+    pub fn drop(self) {
+        let _self = ManuallyDrop::new(self);
+    }
+}
+
+prevent_drop_abort!(Aborted, prevent_drop_no_std_Aborted);
+
+pub struct Panicked<T>(pub T);
+
+prevent_drop_panic!(Panicked<T>, prevent_drop_no_std_Panicked, generics = [T]);
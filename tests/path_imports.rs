@@ -0,0 +1,159 @@
+//! The macros work when imported by path instead of with
+//! `#[macro_use]`, because they refer to each other through `$crate`.
+
+extern crate prevent_drop;
+
+use std::mem::ManuallyDrop;
+
+mod guarded {
+    use prevent_drop::{
+        assert_prevent_drop, prevent_drop, prevent_drop_abort, prevent_drop_callback,
+        prevent_drop_const, prevent_drop_consume, prevent_drop_count, prevent_drop_debug_only,
+        prevent_drop_eprintln, prevent_drop_link, prevent_drop_once, prevent_drop_panic,
+        prevent_drop_static_assert,
+    };
+    use std::mem::ManuallyDrop;
+
+    pub struct Default;
+    prevent_drop!(Default, path_imports_Default);
+
+    pub struct Strategy<T>(pub T);
+    prevent_drop!(
+        Strategy<T>,
+        path_imports_Strategy,
+        defuse = defuse,
+        strategy = panic,
+        generics = [T]
+    );
+
+    pub struct Selected;
+    prevent_drop!(
+        Selected,
+        path_imports_Selected,
+        strategy = cfg { unix => abort, _ => panic }
+    );
+
+    pub struct Linked;
+    prevent_drop_link!(Linked, path_imports_Linked);
+
+    pub struct Aborted;
+    prevent_drop_abort!(Aborted, path_imports_Aborted, "Aborted was dropped.");
+
+    pub struct Panicked;
+    prevent_drop_panic!(Panicked, path_imports_Panicked);
+
+    pub struct Printed;
+    prevent_drop_eprintln!(Printed, path_imports_Printed);
+
+    fn callback() {}
+
+    pub struct Called;
+    prevent_drop_callback!(Called, path_imports_Called, callback);
+
+    pub struct Counted;
+    prevent_drop_count!(Counted, path_imports_Counted);
+
+    pub struct Once;
+    prevent_drop_once!(Once, path_imports_Once);
+
+    pub struct Constant;
+    prevent_drop_const!(Constant);
+
+    pub struct Debug;
+    prevent_drop_debug_only!(Debug, path_imports_Debug, strategy = abort);
+
+    pub struct Marker;
+    prevent_drop_static_assert!(Marker, path_imports_Marker, strategy = panic);
+
+    pub struct Consumed(pub u32);
+    prevent_drop_panic!(Consumed, path_imports_Consumed);
+
+    fn close(consumed: ManuallyDrop<Consumed>) -> u32 {
+        consumed.0
+    }
+
+    prevent_drop_consume!(Consumed, fn close() -> u32 = close);
+
+    assert_prevent_drop!(
+        Default,
+        Strategy<u8>,
+        Selected,
+        Linked,
+        Aborted,
+        Panicked,
+        Printed,
+        Called,
+        Counted,
+        Once,
+        Constant,
+        Debug,
+        Marker,
+        Consumed,
+    );
+}
+
+use guarded::*;
+
+#[test]
+fn path_imported_macros_guard_their_types() {
+    let _ = ManuallyDrop::new(Default);
+    let _ = ManuallyDrop::new(Selected);
+    let _ = ManuallyDrop::new(Linked);
+    let _ = ManuallyDrop::new(Aborted);
+    let _ = ManuallyDrop::new(Printed);
+    let _ = ManuallyDrop::new(Called);
+    let _ = ManuallyDrop::new(Counted);
+    let _ = ManuallyDrop::new(Once);
+    let _ = ManuallyDrop::new(Constant);
+    let _ = ManuallyDrop::new(Debug);
+    let _ = ManuallyDrop::new(Marker);
+    let _ = Strategy(1u8).defuse();
+    assert_eq!(Consumed(3).close(), 3);
+}
+
+#[test]
+#[should_panic(expected = "Forgot to explicitly drop an instance of Panicked.")]
+fn path_imported_panic_strategy_panics() {
+    drop(Panicked);
+}
+
+#[cfg(feature = "log")]
+mod log_strategy {
+    use prevent_drop::prevent_drop_log;
+
+    pub struct Logged;
+    prevent_drop_log!(Logged, path_imports_Logged);
+
+    #[test]
+    fn path_imported_log_strategy_compiles() {
+        let _ = ::std::mem::ManuallyDrop::new(Logged);
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod trace_strategy {
+    use prevent_drop::prevent_drop_trace;
+
+    pub struct Traced;
+    prevent_drop_trace!(Traced, path_imports_Traced);
+
+    #[test]
+    fn path_imported_trace_strategy_compiles() {
+        let _ = ::std::mem::ManuallyDrop::new(Traced);
+    }
+}
+
+#[cfg(feature = "derive")]
+mod derive {
+    use prevent_drop::PreventDrop;
+
+    #[derive(PreventDrop)]
+    #[prevent_drop(strategy = "panic")]
+    pub struct Derived;
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Derived.")]
+    fn path_imported_derive_panics() {
+        drop(Derived);
+    }
+}
@@ -0,0 +1,62 @@
+//! Runs the raw abort strategy in a separate crate, since it needs the
+//! `libc` feature and aborting takes the whole process down.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::Once;
+
+// SIGABRT is 6 on every Unix that Rust supports.
+const SIGABRT: i32 = 6;
+
+fn binary() -> PathBuf {
+    static BUILD: Once = Once::new();
+    BUILD.call_once(|| {
+        common::build_binary("raw_abort", "default", "prevent_drop_raw_abort");
+    });
+    common::target_dir("raw_abort", "default").join("debug/prevent_drop_raw_abort")
+}
+
+fn run(case: &str) -> Output {
+    Command::new(binary()).arg(case).output().unwrap()
+}
+
+fn assert_aborts(case: &str, expected: &str) {
+    let output = run(case);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.signal(), Some(SIGABRT), "dropping {} did not abort:\n{}", case, stderr);
+    assert!(stderr.starts_with(expected), "unexpected stderr:\n{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+}
+
+#[test]
+fn forgotten_values_do_not_abort() {
+    let output = run("forget");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
+
+#[test]
+fn dropping_writes_the_message_and_aborts() {
+    assert_aborts("drop", "Leaked a Resource.\n");
+    assert_aborts("default", "prevent_drop: an instance of Defaulted (defined at src/main.rs:");
+}
+
+#[test]
+fn dropping_in_a_signal_handler_writes_the_message_and_aborts() {
+    assert_aborts("signal", "Leaked a Resource.\n");
+}
+
+#[test]
+fn allow_drop_does_not_silence_the_raw_abort_strategy() {
+    assert_aborts("allowed", "Leaked a Resource.\n");
+}
+
+#[test]
+fn exported_labels_can_be_called_by_name() {
+    assert_aborts("exported", "Exported was dropped.\n");
+}
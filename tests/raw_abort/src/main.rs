@@ -0,0 +1,45 @@
+#[macro_use]
+extern crate prevent_drop;
+extern crate libc;
+
+use std::env;
+use std::mem::ManuallyDrop;
+
+struct Resource;
+prevent_drop_raw_abort!(Resource, raw_abort_Resource, "Leaked a Resource.");
+
+struct Defaulted;
+prevent_drop_raw_abort!(Defaulted, raw_abort_Defaulted);
+
+struct Exported;
+prevent_drop_raw_abort!(Exported, raw_abort_Exported, no_mangle, "Exported was dropped.");
+
+mod symbol {
+    extern "Rust" {
+        pub fn raw_abort_Exported();
+    }
+}
+
+extern "C" fn on_signal(_: libc::c_int) {
+    drop(Resource);
+}
+
+fn main() {
+    match env::args().nth(1).as_ref().map(String::as_str) {
+        Some("drop") => drop(Resource),
+        Some("default") => drop(Defaulted),
+        Some("allowed") => prevent_drop::allow_drop(|| drop(Resource)),
+        Some("exported") => unsafe { symbol::raw_abort_Exported() },
+        Some("signal") => unsafe {
+            let handler: extern "C" fn(libc::c_int) = on_signal;
+            libc::signal(libc::SIGUSR1, handler as libc::sighandler_t);
+            libc::raise(libc::SIGUSR1);
+        },
+        _ => {
+            let _ = ManuallyDrop::new(Resource);
+            let _ = ManuallyDrop::new(Defaulted);
+            let _ = ManuallyDrop::new(Exported);
+        }
+    }
+    println!("ok");
+}
@@ -0,0 +1,46 @@
+//! Runs the guards that are only checked with `debug_assertions` in a
+//! separate crate, so they can be built with and without `--release`.
+
+mod common;
+
+use std::process::Output;
+
+fn cargo(subcommand: &str, bin: &str, profile: &[&str], variant: &str) -> Output {
+    common::cargo(&[subcommand], "release", variant)
+        .args(profile)
+        .arg("--bin")
+        .arg(bin)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn debug_only_panics_with_debug_assertions() {
+    let output = cargo("run", "debug_only", &[], "debug");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(101), "expected a panic:\n{}", stderr);
+    assert!(stderr.contains("Leaked a Buffer."), "unexpected stderr:\n{}", stderr);
+}
+
+#[test]
+fn debug_only_does_nothing_without_debug_assertions() {
+    let output = cargo("run", "debug_only", &["--release"], "release");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "expected the guards to stay inert:\n{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
+
+// Dropping is undefined behavior without `debug_assertions`, so the
+// binary is only built.
+#[test]
+fn assert_unreachable_builds_with_and_without_debug_assertions() {
+    for (profile, variant) in [(&[][..], "debug"), (&["--release"][..], "release")] {
+        let output = cargo("build", "assert_unreachable", profile, variant);
+        assert!(
+            output.status.success(),
+            "expected the {} build to succeed:\n{}",
+            variant,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Compile test for `prevent_drop_assert_unreachable!`.
+//!
+//! `tests/release.rs` only builds this binary, with and without
+//! `--release`. Running it would drop guarded values, which is
+//! undefined behavior without `debug_assertions`.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Buffer<T>(T);
+
+prevent_drop_assert_unreachable!(
+    unsafe Buffer<T>,
+    release_unreachable_Buffer,
+    inline = never,
+    message_fn = release_unreachable_Buffer_message,
+    generics = [T],
+    "Leaked a Buffer."
+);
+
+struct Described<T>(T);
+
+prevent_drop_assert_unreachable!(
+    unsafe Described<T>,
+    release_unreachable_Described,
+    generics = [T],
+    |this| format!("Leaked a Described of {} bytes.", std::mem::size_of_val(&this.0))
+);
+
+fn main() {
+    drop(Buffer(1u8));
+    drop(Described(2u16));
+    release_unreachable_Buffer();
+    release_unreachable_Described(&"Leaked a Described.");
+    let mut message = String::new();
+    release_unreachable_Buffer_message(&mut message).unwrap();
+}
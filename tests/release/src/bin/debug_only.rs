@@ -0,0 +1,50 @@
+//! Drops values guarded by `prevent_drop_debug_only!`.
+//!
+//! `tests/release.rs` runs this binary. With `debug_assertions` the first
+//! drop panics, without them every drop and every call to a label or a
+//! message function does nothing.
+
+#[macro_use]
+extern crate prevent_drop;
+
+struct Buffer<T>(T);
+
+prevent_drop_debug_only!(
+    Buffer<T>,
+    release_Buffer,
+    inline = never,
+    generics = [T],
+    "Leaked a Buffer."
+);
+
+struct Aborting<T>(T);
+
+prevent_drop_debug_only!(
+    Aborting<T>,
+    release_Aborting,
+    strategy = abort,
+    message_fn = release_Aborting_message,
+    generics = [T]
+);
+
+struct Described<T>(T);
+
+prevent_drop_debug_only!(
+    Described<T>,
+    release_Described,
+    generics = [T],
+    |this| format!("Leaked a Described of {} bytes.", std::mem::size_of_val(&this.0))
+);
+
+fn main() {
+    drop(Buffer(1u8));
+    drop(Aborting(2u16));
+    drop(Described(3u32));
+    release_Buffer();
+    release_Aborting();
+    release_Described(&"Leaked a Described.");
+    let mut message = String::new();
+    release_Aborting_message(&mut message).unwrap();
+    assert_eq!(message, "");
+    println!("ok");
+}
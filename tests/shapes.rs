@@ -0,0 +1,142 @@
+//! Every strategy behaves the same for unit structs, tuple structs and
+//! enums, including shapes with fields that need dropping themselves.
+
+#[macro_use]
+extern crate prevent_drop;
+
+use std::env;
+use std::process::Command;
+
+macro_rules! define_shapes {
+    () => {
+        use std::mem::ManuallyDrop;
+        use std::ptr;
+
+        pub struct Unit;
+
+        impl Unit {
+            pub fn consume(self) {
+                let _zelf = ManuallyDrop::new(self);
+            }
+        }
+
+        pub struct Tuple(pub String, pub u32);
+
+        impl Tuple {
+            pub fn consume(self) -> String {
+                let zelf = ManuallyDrop::new(self);
+                unsafe { ptr::read(&zelf.0) }
+            }
+        }
+
+        pub enum Enum {
+            Empty,
+            Named { name: String },
+            Tuple(String, u32),
+        }
+
+        impl Enum {
+            pub fn consume(self) -> Option<String> {
+                let zelf = ManuallyDrop::new(self);
+                match *zelf {
+                    Enum::Empty => None,
+                    Enum::Named { ref name } => Some(unsafe { ptr::read(name) }),
+                    Enum::Tuple(ref value, count) => {
+                        let value = unsafe { ptr::read(value) };
+                        Some(format!("{}{}", value, count))
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn consume_unit() {
+            Unit.consume();
+        }
+
+        #[test]
+        fn consume_tuple() {
+            let x = Tuple(String::from("tuple"), 1);
+            assert_eq!(x.1, 1);
+            assert_eq!(x.consume(), "tuple");
+        }
+
+        #[test]
+        fn consume_enum() {
+            assert_eq!(Enum::Empty.consume(), None);
+            let named = Enum::Named { name: String::from("named") };
+            assert_eq!(named.consume().as_ref().map(String::as_str), Some("named"));
+            let tuple = Enum::Tuple(String::from("tuple"), 2);
+            assert_eq!(tuple.consume().as_ref().map(String::as_str), Some("tuple2"));
+        }
+    };
+}
+
+mod link {
+    define_shapes!();
+
+    prevent_drop_link!(Unit, prevent_drop_shapes_link_Unit);
+    prevent_drop_link!(Tuple, prevent_drop_shapes_link_Tuple);
+    prevent_drop_link!(Enum, prevent_drop_shapes_link_Enum);
+}
+
+mod abort {
+    define_shapes!();
+
+    prevent_drop_abort!(Unit, prevent_drop_shapes_abort_Unit);
+    prevent_drop_abort!(Tuple, prevent_drop_shapes_abort_Tuple);
+    prevent_drop_abort!(Enum, prevent_drop_shapes_abort_Enum);
+}
+
+mod panic {
+    define_shapes!();
+
+    prevent_drop_panic!(Unit, prevent_drop_shapes_panic_Unit);
+    prevent_drop_panic!(Tuple, prevent_drop_shapes_panic_Tuple);
+    prevent_drop_panic!(Enum, prevent_drop_shapes_panic_Enum);
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Unit.")]
+    fn drop_unit_panics() {
+        ::std::mem::drop(Unit);
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Tuple.")]
+    fn drop_tuple_panics() {
+        ::std::mem::drop(Tuple(String::from("tuple"), 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Forgot to explicitly drop an instance of Enum.")]
+    fn drop_enum_panics() {
+        ::std::mem::drop(Enum::Named { name: String::from("named") });
+    }
+}
+
+const CHILD: &str = "PREVENT_DROP_SHAPES_CHILD";
+
+// Only drops a value when spawned by `drop_aborts`, since aborting takes
+// the whole test binary down.
+#[test]
+fn abort_child() {
+    match env::var(CHILD).as_ref().map(String::as_str) {
+        Ok("unit") => ::std::mem::drop(abort::Unit),
+        Ok("tuple") => ::std::mem::drop(abort::Tuple(String::from("tuple"), 1)),
+        Ok("enum") => ::std::mem::drop(abort::Enum::Tuple(String::from("tuple"), 2)),
+        _ => {}
+    }
+}
+
+#[test]
+fn drop_aborts() {
+    for shape in &["unit", "tuple", "enum"] {
+        let status = Command::new(env::current_exe().unwrap())
+            .args(["--exact", "abort_child"])
+            .env(CHILD, shape)
+            .output()
+            .unwrap()
+            .status;
+        assert!(!status.success(), "dropping the {} shape did not abort", shape);
+    }
+}
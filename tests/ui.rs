@@ -0,0 +1,42 @@
+// With `dry_run` every guard expands to an empty `Drop` impl, so the
+// diagnostics differ from the snapshots.
+#![cfg(not(feature = "dry_run"))]
+
+extern crate trybuild;
+
+use std::env;
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/assert_prevent_drop_pass.rs");
+    t.compile_fail("tests/ui/duplicate_label.rs");
+    t.compile_fail("tests/ui/defuse_style_unknown.rs");
+    t.compile_fail("tests/ui/config_invalid.rs");
+    t.pass("tests/ui/static_assert_zst.rs");
+    t.compile_fail("tests/ui/static_assert_not_zst.rs");
+    t.compile_fail("tests/ui/where_clause_missing.rs");
+    t.compile_fail("tests/ui/wrap_conflicting_drop.rs");
+    t.pass("tests/ui/wrap_existing_drop.rs");
+    t.pass("tests/ui/token_consumed.rs");
+    t.compile_fail("tests/ui/token_forgotten.rs");
+    t.compile_fail("tests/ui/token_other_scope.rs");
+    t.compile_fail("tests/ui/token_smuggled.rs");
+    if cfg!(feature = "derive") {
+        t.compile_fail("tests/ui/attr_must_use.rs");
+        t.pass("tests/ui/attr_must_use_existing.rs");
+        t.compile_fail("tests/ui/derive_unknown_strategy.rs");
+        t.compile_fail("tests/ui/attr_unknown_option.rs");
+        t.compile_fail("tests/ui/attr_strategy_not_string.rs");
+        t.compile_fail("tests/ui/attr_message_unsupported.rs");
+        t.compile_fail("tests/ui/attr_on_fn.rs");
+    }
+    // The diagnostics list the implementations inside this crate, which
+    // depend on the strategy selected through the features or the
+    // environment.
+    if cfg!(not(any(feature = "abort", feature = "panic", feature = "auto_fallback")))
+        && env::var_os("PREVENT_DROP_FORCE_STRATEGY").is_none()
+    {
+        t.compile_fail("tests/ui/assert_prevent_drop_missing.rs");
+    }
+}
@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Unguarded;
+
+assert_prevent_drop!(Unguarded);
+
+fn main() {}
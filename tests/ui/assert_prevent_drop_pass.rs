@@ -0,0 +1,22 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Aborted;
+
+prevent_drop_abort!(Aborted, prevent_drop_Aborted);
+
+struct Panicked<T>(T);
+
+prevent_drop_panic!(Panicked<T>, prevent_drop_Panicked, generics = [T]);
+
+fn callback() {}
+
+struct Called;
+
+prevent_drop_callback!(Called, prevent_drop_Called, callback);
+
+assert_prevent_drop!(Aborted, Panicked<u8>, Called,);
+
+fn main() {
+    let _ = std::mem::ManuallyDrop::new(Panicked(0u8));
+}
@@ -0,0 +1,8 @@
+extern crate prevent_drop;
+
+use prevent_drop::attr::prevent_drop;
+
+#[prevent_drop(strategy = "link", message = "Leaked a Resource.")]
+struct Resource;
+
+fn main() {}
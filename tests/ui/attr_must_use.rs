@@ -0,0 +1,16 @@
+#![deny(unused_must_use)]
+
+extern crate prevent_drop;
+
+use prevent_drop::attr::prevent_drop;
+
+#[prevent_drop(strategy = "panic")]
+struct Resource;
+
+fn open() -> Resource {
+    Resource
+}
+
+fn main() {
+    open();
+}
@@ -0,0 +1,19 @@
+#![deny(unused_attributes)]
+
+extern crate prevent_drop;
+
+use prevent_drop::attr::prevent_drop;
+
+#[must_use = "close the resource"]
+#[prevent_drop(strategy = "panic")]
+struct Resource;
+
+impl Resource {
+    fn close(self) {
+        let _this = ::std::mem::ManuallyDrop::new(self);
+    }
+}
+
+fn main() {
+    Resource.close();
+}
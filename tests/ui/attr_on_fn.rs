@@ -0,0 +1,10 @@
+extern crate prevent_drop;
+
+use prevent_drop::attr::prevent_drop;
+
+#[prevent_drop]
+fn resource() {}
+
+fn main() {
+    resource();
+}
@@ -0,0 +1,8 @@
+extern crate prevent_drop;
+
+use prevent_drop::attr::prevent_drop;
+
+#[prevent_drop(stratgy = "panic")]
+struct Resource;
+
+fn main() {}
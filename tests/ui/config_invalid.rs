@@ -0,0 +1,37 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Unknown;
+
+prevent_drop! {
+    ty: Unknown,
+    label: prevent_drop_Unknown,
+    stratgy: panic,
+}
+
+struct Repeated;
+
+prevent_drop! {
+    ty: Repeated,
+    label: prevent_drop_Repeated,
+    strategy: panic,
+    strategy: abort,
+}
+
+struct Unlabeled;
+
+prevent_drop! {
+    ty: Unlabeled,
+    strategy: panic,
+}
+
+struct Inlined;
+
+prevent_drop! {
+    ty: Inlined,
+    label: prevent_drop_Inlined,
+    strategy: eprintln,
+    inline: always,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+prevent_drop!(Resource, my_label, defuse = disarm, defuse_style = leak, strategy = panic);
+
+fn main() {}
@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate prevent_drop;
+
+#[derive(PreventDrop)]
+#[prevent_drop(strategy = "panicc")]
+struct Resource;
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct A;
+struct B;
+
+prevent_drop_panic!(A, my_label);
+prevent_drop_panic!(B, my_label);
+
+fn main() {}
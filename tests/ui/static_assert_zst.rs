@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Token;
+
+prevent_drop_static_assert!(Token, prevent_drop_Token, strategy = panic);
+
+fn main() {
+    let _ = std::mem::ManuallyDrop::new(Token);
+}
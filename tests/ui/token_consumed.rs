@@ -0,0 +1,16 @@
+extern crate prevent_drop;
+
+use prevent_drop::with_token;
+
+struct Connection(u32);
+
+impl Connection {
+    fn close(self) -> u32 {
+        self.0
+    }
+}
+
+fn main() {
+    let id = with_token(|token| token.wrap(Connection(1)).consume(Connection::close));
+    assert_eq!(id, 1);
+}
@@ -0,0 +1,12 @@
+extern crate prevent_drop;
+
+use prevent_drop::with_token;
+
+struct Connection(u32);
+
+fn main() {
+    with_token(|token| {
+        let connection = token.wrap(Connection(1));
+        std::mem::forget(connection);
+    });
+}
@@ -0,0 +1,14 @@
+extern crate prevent_drop;
+
+use prevent_drop::{Consumed, Linear, Token};
+
+struct Connection(u32);
+
+// Consuming a value guarded by another token does not prove that the
+// value of this token was consumed.
+fn close_other<'id, 'other>(token: Token<'id>, other: Linear<'other, Connection>) -> Consumed<'id, u32> {
+    std::mem::forget(token.wrap(Connection(1)));
+    other.consume(|connection| connection.0)
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+extern crate prevent_drop;
+
+use prevent_drop::with_token;
+
+struct Connection(u32);
+
+fn main() {
+    let mut smuggled = None;
+    // Keeping the guarded value past the call would let it be dropped
+    // after the closure returned. The closure never returns, so it does
+    // not need a proof.
+    let _: u32 = with_token(|token| {
+        smuggled = Some(token.wrap(Connection(1)));
+        panic!("never consumed")
+    });
+    drop(smuggled);
+}
@@ -0,0 +1,17 @@
+#[macro_use]
+extern crate prevent_drop;
+
+use std::hash::Hash;
+
+struct Set<T>
+where
+    T: Hash,
+{
+    items: Vec<T>,
+}
+
+prevent_drop!(Set<T>, prevent_drop_Set, strategy = panic, generics = [T]);
+
+fn main() {
+    let _ = std::mem::ManuallyDrop::new(Set::<u8> { items: Vec::new() });
+}
@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource;
+
+impl Drop for Resource {
+    fn drop(&mut self) {}
+}
+
+prevent_drop!(Resource, prevent_drop_Resource, strategy = panic);
+
+fn main() {}
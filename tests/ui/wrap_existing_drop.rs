@@ -0,0 +1,22 @@
+#[macro_use]
+extern crate prevent_drop;
+
+struct Resource {
+    open: bool,
+}
+
+prevent_drop_wrap!(
+    Resource,
+    prevent_drop_Resource,
+    |this| {
+        this.open = false;
+    },
+    strategy = panic
+);
+
+assert_prevent_drop!(Resource);
+
+fn main() {
+    let resource = std::mem::ManuallyDrop::new(Resource { open: true });
+    assert!(resource.open);
+}
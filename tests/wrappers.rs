@@ -0,0 +1,70 @@
+//! Runs the wrapper types in a crate that enables the panic feature, so
+//! the drops that reach their own guards panic in every build.
+
+mod common;
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::Once;
+
+fn binary() -> PathBuf {
+    static BUILD: Once = Once::new();
+    BUILD.call_once(|| {
+        common::build_binary("wrappers", "default", "prevent_drop_wrappers");
+    });
+    common::target_dir("wrappers", "default").join("debug/prevent_drop_wrappers")
+}
+
+fn run(case: &str) -> Output {
+    Command::new(binary()).arg(case).env_remove("RUST_BACKTRACE").output().unwrap()
+}
+
+fn assert_passes(case: &str) {
+    let output = run(case);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{} failed:\n{}", case, stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
+
+fn assert_panics(case: &str, message: &str) {
+    let output = run(case);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(101), "{} did not panic:\n{}", case, stderr);
+    assert!(stderr.contains(message), "unexpected stderr:\n{}", stderr);
+}
+
+#[test]
+fn drop_guard_stays_quiet_when_disarmed() {
+    assert_passes("guard_disarmed");
+    assert_passes("guard_question_mark_ok");
+}
+
+#[test]
+fn drop_guard_fires_on_early_return() {
+    assert_panics("guard_early_return", "Forgot to explicitly drop an instance of DropGuard<'a, T>.");
+}
+
+#[test]
+fn drop_guard_fires_on_question_mark() {
+    assert_panics("guard_question_mark_err", "Forgot to explicitly drop an instance of DropGuard<'a, T>.");
+}
+
+#[test]
+fn closing_a_struct_defuses_its_must_consume_field() {
+    assert_passes("must_consume_closed");
+}
+
+#[test]
+fn dropping_a_struct_fires_its_must_consume_field() {
+    assert_panics("must_consume_dropped", "Forgot to consume a field wrapped in MustConsume<T>.");
+}
+
+#[test]
+fn into_inner_defuses_the_prevent_drop_wrapper() {
+    assert_passes("prevent_drop_into_inner");
+}
+
+#[test]
+fn dropping_the_prevent_drop_wrapper_fires_its_guard() {
+    assert_panics("prevent_drop_dropped", "Forgot to explicitly drop an instance of PreventDrop<T>.");
+}
@@ -0,0 +1,65 @@
+extern crate prevent_drop;
+
+use prevent_drop::{DropGuard, MustConsume, PreventDrop};
+use std::env;
+
+fn process(slot: &mut Option<u32>, stop_early: bool) {
+    let mut guard = DropGuard::new(slot);
+    if stop_early {
+        return;
+    }
+    guard.take();
+    guard.disarm();
+}
+
+fn parse(slot: &mut Option<String>) -> Result<u32, std::num::ParseIntError> {
+    let guard = DropGuard::new(slot);
+    let value = guard.as_ref().map_or("", String::as_str).parse()?;
+    guard.disarm().take();
+    Ok(value)
+}
+
+struct Session {
+    socket: MustConsume<u32>,
+    sent: usize,
+}
+
+impl Session {
+    fn close(self) -> (u32, usize) {
+        (self.socket.defuse(), self.sent)
+    }
+}
+
+// Runs the case named by the first argument, which either prints "ok" or
+// panics with the message of a guard.
+fn main() {
+    let case = env::args().nth(1).unwrap();
+    match case.as_str() {
+        "guard_disarmed" => {
+            let mut slot = Some(1);
+            process(&mut slot, false);
+            assert_eq!(slot, None);
+        }
+        "guard_early_return" => process(&mut Some(1), true),
+        "guard_question_mark_ok" => {
+            let mut slot = Some(String::from("42"));
+            assert_eq!(parse(&mut slot), Ok(42));
+            assert_eq!(slot, None);
+        }
+        "guard_question_mark_err" => {
+            let _ = parse(&mut Some(String::from("not a number")));
+        }
+        "must_consume_closed" => {
+            let session = Session { socket: MustConsume::new(1), sent: 2 };
+            assert_eq!(session.close(), (1, 2));
+        }
+        "must_consume_dropped" => drop(Session { socket: MustConsume::new(1), sent: 0 }),
+        "prevent_drop_into_inner" => {
+            let guarded = PreventDrop::new(String::from("value"));
+            assert_eq!(guarded.into_inner(), "value");
+        }
+        "prevent_drop_dropped" => drop(PreventDrop::new(String::from("value"))),
+        _ => panic!("unknown case {}", case),
+    }
+    println!("ok");
+}